@@ -4,7 +4,8 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::distribution_model::{
-    AncEmissionRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    AncEmissionRateResponse, ConfigResponse, EmissionControllerStateResponse,
+    EmissionScheduleResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
 };
 
 fn main() {
@@ -15,7 +16,10 @@ fn main() {
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(AncEmissionRateResponse), &out_dir);
+    export_schema(&schema_for!(EmissionScheduleResponse), &out_dir);
+    export_schema(&schema_for!(EmissionControllerStateResponse), &out_dir);
 }
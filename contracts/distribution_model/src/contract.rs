@@ -2,13 +2,25 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
 
+use crate::controller::{
+    compute_controlled_emission_rate, execute_epoch_operations, query_emission_controller_state,
+    update_emission_controller,
+};
 use crate::error::ContractError;
-use crate::state::{read_config, store_config, Config};
+use crate::schedule::{
+    compute_scheduled_emission_rate, query_emission_schedule, update_emission_schedule,
+};
+use crate::state::{
+    read_config, read_contract_version, read_depositor_emission_rate, read_emission_controller,
+    read_emission_schedule, store_config, store_contract_version, store_depositor_emission_rate,
+    store_emission_schedule, Config, CONTRACT_VERSION,
+};
 
 use cosmwasm_bignumber::Decimal256;
 use moneymarket::common::optional_addr_validate;
 use moneymarket::distribution_model::{
-    AncEmissionRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    AncEmissionRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+    ScheduleKind,
 };
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -28,6 +40,7 @@ pub fn instantiate(
             decrement_multiplier: msg.decrement_multiplier,
         },
     )?;
+    store_contract_version(deps.storage, CONTRACT_VERSION)?;
 
     Ok(Response::default())
 }
@@ -58,6 +71,30 @@ pub fn execute(
                 decrement_multiplier,
             )
         }
+        ExecuteMsg::UpdateDepositorEmissionRate { emission_rate } => {
+            update_depositor_emission_rate(deps, info, emission_rate)
+        }
+        ExecuteMsg::UpdateEmissionSchedule { schedule } => {
+            update_emission_schedule(deps, info, schedule)
+        }
+        ExecuteMsg::UpdateEmissionController { controller } => {
+            update_emission_controller(deps, info, controller)
+        }
+        ExecuteMsg::ExecuteEpochOperations {
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            current_emission_rate,
+            block_height,
+        } => execute_epoch_operations(
+            deps,
+            info,
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            current_emission_rate,
+            block_height,
+        ),
     }
 }
 
@@ -99,8 +136,59 @@ pub fn update_config(
     Ok(Response::default())
 }
 
+pub fn update_depositor_emission_rate(
+    deps: DepsMut,
+    info: MessageInfo,
+    emission_rate: Decimal256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    store_depositor_emission_rate(deps.storage, &emission_rate)?;
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // Convert per block emission bounds into per second ones,
+    // only once when migrating from the legacy version
+    if let Some(seconds_per_block) = msg.seconds_per_block {
+        if seconds_per_block.is_zero() {
+            return Err(ContractError::InvalidSecondsPerBlock {});
+        }
+
+        if read_contract_version(deps.storage)?.is_some() {
+            return Err(ContractError::RateConversionNotAllowed {});
+        }
+
+        let mut config: Config = read_config(deps.storage)?;
+        config.emission_cap = config.emission_cap / seconds_per_block;
+        config.emission_floor = config.emission_floor / seconds_per_block;
+        store_config(deps.storage, &config)?;
+
+        if let Some(mut schedule) = read_emission_schedule(deps.storage)? {
+            match &mut schedule.kind {
+                ScheduleKind::Halving { initial_rate, .. } => {
+                    *initial_rate = *initial_rate / seconds_per_block;
+                }
+                ScheduleKind::Table { steps } => {
+                    for step in steps.iter_mut() {
+                        step.emission_rate = step.emission_rate / seconds_per_block;
+                    }
+                }
+            }
+            store_emission_schedule(deps.storage, &schedule)?;
+        }
+    }
+
+    store_contract_version(deps.storage, CONTRACT_VERSION)?;
+    Ok(Response::default())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::AncEmissionRate {
@@ -110,11 +198,19 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             current_emission_rate,
         } => to_binary(&query_anc_emission_rate(
             deps,
+            env,
             deposit_rate,
             target_deposit_rate,
             threshold_deposit_rate,
             current_emission_rate,
         )?),
+        QueryMsg::DepositorEmissionRate {} => to_binary(&AncEmissionRateResponse {
+            emission_rate: read_depositor_emission_rate(deps.storage)?,
+        }),
+        QueryMsg::EmissionSchedule { start_after, limit } => {
+            to_binary(&query_emission_schedule(deps, env, start_after, limit)?)
+        }
+        QueryMsg::EmissionControllerState {} => to_binary(&query_emission_controller_state(deps)?),
     }
 }
 
@@ -133,6 +229,7 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 
 fn query_anc_emission_rate(
     deps: Deps,
+    env: Env,
     deposit_rate: Decimal256,
     target_deposit_rate: Decimal256,
     threshold_deposit_rate: Decimal256,
@@ -140,6 +237,31 @@ fn query_anc_emission_rate(
 ) -> StdResult<AncEmissionRateResponse> {
     let config: Config = read_config(deps.storage)?;
 
+    // The schedule takes over the deposit rate feedback
+    if let Some(schedule) = read_emission_schedule(deps.storage)? {
+        return Ok(AncEmissionRateResponse {
+            emission_rate: compute_scheduled_emission_rate(
+                &config,
+                &schedule,
+                env.block.time.seconds(),
+            ),
+        });
+    }
+
+    if let Some(controller) = read_emission_controller(deps.storage)? {
+        return Ok(AncEmissionRateResponse {
+            emission_rate: compute_controlled_emission_rate(
+                deps,
+                &config,
+                &controller,
+                deposit_rate,
+                target_deposit_rate,
+                threshold_deposit_rate,
+                current_emission_rate,
+            )?,
+        });
+    }
+
     let half_dec = Decimal256::one() + Decimal256::one();
     let mid_rate = (threshold_deposit_rate + target_deposit_rate) / half_dec;
     let high_trigger = (mid_rate + target_deposit_rate) / half_dec;
@@ -0,0 +1,243 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{attr, Api, Deps, DepsMut, MessageInfo, Response, StdError, StdResult};
+
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_emission_controller, read_emission_controller_state, read_emission_schedule,
+    remove_emission_controller, remove_emission_controller_state, store_emission_controller,
+    store_emission_controller_state, Config, EmissionController, EmissionControllerState,
+};
+use moneymarket::distribution_model::{EmissionControllerConfig, EmissionControllerStateResponse};
+
+pub fn update_emission_controller(
+    deps: DepsMut,
+    info: MessageInfo,
+    controller: Option<EmissionControllerConfig>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match controller {
+        Some(controller) => {
+            let controller = controller_from_msg(deps.api, controller)?;
+            store_emission_controller(deps.storage, &controller)?;
+        }
+        None => remove_emission_controller(deps.storage),
+    }
+
+    // Start over from a zero integral
+    remove_emission_controller_state(deps.storage);
+
+    Ok(Response::default())
+}
+
+pub fn execute_epoch_operations(
+    deps: DepsMut,
+    info: MessageInfo,
+    deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+    threshold_deposit_rate: Decimal256,
+    current_emission_rate: Decimal256,
+    block_height: u64,
+) -> Result<Response, ContractError> {
+    let controller = match read_emission_controller(deps.storage)? {
+        Some(controller) => controller,
+        None => return Ok(Response::default()),
+    };
+
+    if deps.api.addr_canonicalize(info.sender.as_str())? != controller.market_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // The schedule decides the emission rate, so
+    // the integral must not wind up meanwhile
+    if read_emission_schedule(deps.storage)?.is_some() {
+        return Ok(Response::default());
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let prev_state = read_emission_controller_state(deps.storage)?;
+    let mut state = compute_emission_decision(
+        &config,
+        &controller,
+        &prev_state,
+        deposit_rate,
+        target_deposit_rate,
+        threshold_deposit_rate,
+        current_emission_rate,
+    );
+    state.last_executed_height = block_height;
+    store_emission_controller_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "execute_epoch_operations"),
+        attr("integral", state.integral.to_string()),
+        attr("emission_rate", state.emission_rate.to_string()),
+    ]))
+}
+
+/// Emission rate decided by the controller with the current
+/// integral, the same decision the next epoch operations store
+pub fn compute_controlled_emission_rate(
+    deps: Deps,
+    config: &Config,
+    controller: &EmissionController,
+    deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+    threshold_deposit_rate: Decimal256,
+    current_emission_rate: Decimal256,
+) -> StdResult<Decimal256> {
+    let prev_state = read_emission_controller_state(deps.storage)?;
+    Ok(compute_emission_decision(
+        config,
+        controller,
+        &prev_state,
+        deposit_rate,
+        target_deposit_rate,
+        threshold_deposit_rate,
+        current_emission_rate,
+    )
+    .emission_rate)
+}
+
+pub fn query_emission_controller_state(deps: Deps) -> StdResult<EmissionControllerStateResponse> {
+    let controller = match read_emission_controller(deps.storage)? {
+        Some(controller) => controller,
+        None => {
+            return Err(StdError::generic_err(
+                "Emission controller is not configured",
+            ))
+        }
+    };
+
+    let state = read_emission_controller_state(deps.storage)?;
+    Ok(EmissionControllerStateResponse {
+        controller: controller_to_msg(deps.api, controller)?,
+        mid_rate: state.mid_rate,
+        deposit_rate: state.deposit_rate,
+        error: state.error,
+        error_up: state.error_up,
+        integral: state.integral,
+        integral_up: state.integral_up,
+        proportional_term: state.proportional_term,
+        integral_term: state.integral_term,
+        step: state.step,
+        step_up: state.step_up,
+        saturated: state.saturated,
+        prev_emission_rate: state.prev_emission_rate,
+        emission_rate: state.emission_rate,
+        last_executed_height: state.last_executed_height,
+    })
+}
+
+/// The integral is bounded by integral_limit and does not
+/// accumulate while the emission bounds limit the rate
+fn compute_emission_decision(
+    config: &Config,
+    controller: &EmissionController,
+    prev_state: &EmissionControllerState,
+    deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+    threshold_deposit_rate: Decimal256,
+    current_emission_rate: Decimal256,
+) -> EmissionControllerState {
+    let half_dec = Decimal256::one() + Decimal256::one();
+    let mid_rate = (threshold_deposit_rate + target_deposit_rate) / half_dec;
+
+    // Deposit rates below the midpoint call for more emissions
+    let (error, error_up) = if mid_rate.is_zero() {
+        (Decimal256::zero(), false)
+    } else if deposit_rate < mid_rate {
+        ((mid_rate - deposit_rate) / mid_rate, true)
+    } else {
+        ((deposit_rate - mid_rate) / mid_rate, false)
+    };
+
+    let (integral, integral_up) = signed_add(
+        (prev_state.integral, prev_state.integral_up),
+        (error, error_up),
+    );
+    let integral = std::cmp::min(integral, controller.integral_limit);
+
+    let proportional_term = error * controller.proportional_gain;
+    let integral_term = integral * controller.integral_gain;
+    let (step, step_up) = signed_add((proportional_term, error_up), (integral_term, integral_up));
+    let step = std::cmp::min(step, controller.max_step);
+
+    let raw_emission_rate = if step_up {
+        current_emission_rate * (Decimal256::one() + step)
+    } else {
+        current_emission_rate * (Decimal256::one() - step)
+    };
+    let emission_rate = std::cmp::max(
+        std::cmp::min(raw_emission_rate, config.emission_cap),
+        config.emission_floor,
+    );
+    let saturated = emission_rate != raw_emission_rate;
+
+    let (integral, integral_up) = if saturated {
+        (prev_state.integral, prev_state.integral_up)
+    } else {
+        (integral, integral_up)
+    };
+
+    EmissionControllerState {
+        mid_rate,
+        deposit_rate,
+        error,
+        error_up,
+        integral,
+        integral_up,
+        proportional_term,
+        integral_term,
+        step,
+        step_up,
+        saturated,
+        prev_emission_rate: current_emission_rate,
+        emission_rate,
+        last_executed_height: prev_state.last_executed_height,
+    }
+}
+
+/// Sum of two values given as a magnitude and a sign
+fn signed_add(a: (Decimal256, bool), b: (Decimal256, bool)) -> (Decimal256, bool) {
+    if a.1 == b.1 {
+        (a.0 + b.0, a.1)
+    } else if a.0 >= b.0 {
+        (a.0 - b.0, a.1)
+    } else {
+        (b.0 - a.0, b.1)
+    }
+}
+
+fn controller_from_msg(
+    api: &dyn Api,
+    msg: EmissionControllerConfig,
+) -> Result<EmissionController, ContractError> {
+    if msg.max_step > Decimal256::one() {
+        return Err(ContractError::InvalidEmissionController {});
+    }
+
+    Ok(EmissionController {
+        market_contract: api.addr_canonicalize(&msg.market_contract)?,
+        proportional_gain: msg.proportional_gain,
+        integral_gain: msg.integral_gain,
+        integral_limit: msg.integral_limit,
+        max_step: msg.max_step,
+    })
+}
+
+fn controller_to_msg(
+    api: &dyn Api,
+    controller: EmissionController,
+) -> StdResult<EmissionControllerConfig> {
+    Ok(EmissionControllerConfig {
+        market_contract: api.addr_humanize(&controller.market_contract)?.to_string(),
+        proportional_gain: controller.proportional_gain,
+        integral_gain: controller.integral_gain,
+        integral_limit: controller.integral_limit,
+        max_step: controller.max_step,
+    })
+}
@@ -11,4 +11,16 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Max step of the emission controller must be at most 1")]
+    InvalidEmissionController {},
+
+    #[error("Invalid emission schedule; epoch period and halving epochs must be greater than 0 and table steps sorted from epoch 0")]
+    InvalidEmissionSchedule {},
+
+    #[error("Seconds per block must be greater than 0")]
+    InvalidSecondsPerBlock {},
+
+    #[error("Rates are only converted when migrating from the legacy version")]
+    RateConversionNotAllowed {},
 }
@@ -1,5 +1,7 @@
 pub mod contract;
+pub mod controller;
 pub mod error;
+pub mod schedule;
 pub mod state;
 
 #[cfg(test)]
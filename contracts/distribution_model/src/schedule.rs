@@ -0,0 +1,151 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult};
+
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_emission_schedule, remove_emission_schedule, store_emission_schedule, Config,
+};
+use moneymarket::distribution_model::{
+    EmissionSchedule, EmissionScheduleResponse, ScheduleKind, ScheduledEmission,
+};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn update_emission_schedule(
+    deps: DepsMut,
+    info: MessageInfo,
+    schedule: Option<EmissionSchedule>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match schedule {
+        Some(schedule) => {
+            assert_schedule(&schedule)?;
+            store_emission_schedule(deps.storage, &schedule)?;
+        }
+        None => remove_emission_schedule(deps.storage),
+    }
+
+    Ok(Response::default())
+}
+
+/// Emission rate of the schedule at `time`,
+/// clamped by the emission bounds if requested
+pub fn compute_scheduled_emission_rate(
+    config: &Config,
+    schedule: &EmissionSchedule,
+    time: u64,
+) -> Decimal256 {
+    clamp_emission_rate(
+        config,
+        schedule,
+        scheduled_emission_rate(schedule, schedule_epoch(schedule, time)),
+    )
+}
+
+pub fn query_emission_schedule(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EmissionScheduleResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let schedule = match read_emission_schedule(deps.storage)? {
+        Some(schedule) => schedule,
+        None => return Err(StdError::generic_err("Emission schedule is not set")),
+    };
+
+    let current_epoch = schedule_epoch(&schedule, env.block.time.seconds());
+    let start = start_after
+        .map(|epoch| epoch.saturating_add(1))
+        .unwrap_or(current_epoch);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
+
+    let epochs = (start..start.saturating_add(limit))
+        .map(|epoch| ScheduledEmission {
+            epoch,
+            start_time: schedule
+                .start_time
+                .saturating_add(epoch.saturating_mul(schedule.epoch_period)),
+            emission_rate: clamp_emission_rate(
+                &config,
+                &schedule,
+                scheduled_emission_rate(&schedule, epoch),
+            ),
+        })
+        .collect();
+
+    Ok(EmissionScheduleResponse {
+        schedule,
+        current_epoch,
+        epochs,
+    })
+}
+
+/// Epoch 0 also covers the time before start_time
+fn schedule_epoch(schedule: &EmissionSchedule, time: u64) -> u64 {
+    time.saturating_sub(schedule.start_time) / schedule.epoch_period
+}
+
+fn scheduled_emission_rate(schedule: &EmissionSchedule, epoch: u64) -> Decimal256 {
+    match &schedule.kind {
+        ScheduleKind::Halving {
+            initial_rate,
+            halving_epochs,
+        } => {
+            let two = Decimal256::from_uint256(2u64);
+            let mut halvings = epoch / halving_epochs;
+            let mut emission_rate = *initial_rate;
+            while halvings > 0 && !emission_rate.is_zero() {
+                emission_rate = emission_rate / two;
+                halvings -= 1;
+            }
+
+            emission_rate
+        }
+        ScheduleKind::Table { steps } => steps
+            .iter()
+            .rev()
+            .find(|step| step.start_epoch <= epoch)
+            .map(|step| step.emission_rate)
+            .unwrap_or_else(Decimal256::zero),
+    }
+}
+
+fn clamp_emission_rate(
+    config: &Config,
+    schedule: &EmissionSchedule,
+    emission_rate: Decimal256,
+) -> Decimal256 {
+    if !schedule.clamp {
+        return emission_rate;
+    }
+
+    std::cmp::max(
+        std::cmp::min(emission_rate, config.emission_cap),
+        config.emission_floor,
+    )
+}
+
+fn assert_schedule(schedule: &EmissionSchedule) -> Result<(), ContractError> {
+    let valid_kind = match &schedule.kind {
+        ScheduleKind::Halving { halving_epochs, .. } => *halving_epochs > 0,
+        ScheduleKind::Table { steps } => {
+            steps.first().map(|step| step.start_epoch) == Some(0)
+                && steps
+                    .windows(2)
+                    .all(|w| w[0].start_epoch < w[1].start_epoch)
+        }
+    };
+
+    if schedule.epoch_period == 0 || !valid_kind {
+        return Err(ContractError::InvalidEmissionSchedule {});
+    }
+
+    Ok(())
+}
@@ -4,8 +4,18 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read};
+use moneymarket::distribution_model::EmissionSchedule;
 
 static KEY_CONFIG: &[u8] = b"config";
+static KEY_DEPOSITOR_EMISSION_RATE: &[u8] = b"depositor_emission_rate";
+static KEY_EMISSION_SCHEDULE: &[u8] = b"emission_schedule";
+static KEY_EMISSION_CONTROLLER: &[u8] = b"emission_controller";
+static KEY_EMISSION_CONTROLLER_STATE: &[u8] = b"emission_controller_state";
+pub static KEY_CONTRACT_VERSION: &[u8] = b"contract_version";
+
+/// Version of the storage layout; contracts instantiated
+/// before it was stored still have block based rates
+pub const CONTRACT_VERSION: u64 = 1;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -16,6 +26,33 @@ pub struct Config {
     pub decrement_multiplier: Decimal256,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionController {
+    pub market_contract: CanonicalAddr,
+    pub proportional_gain: Decimal256,
+    pub integral_gain: Decimal256,
+    pub integral_limit: Decimal256,
+    pub max_step: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionControllerState {
+    pub mid_rate: Decimal256,
+    pub deposit_rate: Decimal256,
+    pub error: Decimal256,
+    pub error_up: bool,
+    pub integral: Decimal256,
+    pub integral_up: bool,
+    pub proportional_term: Decimal256,
+    pub integral_term: Decimal256,
+    pub step: Decimal256,
+    pub step_up: bool,
+    pub saturated: bool,
+    pub prev_emission_rate: Decimal256,
+    pub emission_rate: Decimal256,
+    pub last_executed_height: u64,
+}
+
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
     singleton(storage, KEY_CONFIG).save(config)
 }
@@ -23,3 +60,88 @@ pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()>
 pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
+
+pub fn store_contract_version(storage: &mut dyn Storage, version: u64) -> StdResult<()> {
+    singleton(storage, KEY_CONTRACT_VERSION).save(&version)
+}
+
+pub fn read_contract_version(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    singleton_read(storage, KEY_CONTRACT_VERSION).may_load()
+}
+
+pub fn store_depositor_emission_rate(
+    storage: &mut dyn Storage,
+    emission_rate: &Decimal256,
+) -> StdResult<()> {
+    singleton(storage, KEY_DEPOSITOR_EMISSION_RATE).save(emission_rate)
+}
+
+pub fn read_depositor_emission_rate(storage: &dyn Storage) -> StdResult<Decimal256> {
+    singleton_read(storage, KEY_DEPOSITOR_EMISSION_RATE)
+        .may_load()
+        .map(|emission_rate| emission_rate.unwrap_or_else(Decimal256::zero))
+}
+
+pub fn store_emission_schedule(
+    storage: &mut dyn Storage,
+    schedule: &EmissionSchedule,
+) -> StdResult<()> {
+    singleton(storage, KEY_EMISSION_SCHEDULE).save(schedule)
+}
+
+pub fn remove_emission_schedule(storage: &mut dyn Storage) {
+    singleton::<EmissionSchedule>(storage, KEY_EMISSION_SCHEDULE).remove()
+}
+
+pub fn read_emission_schedule(storage: &dyn Storage) -> StdResult<Option<EmissionSchedule>> {
+    singleton_read(storage, KEY_EMISSION_SCHEDULE).may_load()
+}
+
+pub fn store_emission_controller(
+    storage: &mut dyn Storage,
+    controller: &EmissionController,
+) -> StdResult<()> {
+    singleton(storage, KEY_EMISSION_CONTROLLER).save(controller)
+}
+
+pub fn remove_emission_controller(storage: &mut dyn Storage) {
+    singleton::<EmissionController>(storage, KEY_EMISSION_CONTROLLER).remove()
+}
+
+pub fn read_emission_controller(storage: &dyn Storage) -> StdResult<Option<EmissionController>> {
+    singleton_read(storage, KEY_EMISSION_CONTROLLER).may_load()
+}
+
+pub fn store_emission_controller_state(
+    storage: &mut dyn Storage,
+    state: &EmissionControllerState,
+) -> StdResult<()> {
+    singleton(storage, KEY_EMISSION_CONTROLLER_STATE).save(state)
+}
+
+pub fn remove_emission_controller_state(storage: &mut dyn Storage) {
+    singleton::<EmissionControllerState>(storage, KEY_EMISSION_CONTROLLER_STATE).remove()
+}
+
+pub fn read_emission_controller_state(storage: &dyn Storage) -> StdResult<EmissionControllerState> {
+    singleton_read(storage, KEY_EMISSION_CONTROLLER_STATE)
+        .may_load()
+        .map(|state| {
+            state.unwrap_or(EmissionControllerState {
+                mid_rate: Decimal256::zero(),
+                deposit_rate: Decimal256::zero(),
+                error: Decimal256::zero(),
+                error_up: false,
+                integral: Decimal256::zero(),
+                integral_up: false,
+                proportional_term: Decimal256::zero(),
+                integral_term: Decimal256::zero(),
+                step: Decimal256::zero(),
+                step_up: false,
+                saturated: false,
+                prev_emission_rate: Decimal256::zero(),
+                emission_rate: Decimal256::zero(),
+                last_executed_height: 0,
+            })
+        })
+}
@@ -1,11 +1,16 @@
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::error::ContractError;
+use crate::state::{read_config, KEY_CONTRACT_VERSION};
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::from_binary;
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_storage::singleton;
 use moneymarket::distribution_model::{
-    AncEmissionRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    AncEmissionRateResponse, ConfigResponse, EmissionControllerConfig,
+    EmissionControllerStateResponse, EmissionSchedule, EmissionScheduleResponse, ExecuteMsg,
+    InstantiateMsg, MigrateMsg, QueryMsg, ScheduleKind, ScheduleStep,
 };
+use std::str::FromStr;
 
 #[test]
 fn proper_initialization() {
@@ -161,3 +166,402 @@ fn proper_emission_rate() {
     let value: AncEmissionRateResponse = from_binary(&res).unwrap();
     assert_eq!("10", &value.emission_rate.to_string());
 }
+
+#[test]
+fn depositor_emission_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        emission_cap: Decimal256::from_uint256(100u64),
+        emission_floor: Decimal256::from_uint256(10u64),
+        increment_multiplier: Decimal256::percent(110),
+        decrement_multiplier: Decimal256::percent(90),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // zero until it is set
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::DepositorEmissionRate {},
+    )
+    .unwrap();
+    let value: AncEmissionRateResponse = from_binary(&res).unwrap();
+    assert_eq!(value.emission_rate, Decimal256::zero());
+
+    let msg = ExecuteMsg::UpdateDepositorEmissionRate {
+        emission_rate: Decimal256::from_uint256(20u64),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::DepositorEmissionRate {},
+    )
+    .unwrap();
+    let value: AncEmissionRateResponse = from_binary(&res).unwrap();
+    assert_eq!(value.emission_rate, Decimal256::from_uint256(20u64));
+}
+
+#[test]
+fn schedule_emission_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        emission_cap: Decimal256::from_uint256(100u64),
+        emission_floor: Decimal256::from_uint256(10u64),
+        increment_multiplier: Decimal256::percent(110),
+        decrement_multiplier: Decimal256::percent(90),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the current time is in epoch 2
+    let env = mock_env();
+    let start_time = env.block.time.seconds() - 250;
+    let schedule = EmissionSchedule {
+        start_time,
+        epoch_period: 100,
+        kind: ScheduleKind::Halving {
+            initial_rate: Decimal256::from_uint256(400u64),
+            halving_epochs: 1,
+        },
+        clamp: false,
+    };
+
+    let msg = ExecuteMsg::UpdateEmissionSchedule {
+        schedule: Some(schedule.clone()),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateEmissionSchedule {
+        schedule: Some(EmissionSchedule {
+            epoch_period: 0,
+            ..schedule.clone()
+        }),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidEmissionSchedule {}) => (),
+        _ => panic!("Must return invalid emission schedule error"),
+    }
+
+    let msg = ExecuteMsg::UpdateEmissionSchedule {
+        schedule: Some(schedule.clone()),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // the deposit rate is ignored; 400 halved twice
+    let query_msg = QueryMsg::AncEmissionRate {
+        deposit_rate: Decimal256::percent(5),
+        target_deposit_rate: Decimal256::percent(10),
+        threshold_deposit_rate: Decimal256::percent(5),
+        current_emission_rate: Decimal256::from_uint256(80u128),
+    };
+    let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+    let value: AncEmissionRateResponse = from_binary(&res).unwrap();
+    assert_eq!("100", &value.emission_rate.to_string());
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::EmissionSchedule {
+            start_after: None,
+            limit: Some(3),
+        },
+    )
+    .unwrap();
+    let value: EmissionScheduleResponse = from_binary(&res).unwrap();
+    assert_eq!(value.schedule, schedule);
+    assert_eq!(value.current_epoch, 2);
+    assert_eq!(
+        value
+            .epochs
+            .iter()
+            .map(|e| (e.epoch, e.start_time, e.emission_rate.to_string()))
+            .collect::<Vec<(u64, u64, String)>>(),
+        vec![
+            (2, start_time + 200, "100".to_string()),
+            (3, start_time + 300, "50".to_string()),
+            (4, start_time + 400, "25".to_string()),
+        ]
+    );
+
+    // the table rates are clamped by the emission cap and floor
+    let msg = ExecuteMsg::UpdateEmissionSchedule {
+        schedule: Some(EmissionSchedule {
+            kind: ScheduleKind::Table {
+                steps: vec![
+                    ScheduleStep {
+                        start_epoch: 0,
+                        emission_rate: Decimal256::from_uint256(200u64),
+                    },
+                    ScheduleStep {
+                        start_epoch: 3,
+                        emission_rate: Decimal256::from_uint256(5u64),
+                    },
+                ],
+            },
+            clamp: true,
+            ..schedule
+        }),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+    let value: AncEmissionRateResponse = from_binary(&res).unwrap();
+    assert_eq!("100", &value.emission_rate.to_string());
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::EmissionSchedule {
+            start_after: Some(2),
+            limit: Some(1),
+        },
+    )
+    .unwrap();
+    let value: EmissionScheduleResponse = from_binary(&res).unwrap();
+    assert_eq!(value.epochs.len(), 1);
+    assert_eq!(value.epochs[0].epoch, 3);
+    assert_eq!("10", &value.epochs[0].emission_rate.to_string());
+
+    // back to the multipliers
+    let msg = ExecuteMsg::UpdateEmissionSchedule { schedule: None };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+    let value: AncEmissionRateResponse = from_binary(&res).unwrap();
+    assert_eq!("88", &value.emission_rate.to_string());
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::EmissionSchedule {
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn controlled_emission_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        emission_cap: Decimal256::from_uint256(100u64),
+        emission_floor: Decimal256::from_uint256(10u64),
+        increment_multiplier: Decimal256::percent(110),
+        decrement_multiplier: Decimal256::percent(90),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let controller = EmissionControllerConfig {
+        market_contract: "market".to_string(),
+        proportional_gain: Decimal256::one(),
+        integral_gain: Decimal256::percent(50),
+        integral_limit: Decimal256::one(),
+        max_step: Decimal256::percent(50),
+    };
+
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateEmissionController {
+        controller: Some(EmissionControllerConfig {
+            max_step: Decimal256::percent(150),
+            ..controller.clone()
+        }),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidEmissionController {}) => (),
+        _ => panic!("Must return invalid emission controller error"),
+    }
+
+    let msg = ExecuteMsg::UpdateEmissionController {
+        controller: Some(controller.clone()),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // mid_rate = 0.075, error = (0.075 - 0.06) / 0.075 = 0.2
+    // step = 0.2 * 1 + 0.2 * 0.5 = 0.3
+    let query_msg = QueryMsg::AncEmissionRate {
+        deposit_rate: Decimal256::percent(6),
+        target_deposit_rate: Decimal256::percent(10),
+        threshold_deposit_rate: Decimal256::percent(5),
+        current_emission_rate: Decimal256::from_uint256(50u64),
+    };
+    let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    let value: AncEmissionRateResponse = from_binary(&res).unwrap();
+    assert_eq!("65", &value.emission_rate.to_string());
+
+    // only the market records the epoch, at
+    // the height in the market's rate unit
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::percent(6),
+        target_deposit_rate: Decimal256::percent(10),
+        threshold_deposit_rate: Decimal256::percent(5),
+        current_emission_rate: Decimal256::from_uint256(50u64),
+        block_height: mock_env().block.time.seconds(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("market", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EmissionControllerState {},
+    )
+    .unwrap();
+    let value: EmissionControllerStateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value,
+        EmissionControllerStateResponse {
+            controller: controller.clone(),
+            mid_rate: Decimal256::from_str("0.075").unwrap(),
+            deposit_rate: Decimal256::percent(6),
+            error: Decimal256::percent(20),
+            error_up: true,
+            integral: Decimal256::percent(20),
+            integral_up: true,
+            proportional_term: Decimal256::percent(20),
+            integral_term: Decimal256::percent(10),
+            step: Decimal256::percent(30),
+            step_up: true,
+            saturated: false,
+            prev_emission_rate: Decimal256::from_uint256(50u64),
+            emission_rate: Decimal256::from_uint256(65u64),
+            last_executed_height: mock_env().block.time.seconds(),
+        }
+    );
+
+    // the integral grows with the persisting error
+    // step = 0.2 * 1 + 0.4 * 0.5 = 0.4
+    let query_msg = QueryMsg::AncEmissionRate {
+        deposit_rate: Decimal256::percent(6),
+        target_deposit_rate: Decimal256::percent(10),
+        threshold_deposit_rate: Decimal256::percent(5),
+        current_emission_rate: Decimal256::from_uint256(65u64),
+    };
+    let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    let value: AncEmissionRateResponse = from_binary(&res).unwrap();
+    assert_eq!("91", &value.emission_rate.to_string());
+
+    // capped at the emission cap; the integral is held back
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::percent(6),
+        target_deposit_rate: Decimal256::percent(10),
+        threshold_deposit_rate: Decimal256::percent(5),
+        current_emission_rate: Decimal256::from_uint256(90u64),
+        block_height: mock_env().block.time.seconds(),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("market", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EmissionControllerState {},
+    )
+    .unwrap();
+    let value: EmissionControllerStateResponse = from_binary(&res).unwrap();
+    assert!(value.saturated);
+    assert_eq!(value.integral, Decimal256::percent(20));
+    assert_eq!(value.emission_rate, Decimal256::from_uint256(100u64));
+
+    // the integral stops at its limit and the step at max_step
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::zero(),
+        target_deposit_rate: Decimal256::percent(10),
+        threshold_deposit_rate: Decimal256::percent(5),
+        current_emission_rate: Decimal256::from_uint256(20u64),
+        block_height: mock_env().block.time.seconds(),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("market", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EmissionControllerState {},
+    )
+    .unwrap();
+    let value: EmissionControllerStateResponse = from_binary(&res).unwrap();
+    assert_eq!(value.integral, Decimal256::one());
+    assert_eq!(value.step, Decimal256::percent(50));
+    assert_eq!(value.emission_rate, Decimal256::from_uint256(30u64));
+}
+
+#[test]
+fn migrate_to_seconds() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        emission_cap: Decimal256::from_uint256(120u64),
+        emission_floor: Decimal256::from_uint256(12u64),
+        increment_multiplier: Decimal256::percent(110),
+        decrement_multiplier: Decimal256::percent(90),
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // a contract from before the stored version
+    singleton::<u64>(deps.as_mut().storage, KEY_CONTRACT_VERSION).remove();
+
+    let msg = MigrateMsg {
+        seconds_per_block: Some(Decimal256::from_uint256(6u64)),
+    };
+    let _res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+
+    let config = read_config(deps.as_ref().storage).unwrap();
+    assert_eq!(config.emission_cap, Decimal256::from_uint256(20u64));
+    assert_eq!(config.emission_floor, Decimal256::from_uint256(2u64));
+
+    // rates are not converted twice
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert_eq!(res, ContractError::RateConversionNotAllowed {});
+
+    // a later code upgrade keeps the config
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(read_config(deps.as_ref().storage).unwrap(), config);
+}
@@ -4,7 +4,8 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::interest_model::{
-    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    BorrowRateResponse, ConfigResponse, ControllerStateResponse, ExecuteMsg, InstantiateMsg,
+    MigrateMsg, QueryMsg, RateCurveResponse,
 };
 
 fn main() {
@@ -15,7 +16,10 @@ fn main() {
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
     export_schema(&schema_for!(BorrowRateResponse), &out_dir);
+    export_schema(&schema_for!(ControllerStateResponse), &out_dir);
+    export_schema(&schema_for!(RateCurveResponse), &out_dir);
 }
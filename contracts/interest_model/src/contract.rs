@@ -1,5 +1,13 @@
+use crate::controller::{
+    compute_utilization_ratio, controller_from_msg, controller_to_msg, execute_epoch_operations,
+    query_controller_state,
+};
+use crate::curve::{assert_curve, compute_borrow_rate, query_rate_curve};
 use crate::error::ContractError;
-use crate::state::{read_config, store_config, Config};
+use crate::state::{
+    read_config, read_contract_version, read_legacy_config, store_config, store_contract_version,
+    Config, CONTRACT_VERSION,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
@@ -8,7 +16,8 @@ use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
 use moneymarket::common::optional_addr_validate;
 use moneymarket::interest_model::{
-    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    BorrowRateResponse, ConfigResponse, ControllerConfig, Curve, ExecuteMsg, InstantiateMsg,
+    MigrateMsg, QueryMsg,
 };
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -17,15 +26,25 @@ pub fn instantiate(
     _env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    assert_curve(&msg.curve)?;
+
+    let controller = match msg.controller {
+        Some(controller) => Some(controller_from_msg(deps.api, controller)?),
+        None => None,
+    };
+
     store_config(
         deps.storage,
         &Config {
             owner: deps.api.addr_canonicalize(&msg.owner)?,
             base_rate: msg.base_rate,
             interest_multiplier: msg.interest_multiplier,
+            curve: msg.curve,
+            controller,
         },
     )?;
+    store_contract_version(deps.storage, CONTRACT_VERSION)?;
 
     Ok(Response::default())
 }
@@ -42,6 +61,8 @@ pub fn execute(
             owner,
             base_rate,
             interest_multiplier,
+            curve,
+            controller,
         } => {
             let api = deps.api;
             update_config(
@@ -50,8 +71,23 @@ pub fn execute(
                 optional_addr_validate(api, owner)?,
                 base_rate,
                 interest_multiplier,
+                curve,
+                controller,
             )
         }
+        ExecuteMsg::ExecuteEpochOperations {
+            market_balance,
+            total_liabilities,
+            total_reserves,
+            block_height,
+        } => execute_epoch_operations(
+            deps,
+            info,
+            market_balance,
+            total_liabilities,
+            total_reserves,
+            block_height,
+        ),
     }
 }
 
@@ -61,6 +97,8 @@ pub fn update_config(
     owner: Option<Addr>,
     base_rate: Option<Decimal256>,
     interest_multiplier: Option<Decimal256>,
+    curve: Option<Curve>,
+    controller: Option<ControllerConfig>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
@@ -79,7 +117,53 @@ pub fn update_config(
         config.interest_multiplier = interest_multiplier;
     }
 
+    if let Some(curve) = curve {
+        assert_curve(&curve)?;
+        config.curve = curve;
+    }
+
+    if let Some(controller) = controller {
+        config.controller = Some(controller_from_msg(deps.api, controller)?);
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    if msg.seconds_per_block == Some(Decimal256::zero()) {
+        return Err(ContractError::InvalidSecondsPerBlock {});
+    }
+
+    // The config is only rebuilt from the legacy layout
+    // once; later migrations keep every stored value
+    if read_contract_version(deps.storage)?.is_some() {
+        if msg.seconds_per_block.is_some() {
+            return Err(ContractError::RateConversionNotAllowed {});
+        }
+
+        store_contract_version(deps.storage, CONTRACT_VERSION)?;
+        return Ok(Response::default());
+    }
+
+    let legacy_config = read_legacy_config(deps.storage)?;
+    let mut config = Config {
+        owner: legacy_config.owner,
+        base_rate: legacy_config.base_rate,
+        interest_multiplier: legacy_config.interest_multiplier,
+        curve: Curve::Linear {},
+        controller: None,
+    };
+
+    // Convert per block rates into per second rates
+    if let Some(seconds_per_block) = msg.seconds_per_block {
+        config.base_rate = config.base_rate / seconds_per_block;
+        config.interest_multiplier = config.interest_multiplier / seconds_per_block;
+    }
+
     store_config(deps.storage, &config)?;
+    store_contract_version(deps.storage, CONTRACT_VERSION)?;
     Ok(Response::default())
 }
 
@@ -97,6 +181,8 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             total_liabilities,
             total_reserves,
         )?),
+        QueryMsg::ControllerState {} => to_binary(&query_controller_state(deps)?),
+        QueryMsg::RateCurve { points } => to_binary(&query_rate_curve(deps, points)?),
     }
 }
 
@@ -106,6 +192,11 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         owner: deps.api.addr_humanize(&state.owner)?.to_string(),
         base_rate: state.base_rate,
         interest_multiplier: state.interest_multiplier,
+        curve: state.curve,
+        controller: match state.controller {
+            Some(controller) => Some(controller_to_msg(deps.api, controller)?),
+            None => None,
+        },
     };
 
     Ok(resp)
@@ -118,18 +209,10 @@ fn query_borrow_rate(
     total_reserves: Decimal256,
 ) -> StdResult<BorrowRateResponse> {
     let config: Config = read_config(deps.storage)?;
-
-    // ignore decimal parts
-    let total_value_in_market =
-        Decimal256::from_uint256(market_balance) + total_liabilities - total_reserves;
-
-    let utilization_ratio = if total_value_in_market.is_zero() {
-        Decimal256::zero()
-    } else {
-        total_liabilities / total_value_in_market
-    };
+    let utilization_ratio =
+        compute_utilization_ratio(market_balance, total_liabilities, total_reserves);
 
     Ok(BorrowRateResponse {
-        rate: utilization_ratio * config.interest_multiplier + config.base_rate,
+        rate: compute_borrow_rate(&config, utilization_ratio),
     })
 }
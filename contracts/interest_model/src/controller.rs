@@ -0,0 +1,157 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{attr, Api, Deps, DepsMut, MessageInfo, Response, StdError, StdResult};
+
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_controller_state, store_config, store_controller_state, Controller,
+    ControllerState,
+};
+use moneymarket::interest_model::{ControllerConfig, ControllerStateResponse};
+
+pub fn execute_epoch_operations(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_balance: Uint256,
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+    block_height: u64,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+    let controller = match config.controller.clone() {
+        Some(controller) => controller,
+        None => return Ok(Response::default()),
+    };
+
+    if deps.api.addr_canonicalize(info.sender.as_str())? != controller.market_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // The first step is not throttled
+    let state = read_controller_state(deps.storage)?;
+    let next_height = state
+        .last_executed_height
+        .saturating_add(controller.epoch_period);
+    if state.last_executed_height != 0 && block_height < next_height {
+        return Err(ContractError::EpochPeriodNotElapsed(next_height));
+    }
+
+    let utilization = compute_utilization_ratio(market_balance, total_liabilities, total_reserves);
+    let (step, step_up) = compute_step(&controller, utilization);
+
+    let base_rate = if step_up {
+        config.base_rate * (Decimal256::one() + step)
+    } else {
+        config.base_rate * (Decimal256::one() - step)
+    };
+
+    config.base_rate = std::cmp::min(
+        std::cmp::max(base_rate, controller.min_base_rate),
+        controller.max_base_rate,
+    );
+    store_config(deps.storage, &config)?;
+
+    store_controller_state(
+        deps.storage,
+        &ControllerState {
+            utilization,
+            last_step: step,
+            last_step_up: step_up,
+            last_executed_height: block_height,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "execute_epoch_operations"),
+        attr("utilization", utilization.to_string()),
+        attr("base_rate", config.base_rate.to_string()),
+    ]))
+}
+
+pub fn query_controller_state(deps: Deps) -> StdResult<ControllerStateResponse> {
+    let config = read_config(deps.storage)?;
+    let controller = match config.controller {
+        Some(controller) => controller,
+        None => return Err(StdError::generic_err("Controller is not configured")),
+    };
+
+    let state = read_controller_state(deps.storage)?;
+    Ok(ControllerStateResponse {
+        target_utilization: controller.target_utilization,
+        utilization: state.utilization,
+        base_rate: config.base_rate,
+        last_step: state.last_step,
+        last_step_up: state.last_step_up,
+        last_executed_height: state.last_executed_height,
+        epoch_period: controller.epoch_period,
+    })
+}
+
+pub fn compute_utilization_ratio(
+    market_balance: Uint256,
+    total_liabilities: Decimal256,
+    total_reserves: Decimal256,
+) -> Decimal256 {
+    // ignore decimal parts
+    let total_value_in_market =
+        Decimal256::from_uint256(market_balance) + total_liabilities - total_reserves;
+
+    if total_value_in_market.is_zero() {
+        Decimal256::zero()
+    } else {
+        total_liabilities / total_value_in_market
+    }
+}
+
+/// Relative base_rate change and its direction; the utilization
+/// error is normalized by the distance from the target to 1 above
+/// the target and to 0 below it, so it stays in [0, 1]
+fn compute_step(controller: &Controller, utilization: Decimal256) -> (Decimal256, bool) {
+    let target = controller.target_utilization;
+    let (error, step_up) = if utilization > target {
+        let error = std::cmp::min(utilization - target, Decimal256::one() - target);
+        (error / (Decimal256::one() - target), true)
+    } else {
+        ((target - utilization) / target, false)
+    };
+
+    (
+        std::cmp::min(error * controller.adjustment_factor, controller.max_step),
+        step_up,
+    )
+}
+
+pub fn controller_from_msg(
+    api: &dyn Api,
+    msg: ControllerConfig,
+) -> Result<Controller, ContractError> {
+    if msg.target_utilization.is_zero()
+        || msg.target_utilization >= Decimal256::one()
+        || msg.max_step > Decimal256::one()
+        || msg.min_base_rate.is_zero()
+        || msg.min_base_rate > msg.max_base_rate
+    {
+        return Err(ContractError::InvalidControllerConfig {});
+    }
+
+    Ok(Controller {
+        market_contract: api.addr_canonicalize(&msg.market_contract)?,
+        target_utilization: msg.target_utilization,
+        adjustment_factor: msg.adjustment_factor,
+        max_step: msg.max_step,
+        min_base_rate: msg.min_base_rate,
+        max_base_rate: msg.max_base_rate,
+        epoch_period: msg.epoch_period,
+    })
+}
+
+pub fn controller_to_msg(api: &dyn Api, controller: Controller) -> StdResult<ControllerConfig> {
+    Ok(ControllerConfig {
+        market_contract: api.addr_humanize(&controller.market_contract)?.to_string(),
+        target_utilization: controller.target_utilization,
+        adjustment_factor: controller.adjustment_factor,
+        max_step: controller.max_step,
+        min_base_rate: controller.min_base_rate,
+        max_base_rate: controller.max_base_rate,
+        epoch_period: controller.epoch_period,
+    })
+}
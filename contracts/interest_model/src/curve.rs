@@ -0,0 +1,120 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Deps, StdError, StdResult};
+
+use crate::error::ContractError;
+use crate::state::{read_config, Config};
+use moneymarket::interest_model::{Curve, RateCurvePoint, RateCurveResponse};
+
+/// Upper bound of the exponential curve steepness,
+/// e^20 keeps the curve computable in Decimal256
+const MAX_STEEPNESS: u64 = 20;
+
+/// Maximum number of points sampled by a rate curve query
+const MAX_POINTS: usize = 100;
+
+/// Borrow rate of the active curve at the utilization ratio
+pub fn compute_borrow_rate(config: &Config, utilization_ratio: Decimal256) -> Decimal256 {
+    let rate = match &config.curve {
+        Curve::Linear {} => utilization_ratio * config.interest_multiplier,
+        // Above the kink the rate jumps with jump_multiplier
+        Curve::Kinked {
+            optimal_utilization,
+            jump_multiplier,
+        } if utilization_ratio > *optimal_utilization => {
+            *optimal_utilization * config.interest_multiplier
+                + (utilization_ratio - *optimal_utilization) * *jump_multiplier
+        }
+        Curve::Kinked { .. } => utilization_ratio * config.interest_multiplier,
+        Curve::PiecewiseLinear { breakpoints } => {
+            let upper = breakpoints
+                .iter()
+                .position(|point| point.utilization >= utilization_ratio);
+            match upper {
+                None => breakpoints[breakpoints.len() - 1].rate,
+                Some(0) => breakpoints[0].rate,
+                Some(i) => {
+                    let (lower, upper) = (&breakpoints[i - 1], &breakpoints[i]);
+                    lower.rate
+                        + (upper.rate - lower.rate) * (utilization_ratio - lower.utilization)
+                            / (upper.utilization - lower.utilization)
+                }
+            }
+        }
+        Curve::Exponential { steepness } => {
+            (exp(*steepness * utilization_ratio) - Decimal256::one())
+                / (exp(*steepness) - Decimal256::one())
+                * config.interest_multiplier
+        }
+    };
+
+    config.base_rate + rate
+}
+
+pub fn assert_curve(curve: &Curve) -> Result<(), ContractError> {
+    match curve {
+        Curve::Linear {} => {}
+        Curve::Kinked {
+            optimal_utilization,
+            ..
+        } => {
+            if optimal_utilization.is_zero() || *optimal_utilization > Decimal256::one() {
+                return Err(ContractError::InvalidOptimalUtilization {});
+            }
+        }
+        Curve::PiecewiseLinear { breakpoints } => {
+            let sorted = breakpoints
+                .windows(2)
+                .all(|w| w[0].utilization < w[1].utilization && w[0].rate <= w[1].rate);
+            if breakpoints.is_empty()
+                || !sorted
+                || breakpoints[breakpoints.len() - 1].utilization > Decimal256::one()
+            {
+                return Err(ContractError::InvalidBreakpoints {});
+            }
+        }
+        Curve::Exponential { steepness } => {
+            if steepness.is_zero() || *steepness > Decimal256::from_uint256(MAX_STEEPNESS) {
+                return Err(ContractError::InvalidSteepness(MAX_STEEPNESS));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn query_rate_curve(deps: Deps, points: Vec<Decimal256>) -> StdResult<RateCurveResponse> {
+    if points.len() > MAX_POINTS {
+        return Err(StdError::generic_err(format!(
+            "Cannot sample more than {} points",
+            MAX_POINTS
+        )));
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    Ok(RateCurveResponse {
+        points: points
+            .into_iter()
+            .map(|utilization| RateCurvePoint {
+                utilization,
+                rate: compute_borrow_rate(&config, utilization),
+            })
+            .collect(),
+    })
+}
+
+/// e^x by its Taylor series, summed until the terms
+/// fall below the Decimal256 precision
+fn exp(x: Decimal256) -> Decimal256 {
+    let mut sum = Decimal256::one();
+    let mut term = Decimal256::one();
+    let mut n = 1u64;
+    loop {
+        term = term * x / Decimal256::from_uint256(Uint256::from(n));
+        if term.is_zero() {
+            return sum;
+        }
+
+        sum += term;
+        n += 1;
+    }
+}
@@ -11,4 +11,25 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Breakpoints must be sorted by utilization of at most 1, with non-decreasing rates")]
+    InvalidBreakpoints {},
+
+    #[error("Epoch period has not elapsed; base rate can change again at {0}")]
+    EpochPeriodNotElapsed(u64),
+
+    #[error("Invalid controller; target utilization must be between 0 and 1, max step at most 1 and min base rate greater than 0 and at most max base rate")]
+    InvalidControllerConfig {},
+
+    #[error("Optimal utilization must be greater than 0 and at most 1")]
+    InvalidOptimalUtilization {},
+
+    #[error("Seconds per block must be greater than 0")]
+    InvalidSecondsPerBlock {},
+
+    #[error("Rates are only converted when migrating from the legacy version")]
+    RateConversionNotAllowed {},
+
+    #[error("Steepness must be greater than 0 and at most {0}")]
+    InvalidSteepness(u64),
 }
@@ -1,4 +1,6 @@
 pub mod contract;
+pub mod controller;
+pub mod curve;
 pub mod error;
 pub mod state;
 
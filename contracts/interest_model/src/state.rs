@@ -4,14 +4,49 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
 use cosmwasm_storage::{singleton, singleton_read};
+use moneymarket::interest_model::Curve;
 
-static KEY_CONFIG: &[u8] = b"config";
+pub static KEY_CONFIG: &[u8] = b"config";
+static KEY_CONTROLLER_STATE: &[u8] = b"controller_state";
+pub static KEY_CONTRACT_VERSION: &[u8] = b"contract_version";
+
+/// Version of the storage layout; contracts instantiated
+/// before it was stored have the legacy config
+pub const CONTRACT_VERSION: u64 = 1;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: CanonicalAddr,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    pub curve: Curve,
+    pub controller: Option<Controller>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner: CanonicalAddr,
+    pub base_rate: Decimal256,
+    pub interest_multiplier: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Controller {
+    pub market_contract: CanonicalAddr,
+    pub target_utilization: Decimal256,
+    pub adjustment_factor: Decimal256,
+    pub max_step: Decimal256,
+    pub min_base_rate: Decimal256,
+    pub max_base_rate: Decimal256,
+    pub epoch_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ControllerState {
+    pub utilization: Decimal256,
+    pub last_step: Decimal256,
+    pub last_step_up: bool,
+    pub last_executed_height: u64,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -21,3 +56,32 @@ pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()>
 pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
+
+pub fn read_legacy_config(storage: &dyn Storage) -> StdResult<LegacyConfig> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+pub fn store_contract_version(storage: &mut dyn Storage, version: u64) -> StdResult<()> {
+    singleton(storage, KEY_CONTRACT_VERSION).save(&version)
+}
+
+pub fn read_contract_version(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    singleton_read(storage, KEY_CONTRACT_VERSION).may_load()
+}
+
+pub fn store_controller_state(storage: &mut dyn Storage, state: &ControllerState) -> StdResult<()> {
+    singleton(storage, KEY_CONTROLLER_STATE).save(state)
+}
+
+pub fn read_controller_state(storage: &dyn Storage) -> StdResult<ControllerState> {
+    singleton_read(storage, KEY_CONTROLLER_STATE)
+        .may_load()
+        .map(|state| {
+            state.unwrap_or(ControllerState {
+                utilization: Decimal256::zero(),
+                last_step: Decimal256::zero(),
+                last_step_up: false,
+                last_executed_height: 0,
+            })
+        })
+}
@@ -1,11 +1,15 @@
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::error::ContractError;
+use crate::state::{read_config, LegacyConfig, KEY_CONFIG, KEY_CONTRACT_VERSION};
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::from_binary;
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_storage::singleton;
 use moneymarket::interest_model::{
-    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    BorrowRateResponse, Breakpoint, ConfigResponse, ControllerConfig, ControllerStateResponse,
+    Curve, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RateCurveResponse,
 };
+use std::str::FromStr;
 
 #[test]
 fn proper_initialization() {
@@ -15,6 +19,8 @@ fn proper_initialization() {
         owner: "owner0000".to_string(),
         base_rate: Decimal256::percent(10),
         interest_multiplier: Decimal256::percent(10),
+        curve: Curve::Linear {},
+        controller: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -59,6 +65,8 @@ fn update_config() {
         owner: "owner0000".to_string(),
         base_rate: Decimal256::percent(10),
         interest_multiplier: Decimal256::percent(10),
+        curve: Curve::Linear {},
+        controller: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -70,6 +78,8 @@ fn update_config() {
         owner: Some("owner0001".to_string()),
         base_rate: None,
         interest_multiplier: None,
+        curve: None,
+        controller: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -88,6 +98,8 @@ fn update_config() {
         owner: None,
         base_rate: Some(Decimal256::percent(1)),
         interest_multiplier: Some(Decimal256::percent(1)),
+        curve: None,
+        controller: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -96,3 +108,475 @@ fn update_config() {
         _ => panic!("Must return unauthorized error"),
     }
 }
+
+#[test]
+fn kinked_borrow_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(2),
+        interest_multiplier: Decimal256::percent(10),
+        curve: Curve::Kinked {
+            optimal_utilization: Decimal256::zero(),
+            jump_multiplier: Decimal256::one(),
+        },
+        controller: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidOptimalUtilization {}) => (),
+        _ => panic!("Must return invalid optimal utilization error"),
+    }
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(2),
+        interest_multiplier: Decimal256::percent(10),
+        curve: Curve::Kinked {
+            optimal_utilization: Decimal256::percent(80),
+            jump_multiplier: Decimal256::one(),
+        },
+        controller: None,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // below the kink; utilization_ratio = 0.5
+    // borrow_rate = 0.5 * 0.1 + 0.02
+    let query_msg = QueryMsg::BorrowRate {
+        market_balance: Uint256::from(500000u128),
+        total_liabilities: Decimal256::from_uint256(500000u128),
+        total_reserves: Decimal256::zero(),
+    };
+    let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    let value: BorrowRateResponse = from_binary(&res).unwrap();
+    assert_eq!("0.07", &value.rate.to_string());
+
+    // above the kink; utilization_ratio = 0.9
+    // borrow_rate = 0.8 * 0.1 + 0.02 + (0.9 - 0.8) * 1
+    let query_msg = QueryMsg::BorrowRate {
+        market_balance: Uint256::from(100000u128),
+        total_liabilities: Decimal256::from_uint256(900000u128),
+        total_reserves: Decimal256::zero(),
+    };
+    let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    let value: BorrowRateResponse = from_binary(&res).unwrap();
+    assert_eq!("0.2", &value.rate.to_string());
+
+    // optimal_utilization cannot exceed 1
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        base_rate: None,
+        interest_multiplier: None,
+        curve: Some(Curve::Kinked {
+            optimal_utilization: Decimal256::percent(110),
+            jump_multiplier: Decimal256::one(),
+        }),
+        controller: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidOptimalUtilization {}) => (),
+        _ => panic!("Must return invalid optimal utilization error"),
+    }
+
+    // flatten the kink
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        base_rate: None,
+        interest_multiplier: None,
+        curve: Some(Curve::Kinked {
+            optimal_utilization: Decimal256::one(),
+            jump_multiplier: Decimal256::one(),
+        }),
+        controller: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let query_msg = QueryMsg::BorrowRate {
+        market_balance: Uint256::from(100000u128),
+        total_liabilities: Decimal256::from_uint256(900000u128),
+        total_reserves: Decimal256::zero(),
+    };
+    let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    let value: BorrowRateResponse = from_binary(&res).unwrap();
+    assert_eq!("0.11", &value.rate.to_string());
+}
+
+#[test]
+fn adaptive_controller() {
+    let mut deps = mock_dependencies(&[]);
+
+    let controller = ControllerConfig {
+        market_contract: "market0000".to_string(),
+        target_utilization: Decimal256::percent(80),
+        adjustment_factor: Decimal256::one(),
+        max_step: Decimal256::percent(10),
+        min_base_rate: Decimal256::percent(1),
+        max_base_rate: Decimal256::percent(20),
+        epoch_period: 100,
+    };
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(10),
+        interest_multiplier: Decimal256::percent(10),
+        curve: Curve::Linear {},
+        controller: Some(ControllerConfig {
+            target_utilization: Decimal256::one(),
+            ..controller.clone()
+        }),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidControllerConfig {}) => (),
+        _ => panic!("Must return invalid controller config error"),
+    }
+
+    // base_rate could never move up again from 0
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(10),
+        interest_multiplier: Decimal256::percent(10),
+        curve: Curve::Linear {},
+        controller: Some(ControllerConfig {
+            min_base_rate: Decimal256::zero(),
+            ..controller.clone()
+        }),
+    };
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidControllerConfig {}) => (),
+        _ => panic!("Must return invalid controller config error"),
+    }
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(10),
+        interest_multiplier: Decimal256::percent(10),
+        curve: Curve::Linear {},
+        controller: Some(controller.clone()),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(value.controller, Some(controller));
+
+    // only the market can execute epoch operations
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        market_balance: Uint256::from(100000u128),
+        total_liabilities: Decimal256::from_uint256(900000u128),
+        total_reserves: Decimal256::zero(),
+        block_height: 12345u64,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // utilization 0.9 is above the target;
+    // error = (0.9 - 0.8) / (1 - 0.8) = 0.5, capped by max_step
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("market0000", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::ControllerState {}).unwrap();
+    let value: ControllerStateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value,
+        ControllerStateResponse {
+            target_utilization: Decimal256::percent(80),
+            utilization: Decimal256::percent(90),
+            base_rate: Decimal256::percent(11),
+            last_step: Decimal256::percent(10),
+            last_step_up: true,
+            last_executed_height: 12345u64,
+            epoch_period: 100,
+        }
+    );
+
+    // utilization 0.76 is below the target;
+    // error = (0.8 - 0.76) / 0.8 = 0.05
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        market_balance: Uint256::from(240000u128),
+        total_liabilities: Decimal256::from_uint256(760000u128),
+        total_reserves: Decimal256::zero(),
+        block_height: 12445u64,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("market0000", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::ControllerState {}).unwrap();
+    let value: ControllerStateResponse = from_binary(&res).unwrap();
+    assert_eq!(value.last_step, Decimal256::percent(5));
+    assert!(!value.last_step_up);
+    assert_eq!(value.base_rate, Decimal256::from_str("0.1045").unwrap());
+
+    // base_rate stays within the bounds
+    for epoch in 0..10u64 {
+        let msg = ExecuteMsg::ExecuteEpochOperations {
+            market_balance: Uint256::zero(),
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            block_height: 12545u64 + epoch * 100,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("market0000", &[]), msg).unwrap();
+    }
+
+    let query_msg = QueryMsg::BorrowRate {
+        market_balance: Uint256::zero(),
+        total_liabilities: Decimal256::zero(),
+        total_reserves: Decimal256::zero(),
+    };
+    let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    let value: BorrowRateResponse = from_binary(&res).unwrap();
+    assert_eq!(value.rate, Decimal256::percent(20));
+}
+
+#[test]
+fn adaptive_controller_epoch_period() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(10),
+        interest_multiplier: Decimal256::percent(10),
+        curve: Curve::Linear {},
+        controller: Some(ControllerConfig {
+            market_contract: "market0000".to_string(),
+            target_utilization: Decimal256::percent(80),
+            adjustment_factor: Decimal256::one(),
+            max_step: Decimal256::percent(10),
+            min_base_rate: Decimal256::percent(1),
+            max_base_rate: Decimal256::percent(20),
+            epoch_period: 100,
+        }),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        market_balance: Uint256::from(100000u128),
+        total_liabilities: Decimal256::from_uint256(900000u128),
+        total_reserves: Decimal256::zero(),
+        block_height: 12345u64,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("market0000", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+
+    // base_rate moves at most once per epoch
+    let res = execute(deps.as_mut(), mock_env(), mock_info("market0000", &[]), msg);
+    match res {
+        Err(ContractError::EpochPeriodNotElapsed(12445)) => (),
+        _ => panic!("Must return epoch period not elapsed error"),
+    }
+
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        market_balance: Uint256::from(100000u128),
+        total_liabilities: Decimal256::from_uint256(900000u128),
+        total_reserves: Decimal256::zero(),
+        block_height: 12445u64,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("market0000", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::ControllerState {}).unwrap();
+    let value: ControllerStateResponse = from_binary(&res).unwrap();
+    assert_eq!(value.base_rate, Decimal256::from_str("0.121").unwrap());
+    assert_eq!(value.last_executed_height, 12445u64);
+}
+
+#[test]
+fn rate_curves() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(2),
+        interest_multiplier: Decimal256::percent(10),
+        curve: Curve::PiecewiseLinear {
+            breakpoints: vec![
+                Breakpoint {
+                    utilization: Decimal256::percent(50),
+                    rate: Decimal256::percent(5),
+                },
+                Breakpoint {
+                    utilization: Decimal256::percent(40),
+                    rate: Decimal256::percent(50),
+                },
+            ],
+        },
+        controller: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidBreakpoints {}) => (),
+        _ => panic!("Must return invalid breakpoints error"),
+    }
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(2),
+        interest_multiplier: Decimal256::percent(10),
+        curve: Curve::PiecewiseLinear {
+            breakpoints: vec![
+                Breakpoint {
+                    utilization: Decimal256::percent(20),
+                    rate: Decimal256::percent(1),
+                },
+                Breakpoint {
+                    utilization: Decimal256::percent(50),
+                    rate: Decimal256::percent(5),
+                },
+                Breakpoint {
+                    utilization: Decimal256::percent(80),
+                    rate: Decimal256::percent(50),
+                },
+            ],
+        },
+        controller: None,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // flat outside the breakpoints, interpolated between them
+    let query_msg = QueryMsg::RateCurve {
+        points: vec![
+            Decimal256::zero(),
+            Decimal256::percent(35),
+            Decimal256::percent(60),
+            Decimal256::one(),
+        ],
+    };
+    let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    let value: RateCurveResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value
+            .points
+            .iter()
+            .map(|point| point.rate.to_string())
+            .collect::<Vec<String>>(),
+        vec!["0.03", "0.05", "0.22", "0.52"]
+    );
+
+    // steepness must be positive
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        base_rate: None,
+        interest_multiplier: None,
+        curve: Some(Curve::Exponential {
+            steepness: Decimal256::zero(),
+        }),
+        controller: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidSteepness(20)) => (),
+        _ => panic!("Must return invalid steepness error"),
+    }
+
+    // switch to the exponential curve
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        base_rate: None,
+        interest_multiplier: None,
+        curve: Some(Curve::Exponential {
+            steepness: Decimal256::from_uint256(2u64),
+        }),
+        controller: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value.curve,
+        Curve::Exponential {
+            steepness: Decimal256::from_uint256(2u64),
+        }
+    );
+
+    // borrow_rate = 0.02 + 0.1 * (e^(2 * u) - 1) / (e^2 - 1)
+    let query_msg = QueryMsg::RateCurve {
+        points: vec![
+            Decimal256::zero(),
+            Decimal256::percent(50),
+            Decimal256::one(),
+        ],
+    };
+    let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+    let value: RateCurveResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value
+            .points
+            .iter()
+            .map(|point| point.rate.to_string())
+            .collect::<Vec<String>>(),
+        vec!["0.02", "0.046894142136999511", "0.12"]
+    );
+}
+
+#[test]
+fn migrate_to_seconds() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(12),
+        interest_multiplier: Decimal256::percent(60),
+        curve: Curve::Linear {},
+        controller: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // store the config in the layout before rate curves
+    let config = read_config(deps.as_ref().storage).unwrap();
+    singleton(deps.as_mut().storage, KEY_CONFIG)
+        .save(&LegacyConfig {
+            owner: config.owner,
+            base_rate: config.base_rate,
+            interest_multiplier: config.interest_multiplier,
+        })
+        .unwrap();
+    singleton::<u64>(deps.as_mut().storage, KEY_CONTRACT_VERSION).remove();
+
+    let msg = MigrateMsg {
+        seconds_per_block: Some(Decimal256::from_uint256(6u64)),
+    };
+    let _res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+
+    let config = read_config(deps.as_ref().storage).unwrap();
+    assert_eq!(config.base_rate, Decimal256::percent(2));
+    assert_eq!(config.interest_multiplier, Decimal256::percent(10));
+    assert_eq!(config.curve, Curve::Linear {});
+
+    // rates are not converted twice
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert_eq!(res, ContractError::RateConversionNotAllowed {});
+
+    // a later code upgrade keeps the config
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(read_config(deps.as_ref().storage).unwrap(), config);
+}
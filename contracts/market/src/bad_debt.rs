@@ -0,0 +1,152 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{attr, Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+
+use crate::borrow::{compute_borrower_interest, compute_interest, compute_repay};
+use crate::deposit::compute_exchange_rate;
+use crate::error::ContractError;
+use crate::fixed_loan::query_fixed_loan;
+use crate::redemption::fill_redemptions;
+use crate::state::{
+    read_bad_debt, read_bad_debt_count, read_borrower_info, read_config, read_fixed_loan,
+    read_state, remove_fixed_loan, store_bad_debt, store_borrower_info, store_loan_size,
+    store_state, BadDebt, BorrowerInfo, Config, State,
+};
+
+use moneymarket::market::{BadDebtResponse, BadDebtsResponse};
+use moneymarket::querier::query_balance;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn write_off_bad_debt(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+    prev_balance: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if config.overseer_contract != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let block_height = config.rate_unit.now(&env.block);
+    let cur_balance: Uint256 = query_balance(
+        deps.as_ref(),
+        env.contract.address.clone(),
+        config.stable_denom.to_string(),
+    )?;
+    let buffer_amount = cur_balance - prev_balance;
+
+    let mut state: State = read_state(deps.storage)?;
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+    let mut fixed_loan = read_fixed_loan(deps.storage, &borrower_raw);
+
+    // Compute the loan with the interest accrued so far
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        block_height,
+        Some(buffer_amount),
+    )?;
+    compute_borrower_interest(&state, &mut liability);
+
+    let amount = liability.loan_amount
+        + query_fixed_loan(deps.as_ref(), &borrower_raw, block_height)
+            .map(|fixed_loan| fixed_loan.loan_amount)
+            .unwrap_or_else(Uint256::zero);
+    if amount.is_zero() {
+        return Err(ContractError::NoBadDebt {});
+    }
+
+    // Clear the loans as if they were repaid in full
+    compute_repay(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        block_height,
+        &mut liability,
+        fixed_loan.as_mut(),
+        amount,
+        Some(buffer_amount),
+    )?;
+
+    // The interest buffer covers the bad debt first,
+    // then the reserves and the depositors take the rest
+    let buffer_amount = std::cmp::min(buffer_amount, amount);
+    let reserve_amount = std::cmp::min(
+        amount - buffer_amount,
+        state.total_reserves * Uint256::one(),
+    );
+    let loss_amount = amount - buffer_amount - reserve_amount;
+    state.total_reserves = state.total_reserves - Decimal256::from_uint256(reserve_amount);
+
+    // Stable received from the buffer fills queued redemptions
+    fill_redemptions(deps.storage, &mut state, cur_balance)?;
+
+    // Rebase the exchange rate on the loss, so the next interest
+    // update does not measure the drop as a negative deposit rate
+    if !loss_amount.is_zero() {
+        state.prev_exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, None)?;
+    }
+
+    if fixed_loan.is_some() {
+        remove_fixed_loan(deps.storage, &borrower_raw);
+    }
+    store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+    store_loan_size(deps.storage, &borrower_raw, state.global_interest_index)?;
+    store_state(deps.storage, &state)?;
+
+    let id = store_bad_debt(
+        deps.storage,
+        &BadDebt {
+            borrower: borrower_raw,
+            height: block_height,
+            amount,
+            buffer_amount,
+            reserve_amount,
+            loss_amount,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "write_off_bad_debt"),
+        attr("bad_debt_id", id.to_string()),
+        attr("borrower", borrower),
+        attr("amount", amount),
+        attr("buffer_amount", buffer_amount),
+        attr("reserve_amount", reserve_amount),
+        attr("loss_amount", loss_amount),
+    ]))
+}
+
+pub fn query_bad_debts(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<BadDebtsResponse> {
+    let count = read_bad_debt_count(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
+    let start = start_after.map(|id| id + 1).unwrap_or(0);
+
+    let bad_debts = (start..count.min(start + limit))
+        .map(|id| {
+            let bad_debt = read_bad_debt(deps.storage, id)?;
+            Ok(BadDebtResponse {
+                id,
+                borrower: deps.api.addr_humanize(&bad_debt.borrower)?.to_string(),
+                height: bad_debt.height,
+                amount: bad_debt.amount,
+                buffer_amount: bad_debt.buffer_amount,
+                reserve_amount: bad_debt.reserve_amount,
+                loss_amount: bad_debt.loss_amount,
+            })
+        })
+        .collect::<StdResult<Vec<BadDebtResponse>>>()?;
+
+    Ok(BadDebtsResponse { bad_debts })
+}
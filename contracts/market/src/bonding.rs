@@ -0,0 +1,124 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    Uint128, WasmMsg,
+};
+
+use crate::borrow::compute_reward;
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_depositor_info, read_state, store_depositor_info, store_state, Config,
+    DepositorInfo, State,
+};
+
+use cw20::Cw20ExecuteMsg;
+use moneymarket::market::DepositorInfoResponse;
+
+pub fn bond_aterra(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let depositor_raw = deps.api.addr_canonicalize(sender.as_str())?;
+    let mut depositor_info: DepositorInfo = read_depositor_info(deps.storage, &depositor_raw);
+
+    // Compute ANC reward before the bonded amount changes
+    compute_reward(&config, &mut state, config.rate_unit.now(&env.block));
+    compute_depositor_reward(&state, &mut depositor_info);
+
+    let amount = Uint256::from(amount);
+    depositor_info.bonded_amount += amount;
+    state.total_bonded_aterra += amount;
+
+    store_state(deps.storage, &state)?;
+    store_depositor_info(deps.storage, &depositor_raw, &depositor_info)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "bond_aterra"),
+        attr("depositor", sender),
+        attr("amount", amount),
+    ]))
+}
+
+pub fn unbond_aterra(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroUnbond {});
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let depositor = info.sender;
+    let depositor_raw = deps.api.addr_canonicalize(depositor.as_str())?;
+    let mut depositor_info: DepositorInfo = read_depositor_info(deps.storage, &depositor_raw);
+    if amount > depositor_info.bonded_amount {
+        return Err(ContractError::InsufficientBondedATerra(
+            depositor_info.bonded_amount.into(),
+        ));
+    }
+
+    // Compute ANC reward before the bonded amount changes
+    compute_reward(&config, &mut state, config.rate_unit.now(&env.block));
+    compute_depositor_reward(&state, &mut depositor_info);
+
+    depositor_info.bonded_amount = depositor_info.bonded_amount - amount;
+    state.total_bonded_aterra = state.total_bonded_aterra - amount;
+
+    store_state(deps.storage, &state)?;
+    store_depositor_info(deps.storage, &depositor_raw, &depositor_info)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: depositor.to_string(),
+                amount: amount.into(),
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "unbond_aterra"),
+            attr("depositor", depositor),
+            attr("amount", amount),
+        ]))
+}
+
+/// Compute reward amount a depositor received
+pub(crate) fn compute_depositor_reward(state: &State, depositor_info: &mut DepositorInfo) {
+    depositor_info.pending_rewards += Decimal256::from_uint256(depositor_info.bonded_amount)
+        * (state.depositor_reward_index - depositor_info.reward_index);
+    depositor_info.reward_index = state.depositor_reward_index;
+}
+
+pub fn query_depositor_info(
+    deps: Deps,
+    env: Env,
+    depositor: Addr,
+    block_height: Option<u64>,
+) -> StdResult<DepositorInfoResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let depositor_raw = deps.api.addr_canonicalize(depositor.as_str())?;
+    let mut depositor_info: DepositorInfo = read_depositor_info(deps.storage, &depositor_raw);
+
+    let block_height = block_height.unwrap_or_else(|| config.rate_unit.now(&env.block));
+    compute_reward(&config, &mut state, block_height);
+    compute_depositor_reward(&state, &mut depositor_info);
+
+    Ok(DepositorInfoResponse {
+        depositor: depositor.to_string(),
+        bonded_amount: depositor_info.bonded_amount,
+        reward_index: depositor_info.reward_index,
+        pending_rewards: depositor_info.pending_rewards,
+    })
+}
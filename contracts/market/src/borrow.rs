@@ -1,20 +1,29 @@
 use anchor_token::distributor::ExecuteMsg as FaucetExecuteMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, WasmMsg,
+    attr, to_binary, Addr, BankMsg, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env,
+    MessageInfo, Response, StdError, StdResult, Storage, WasmMsg,
 };
 use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::market::{BorrowerInfoResponse, BorrowerInfosResponse};
+use moneymarket::market::{
+    BorrowHeadroomResponse, BorrowerInfoResponse, BorrowerInfosResponse, SimulateBorrowResponse,
+    SimulateRepayResponse,
+};
 use moneymarket::overseer::BorrowLimitResponse;
 use moneymarket::querier::{deduct_tax, query_balance, query_supply};
 
-use crate::deposit::compute_exchange_rate_raw;
+use crate::bonding::compute_depositor_reward;
+use crate::deposit::{compute_exchange_rate_raw, queued_redemptions};
+use crate::emission::compute_remaining_emission;
 use crate::error::ContractError;
+use crate::fixed_loan::{query_fixed_loan, repay_fixed_loan};
 use crate::querier::{query_borrow_limit, query_borrow_rate, query_target_deposit_rate};
+use crate::redemption::fill_redemptions;
 use crate::state::{
-    read_borrower_info, read_borrower_infos, read_config, read_state, store_borrower_info,
-    store_state, BorrowerInfo, Config, State,
+    read_borrow_cap, read_borrower_info, read_borrower_info_range, read_borrower_infos,
+    read_config, read_depositor_info, read_fixed_loan, read_largest_borrowers, read_state,
+    remove_fixed_loan, store_borrower_info, store_depositor_info, store_fixed_loan,
+    store_loan_size, store_state, BorrowerInfo, Config, FixedLoan, State,
 };
 
 pub fn borrow_stable(
@@ -32,41 +41,19 @@ pub fn borrow_stable(
     let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
     let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
 
-    // Compute interest
-    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
-    compute_borrower_interest(&state, &mut liability);
-
-    // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
-    compute_borrower_reward(&state, &mut liability);
-
-    let overseer = deps.api.addr_humanize(&config.overseer_contract)?;
-    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
-        deps.as_ref(),
-        overseer,
-        borrower.clone(),
-        Some(env.block.time.seconds()),
-    )?;
-
-    if borrow_limit_res.borrow_limit < borrow_amount + liability.loan_amount {
-        return Err(ContractError::BorrowExceedsLimit(
-            borrow_limit_res.borrow_limit.into(),
-        ));
-    }
-
-    let current_balance = query_balance(
+    let origination_fee = compute_borrow(
         deps.as_ref(),
-        env.contract.address,
-        config.stable_denom.to_string(),
+        &env,
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        &borrower,
+        &mut liability,
+        borrow_amount,
     )?;
-
-    // Assert borrow amount
-    assert_max_borrow_factor(&config, &state, current_balance, borrow_amount)?;
-
-    liability.loan_amount += borrow_amount;
-    state.total_liabilities += Decimal256::from_uint256(borrow_amount);
     store_state(deps.storage, &state)?;
     store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+    store_loan_size(deps.storage, &borrower_raw, state.global_interest_index)?;
 
     Ok(Response::new()
         .add_message(CosmosMsg::Bank(BankMsg::Send {
@@ -75,7 +62,7 @@ pub fn borrow_stable(
                 deps.as_ref(),
                 Coin {
                     denom: config.stable_denom,
-                    amount: borrow_amount.into(),
+                    amount: (borrow_amount - origination_fee).into(),
                 },
             )?],
         }))
@@ -83,6 +70,7 @@ pub fn borrow_stable(
             attr("action", "borrow_stable"),
             attr("borrower", borrower),
             attr("borrow_amount", borrow_amount),
+            attr("origination_fee", origination_fee),
         ]))
 }
 
@@ -113,10 +101,15 @@ pub fn repay_stable_from_liquidation(
         amount: (cur_balance - prev_balance).into(),
     }];
 
-    repay_stable(deps, env, info)
+    repay_stable(deps, env, info, None)
 }
 
-pub fn repay_stable(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+pub fn repay_stable(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Option<Addr>,
+) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
 
     // Check stable denom deposit
@@ -127,65 +120,310 @@ pub fn repay_stable(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respon
         .map(|c| Uint256::from(c.amount))
         .unwrap_or_else(Uint256::zero);
 
-    // Cannot deposit zero amount
-    if amount.is_zero() {
-        return Err(ContractError::ZeroRepay(config.stable_denom));
-    }
-
     let mut state: State = read_state(deps.storage)?;
 
-    let borrower = info.sender;
+    // Repay the sender's own loan unless a borrower is given
+    let payer = info.sender;
+    let borrower = borrower.unwrap_or_else(|| payer.clone());
     let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
     let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+    let mut fixed_loan = read_fixed_loan(deps.storage, &borrower_raw);
 
-    // Compute interest
-    compute_interest(
+    let repay_amount = compute_repay(
         deps.as_ref(),
         &config,
         &mut state,
-        env.block.height,
+        config.rate_unit.now(&env.block),
+        &mut liability,
+        fixed_loan.as_mut(),
+        amount,
         Some(amount),
     )?;
-    compute_borrower_interest(&state, &mut liability);
 
-    // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
-    compute_borrower_reward(&state, &mut liability);
+    match fixed_loan {
+        Some(fixed_loan) if fixed_loan.loan_amount.is_zero() => {
+            remove_fixed_loan(deps.storage, &borrower_raw)
+        }
+        Some(fixed_loan) => store_fixed_loan(deps.storage, &borrower_raw, &fixed_loan)?,
+        None => (),
+    }
 
-    let repay_amount: Uint256;
     let mut messages: Vec<CosmosMsg> = vec![];
-    if liability.loan_amount < amount {
-        repay_amount = liability.loan_amount;
-        liability.loan_amount = Uint256::zero();
-
-        // Payback left repay amount to sender
+    if repay_amount < amount {
+        // Payback left repay amount to payer
         messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: borrower.to_string(),
+            to_address: payer.to_string(),
             amount: vec![deduct_tax(
                 deps.as_ref(),
                 Coin {
-                    denom: config.stable_denom,
+                    denom: config.stable_denom.to_string(),
                     amount: (amount - repay_amount).into(),
                 },
             )?],
         }));
-    } else {
-        repay_amount = amount;
-        liability.loan_amount = liability.loan_amount - repay_amount;
     }
 
-    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(repay_amount);
+    // Queued redemptions are filled first; the
+    // refunded amount is not part of the liquidity
+    let balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )? - (amount - repay_amount);
+    fill_redemptions(deps.storage, &mut state, balance)?;
 
     store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+    store_loan_size(deps.storage, &borrower_raw, state.global_interest_index)?;
     store_state(deps.storage, &state)?;
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "repay_stable"),
+        attr("payer", payer),
         attr("borrower", borrower),
         attr("repay_amount", repay_amount),
     ]))
 }
 
+pub fn query_simulate_borrow(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    borrow_amount: Uint256,
+    block_height: Option<u64>,
+) -> StdResult<SimulateBorrowResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_height = simulation_height(&config, &state, &env, block_height)?;
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+
+    let res = compute_borrow(
+        deps,
+        &env,
+        &config,
+        &mut state,
+        block_height,
+        &borrower,
+        &mut liability,
+        borrow_amount,
+    )
+    .and_then(|origination_fee| {
+        let received_amount = deduct_tax(
+            deps,
+            Coin {
+                denom: config.stable_denom.to_string(),
+                amount: (borrow_amount - origination_fee).into(),
+            },
+        )?
+        .amount;
+        Ok((received_amount, origination_fee))
+    });
+
+    Ok(match res {
+        Ok((received_amount, origination_fee)) => SimulateBorrowResponse {
+            received_amount: received_amount.into(),
+            origination_fee,
+            loan_amount: liability.loan_amount,
+            error: None,
+        },
+        Err(err) => SimulateBorrowResponse {
+            received_amount: Uint256::zero(),
+            origination_fee: Uint256::zero(),
+            loan_amount: Uint256::zero(),
+            error: Some(err.to_string()),
+        },
+    })
+}
+
+pub fn query_simulate_repay(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    amount: Uint256,
+    block_height: Option<u64>,
+) -> StdResult<SimulateRepayResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_height = simulation_height(&config, &state, &env, block_height)?;
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+    let mut fixed_loan = read_fixed_loan(deps.storage, &borrower_raw);
+
+    // The repay amount is not part of the contract balance yet
+    let res = compute_repay(
+        deps,
+        &config,
+        &mut state,
+        block_height,
+        &mut liability,
+        fixed_loan.as_mut(),
+        amount,
+        None,
+    )
+    .and_then(|repay_amount| {
+        let refund_amount = if repay_amount < amount {
+            deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.to_string(),
+                    amount: (amount - repay_amount).into(),
+                },
+            )?
+            .amount
+            .into()
+        } else {
+            Uint256::zero()
+        };
+
+        Ok((repay_amount, refund_amount))
+    });
+
+    Ok(match res {
+        Ok((repay_amount, refund_amount)) => SimulateRepayResponse {
+            repay_amount,
+            refund_amount,
+            loan_amount: liability.loan_amount,
+            fixed_loan_amount: fixed_loan
+                .map(|fixed_loan| fixed_loan.loan_amount)
+                .unwrap_or_else(Uint256::zero),
+            error: None,
+        },
+        Err(err) => SimulateRepayResponse {
+            repay_amount: Uint256::zero(),
+            refund_amount: Uint256::zero(),
+            loan_amount: Uint256::zero(),
+            fixed_loan_amount: Uint256::zero(),
+            error: Some(err.to_string()),
+        },
+    })
+}
+
+/// Update the interest up to `block_height`, check
+/// the borrow against all the limits and add it to the
+/// borrower liability and the total liabilities
+#[allow(clippy::too_many_arguments)]
+fn compute_borrow(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    state: &mut State,
+    block_height: u64,
+    borrower: &Addr,
+    liability: &mut BorrowerInfo,
+    borrow_amount: Uint256,
+) -> Result<Uint256, ContractError> {
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+
+    // Compute interest
+    compute_interest(deps, config, state, block_height, None)?;
+    compute_borrower_interest(state, liability);
+
+    // Compute ANC reward
+    compute_reward(config, state, block_height);
+    compute_borrower_reward(state, liability);
+
+    let overseer = deps.api.addr_humanize(&config.overseer_contract)?;
+    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+        deps,
+        overseer,
+        borrower.clone(),
+        Some(env.block.time.seconds()),
+    )?;
+
+    let fixed_loan_amount = query_fixed_loan(deps, &borrower_raw, block_height)
+        .map(|fixed_loan| fixed_loan.loan_amount)
+        .unwrap_or_else(Uint256::zero);
+
+    if borrow_limit_res.borrow_limit < borrow_amount + liability.loan_amount + fixed_loan_amount {
+        return Err(ContractError::BorrowExceedsLimit(
+            borrow_limit_res.borrow_limit.into(),
+        ));
+    }
+
+    let current_balance = query_balance(
+        deps,
+        env.contract.address.clone(),
+        config.stable_denom.to_string(),
+    )?;
+
+    // Assert borrow amount
+    assert_max_borrow_factor(config, state, current_balance, borrow_amount)?;
+    assert_borrow_caps(
+        deps.storage,
+        config,
+        state,
+        &borrower_raw,
+        liability.loan_amount + fixed_loan_amount,
+        borrow_amount,
+    )?;
+
+    liability.loan_amount += borrow_amount;
+    state.total_liabilities += Decimal256::from_uint256(borrow_amount);
+
+    Ok(charge_origination_fee(config, state, borrow_amount))
+}
+
+/// Add the origination fee of the borrow to the reserves and
+/// return it; the fee is kept from the amount sent to the borrower
+pub(crate) fn charge_origination_fee(
+    config: &Config,
+    state: &mut State,
+    borrow_amount: Uint256,
+) -> Uint256 {
+    let origination_fee = borrow_amount * config.origination_fee;
+    state.total_reserves += Decimal256::from_uint256(origination_fee);
+
+    origination_fee
+}
+
+/// Update the interest up to `block_height` and repay the variable
+/// rate loan first and the fixed rate loan with the rest; returns
+/// the repaid amount. `received_amount` is the part of the amount
+/// which is already in the contract balance
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn compute_repay(
+    deps: Deps,
+    config: &Config,
+    state: &mut State,
+    block_height: u64,
+    liability: &mut BorrowerInfo,
+    fixed_loan: Option<&mut FixedLoan>,
+    amount: Uint256,
+    received_amount: Option<Uint256>,
+) -> Result<Uint256, ContractError> {
+    // Cannot repay zero amount
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay(config.stable_denom.to_string()));
+    }
+
+    // Compute interest
+    compute_interest(deps, config, state, block_height, received_amount)?;
+    compute_borrower_interest(state, liability);
+
+    // Compute ANC reward
+    compute_reward(config, state, block_height);
+    compute_borrower_reward(state, liability);
+
+    let variable_repay_amount = std::cmp::min(amount, liability.loan_amount);
+    liability.loan_amount = liability.loan_amount - variable_repay_amount;
+    state.total_liabilities =
+        state.total_liabilities - Decimal256::from_uint256(variable_repay_amount);
+
+    let fixed_repay_amount = match fixed_loan {
+        Some(fixed_loan) => repay_fixed_loan(
+            state,
+            fixed_loan,
+            amount - variable_repay_amount,
+            block_height,
+        ),
+        None => Uint256::zero(),
+    };
+
+    Ok(variable_repay_amount + fixed_repay_amount)
+}
+
 pub fn claim_rewards(
     deps: DepsMut,
     env: Env,
@@ -200,18 +438,39 @@ pub fn claim_rewards(
     let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
 
     // Compute interest
-    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        None,
+    )?;
     compute_borrower_interest(&state, &mut liability);
 
     // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&config, &mut state, config.rate_unit.now(&env.block));
     compute_borrower_reward(&state, &mut liability);
 
-    let claim_amount = liability.pending_rewards * Uint256::one();
-    liability.pending_rewards = liability.pending_rewards - Decimal256::from_uint256(claim_amount);
+    let borrower_claim_amount = liability.pending_rewards * Uint256::one();
+    liability.pending_rewards =
+        liability.pending_rewards - Decimal256::from_uint256(borrower_claim_amount);
+
+    // Rewards of the bonded aTerra
+    let mut depositor_info = read_depositor_info(deps.storage, &borrower_raw);
+    compute_depositor_reward(&state, &mut depositor_info);
+
+    let depositor_claim_amount = depositor_info.pending_rewards * Uint256::one();
+    depositor_info.pending_rewards =
+        depositor_info.pending_rewards - Decimal256::from_uint256(depositor_claim_amount);
+    let claim_amount = borrower_claim_amount + depositor_claim_amount;
+    state.total_claimed_anc += claim_amount;
 
     store_state(deps.storage, &state)?;
     store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+    store_loan_size(deps.storage, &borrower_raw, state.global_interest_index)?;
+    if !depositor_info.bonded_amount.is_zero() || !depositor_claim_amount.is_zero() {
+        store_depositor_info(deps.storage, &borrower_raw, &depositor_info)?;
+    }
 
     let messages: Vec<CosmosMsg> = if !claim_amount.is_zero() {
         vec![CosmosMsg::Wasm(WasmMsg::Execute {
@@ -259,13 +518,7 @@ pub fn compute_interest(
         config.stable_denom.to_string(),
     )? - deposit_amount.unwrap_or_else(Uint256::zero);
 
-    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-        deps,
-        deps.api.addr_humanize(&config.interest_model)?,
-        balance,
-        state.total_liabilities,
-        state.total_reserves,
-    )?;
+    let borrow_rate = query_market_borrow_rate(deps, config, state, balance)?;
 
     let target_deposit_rate: Decimal256 =
         query_target_deposit_rate(deps, deps.api.addr_humanize(&config.overseer_contract)?)?;
@@ -275,13 +528,34 @@ pub fn compute_interest(
         block_height,
         balance,
         aterra_supply,
-        borrow_rate_res.rate,
+        borrow_rate,
+        config.reserve_factor,
         target_deposit_rate,
     );
 
     Ok(())
 }
 
+/// Variable borrow rate of the market with the given balance;
+/// fixed rate loans count in the utilization and the stable set
+/// aside for redemptions does not
+pub(crate) fn query_market_borrow_rate(
+    deps: Deps,
+    config: &Config,
+    state: &State,
+    balance: Uint256,
+) -> StdResult<Decimal256> {
+    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+        deps,
+        deps.api.addr_humanize(&config.interest_model)?,
+        balance - state.claimable_redemptions,
+        state.total_liabilities + state.total_fixed_liabilities,
+        state.total_reserves,
+    )?;
+
+    Ok(borrow_rate_res.rate)
+}
+
 // CONTRACT: to use this function as state update purpose,
 // executor must update following three state after execution
 // * state.prev_aterra_supply
@@ -293,6 +567,7 @@ pub fn compute_interest_raw(
     balance: Uint256,
     aterra_supply: Uint256,
     borrow_rate: Decimal256,
+    reserve_factor: Decimal256,
     target_deposit_rate: Decimal256,
 ) {
     if state.last_interest_updated >= block_height {
@@ -303,14 +578,26 @@ pub fn compute_interest_raw(
 
     let interest_factor = passed_blocks * borrow_rate;
     let interest_accrued = state.total_liabilities * interest_factor;
+    let fixed_interest_accrued = state.fixed_interest_flow * passed_blocks;
 
     state.global_interest_index =
         state.global_interest_index * (Decimal256::one() + interest_factor);
     state.total_liabilities += interest_accrued;
+    state.total_fixed_liabilities += fixed_interest_accrued;
 
+    // Set aside the reserve factor share of the interest
+    state.total_reserves += (interest_accrued + fixed_interest_accrued) * reserve_factor;
+
+    // The exchange rate is below the previous one after depositors
+    // take a bad debt loss, which is a zero deposit rate
     let mut exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
-    let effective_deposit_rate = exchange_rate / state.prev_exchange_rate;
-    let deposit_rate = (effective_deposit_rate - Decimal256::one()) / passed_blocks;
+    let deposit_rate =
+        if state.prev_exchange_rate.is_zero() || exchange_rate <= state.prev_exchange_rate {
+            Decimal256::zero()
+        } else {
+            let effective_deposit_rate = exchange_rate / state.prev_exchange_rate;
+            (effective_deposit_rate - Decimal256::one()) / passed_blocks
+        };
 
     if deposit_rate > target_deposit_rate {
         // excess_deposit_rate(_per_block)
@@ -337,20 +624,49 @@ pub(crate) fn compute_borrower_interest(state: &State, liability: &mut BorrowerI
     liability.interest_index = state.global_interest_index;
 }
 
-/// Compute distributed reward and update global index
-pub fn compute_reward(state: &mut State, block_height: u64) {
+/// Compute distributed reward and update global index;
+/// the rewards stop once the emission budget is spent
+pub fn compute_reward(config: &Config, state: &mut State, block_height: u64) {
     if state.last_reward_updated >= block_height {
         return;
     }
 
     let passed_blocks = Decimal256::from_uint256(block_height - state.last_reward_updated);
-    let reward_accrued = passed_blocks * state.anc_emission_rate;
     let borrow_amount = state.total_liabilities / state.global_interest_index;
 
-    if !reward_accrued.is_zero() && !borrow_amount.is_zero() {
+    // Nothing is emitted without anyone to receive it
+    let mut reward_accrued = if borrow_amount.is_zero() {
+        Decimal256::zero()
+    } else {
+        passed_blocks * state.anc_emission_rate
+    };
+    let mut depositor_reward_accrued = if state.total_bonded_aterra.is_zero() {
+        Decimal256::zero()
+    } else {
+        passed_blocks * state.depositor_emission_rate
+    };
+
+    // Scale both rewards down to the emission left
+    let emitted = reward_accrued + depositor_reward_accrued;
+    if let Some(remaining_emission) = compute_remaining_emission(config, state) {
+        if emitted > remaining_emission {
+            let ratio = remaining_emission / emitted;
+            reward_accrued = reward_accrued * ratio;
+            depositor_reward_accrued = depositor_reward_accrued * ratio;
+        }
+    }
+
+    if !reward_accrued.is_zero() {
         state.global_reward_index += reward_accrued / borrow_amount;
     }
 
+    if !depositor_reward_accrued.is_zero() {
+        state.depositor_reward_index +=
+            depositor_reward_accrued / Decimal256::from_uint256(state.total_bonded_aterra);
+    }
+
+    state.total_emitted_anc += reward_accrued + depositor_reward_accrued;
+    state.epoch_emitted_anc += reward_accrued + depositor_reward_accrued;
     state.last_reward_updated = block_height;
 }
 
@@ -368,32 +684,235 @@ pub fn query_borrower_info(
     borrower: Addr,
     block_height: Option<u64>,
 ) -> StdResult<BorrowerInfoResponse> {
-    let mut borrower_info: BorrowerInfo = read_borrower_info(
-        deps.storage,
-        &deps.api.addr_canonicalize(borrower.as_str())?,
-    );
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let borrower_info: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+
+    let (state, block_height) = query_accrued_state(deps, &env, block_height)?;
+    Ok(accrued_borrower_info(
+        deps,
+        &state,
+        borrower,
+        &borrower_raw,
+        borrower_info,
+        block_height,
+    ))
+}
+
+/// Borrower infos in address order with the interest
+/// and the rewards accrued up to `block_height`
+pub fn query_accrued_borrower_infos(
+    deps: Deps,
+    env: Env,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+    block_height: Option<u64>,
+) -> StdResult<BorrowerInfosResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(start_after.as_str())?)
+    } else {
+        None
+    };
+
+    let (state, block_height) = query_accrued_state(deps, &env, block_height)?;
+    let borrower_infos = read_borrower_info_range(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|(borrower_raw, borrower_info)| {
+            Ok(accrued_borrower_info(
+                deps,
+                &state,
+                deps.api.addr_humanize(&borrower_raw)?,
+                &borrower_raw,
+                borrower_info,
+                block_height,
+            ))
+        })
+        .collect::<StdResult<Vec<BorrowerInfoResponse>>>()?;
+
+    Ok(BorrowerInfosResponse { borrower_infos })
+}
+
+/// Borrower infos ordered by the loan size at the last update
+/// of each borrower, largest first, with the interest and
+/// the rewards accrued up to `block_height`
+pub fn query_largest_borrowers(
+    deps: Deps,
+    env: Env,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+    block_height: Option<u64>,
+) -> StdResult<BorrowerInfosResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(start_after.as_str())?)
+    } else {
+        None
+    };
+
+    let (state, block_height) = query_accrued_state(deps, &env, block_height)?;
+    let borrower_infos = read_largest_borrowers(deps.storage, start_after, limit)?
+        .into_iter()
+        .map(|borrower_raw| {
+            Ok(accrued_borrower_info(
+                deps,
+                &state,
+                deps.api.addr_humanize(&borrower_raw)?,
+                &borrower_raw,
+                read_borrower_info(deps.storage, &borrower_raw),
+                block_height,
+            ))
+        })
+        .collect::<StdResult<Vec<BorrowerInfoResponse>>>()?;
+
+    Ok(BorrowerInfosResponse { borrower_infos })
+}
 
-    let block_height = if let Some(block_height) = block_height {
-        block_height
+/// Index the loan sizes of the borrowers after `start_after`
+/// against the current interest index; the last borrower is
+/// returned to continue from
+pub fn reindex_loan_sizes(
+    deps: DepsMut,
+    env: Env,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(start_after.as_str())?)
     } else {
-        env.block.height
+        None
     };
 
+    let (state, _) = query_accrued_state(deps.as_ref(), &env, None)?;
+    let borrowers = read_borrower_info_range(deps.storage, start_after, limit)?;
+    for (borrower, _) in borrowers.iter() {
+        store_loan_size(deps.storage, borrower, state.global_interest_index)?;
+    }
+
+    let last_borrower = match borrowers.last() {
+        Some((borrower, _)) => deps.api.addr_humanize(borrower)?.to_string(),
+        None => String::new(),
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "reindex_loan_sizes"),
+        attr("count", borrowers.len().to_string()),
+        attr("last_borrower", last_borrower),
+    ]))
+}
+
+/// Height of a simulation, the current block by default; the
+/// interest cannot be simulated before its last update
+pub(crate) fn simulation_height(
+    config: &Config,
+    state: &State,
+    env: &Env,
+    block_height: Option<u64>,
+) -> StdResult<u64> {
+    let block_height = block_height.unwrap_or_else(|| config.rate_unit.now(&env.block));
+    if block_height < state.last_interest_updated {
+        return Err(StdError::generic_err(
+            "block_height must bigger than last_interest_updated",
+        ));
+    }
+
+    Ok(block_height)
+}
+
+/// State with the interest and the rewards
+/// accrued up to `block_height`, if given
+fn query_accrued_state(
+    deps: Deps,
+    env: &Env,
+    block_height: Option<u64>,
+) -> StdResult<(State, u64)> {
     let config: Config = read_config(deps.storage)?;
     let mut state: State = read_state(deps.storage)?;
 
+    let block_height = block_height.unwrap_or_else(|| config.rate_unit.now(&env.block));
     compute_interest(deps, &config, &mut state, block_height, None)?;
-    compute_borrower_interest(&state, &mut borrower_info);
+    compute_reward(&config, &mut state, block_height);
+
+    Ok((state, block_height))
+}
+
+fn accrued_borrower_info(
+    deps: Deps,
+    state: &State,
+    borrower: Addr,
+    borrower_raw: &CanonicalAddr,
+    mut borrower_info: BorrowerInfo,
+    block_height: u64,
+) -> BorrowerInfoResponse {
+    compute_borrower_interest(state, &mut borrower_info);
+    compute_borrower_reward(state, &mut borrower_info);
 
-    compute_reward(&mut state, block_height);
-    compute_borrower_reward(&state, &mut borrower_info);
+    let fixed_loan = query_fixed_loan(deps, borrower_raw, block_height);
 
-    Ok(BorrowerInfoResponse {
+    BorrowerInfoResponse {
         borrower: borrower.to_string(),
         interest_index: borrower_info.interest_index,
         reward_index: borrower_info.reward_index,
         loan_amount: borrower_info.loan_amount,
         pending_rewards: borrower_info.pending_rewards,
+        fixed_loan_amount: fixed_loan
+            .as_ref()
+            .map(|l| l.loan_amount)
+            .unwrap_or_else(Uint256::zero),
+        fixed_rate: fixed_loan.map(|l| l.rate).unwrap_or_else(Decimal256::zero),
+    }
+}
+
+pub fn query_borrow_headroom(
+    deps: Deps,
+    env: Env,
+    borrower: Option<Addr>,
+    block_height: Option<u64>,
+) -> StdResult<BorrowHeadroomResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let block_height = block_height.unwrap_or_else(|| config.rate_unit.now(&env.block));
+    compute_interest(deps, &config, &mut state, block_height, None)?;
+
+    let total_liabilities = state.total_liabilities + state.total_fixed_liabilities;
+    let debt_ceiling_headroom = config.debt_ceiling.map(|debt_ceiling| {
+        let debt_ceiling = Decimal256::from_uint256(debt_ceiling);
+        if debt_ceiling > total_liabilities {
+            (debt_ceiling - total_liabilities) * Uint256::one()
+        } else {
+            Uint256::zero()
+        }
+    });
+
+    let (borrow_cap, borrow_cap_headroom) = match borrower {
+        Some(borrower) => {
+            let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+            match read_borrow_cap(deps.storage, &borrower_raw) {
+                Some(borrow_cap) => {
+                    let mut liability = read_borrower_info(deps.storage, &borrower_raw);
+                    compute_borrower_interest(&state, &mut liability);
+
+                    let loan_amount = liability.loan_amount
+                        + query_fixed_loan(deps, &borrower_raw, block_height)
+                            .map(|fixed_loan| fixed_loan.loan_amount)
+                            .unwrap_or_else(Uint256::zero);
+                    let headroom = if borrow_cap > loan_amount {
+                        borrow_cap - loan_amount
+                    } else {
+                        Uint256::zero()
+                    };
+
+                    (Some(borrow_cap), Some(headroom))
+                }
+                None => (None, None),
+            }
+        }
+        None => (None, None),
+    };
+
+    Ok(BorrowHeadroomResponse {
+        debt_ceiling: config.debt_ceiling,
+        debt_ceiling_headroom,
+        borrow_cap,
+        borrow_cap_headroom,
     })
 }
 
@@ -412,7 +931,36 @@ pub fn query_borrower_infos(
     Ok(BorrowerInfosResponse { borrower_infos })
 }
 
-fn assert_max_borrow_factor(
+/// Assert the debt ceiling of the market
+/// and the borrow cap of the borrower
+pub(crate) fn assert_borrow_caps(
+    storage: &dyn Storage,
+    config: &Config,
+    state: &State,
+    borrower: &CanonicalAddr,
+    loan_amount: Uint256,
+    borrow_amount: Uint256,
+) -> Result<(), ContractError> {
+    if let Some(debt_ceiling) = config.debt_ceiling {
+        if state.total_liabilities
+            + state.total_fixed_liabilities
+            + Decimal256::from_uint256(borrow_amount)
+            > Decimal256::from_uint256(debt_ceiling)
+        {
+            return Err(ContractError::DebtCeilingReached(debt_ceiling.into()));
+        }
+    }
+
+    if let Some(borrow_cap) = read_borrow_cap(storage, borrower) {
+        if loan_amount + borrow_amount > borrow_cap {
+            return Err(ContractError::BorrowCapReached(borrow_cap.into()));
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn assert_max_borrow_factor(
     config: &Config,
     state: &State,
     current_balance: Uint256,
@@ -420,11 +968,11 @@ fn assert_max_borrow_factor(
 ) -> Result<(), ContractError> {
     let current_balance = Decimal256::from_uint256(current_balance);
     let borrow_amount = Decimal256::from_uint256(borrow_amount);
+    let total_liabilities = state.total_liabilities + state.total_fixed_liabilities;
 
     // Assert max borrow factor
-    if state.total_liabilities + borrow_amount
-        > (current_balance + state.total_liabilities - state.total_reserves)
-            * config.max_borrow_factor
+    if total_liabilities + borrow_amount
+        > (current_balance + total_liabilities - state.total_reserves) * config.max_borrow_factor
     {
         return Err(ContractError::MaxBorrowFactorReached(
             config.stable_denom.clone(),
@@ -432,7 +980,7 @@ fn assert_max_borrow_factor(
     }
 
     // Assert available balance
-    if borrow_amount + state.total_reserves > current_balance {
+    if borrow_amount + state.total_reserves + queued_redemptions(state) > current_balance {
         return Err(ContractError::NoStableAvailable(
             config.stable_denom.clone(),
         ));
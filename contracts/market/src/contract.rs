@@ -1,34 +1,59 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
+use crate::bad_debt::{query_bad_debts, write_off_bad_debt};
+use crate::bonding::{bond_aterra, query_depositor_info, unbond_aterra};
 use crate::borrow::{
     borrow_stable, claim_rewards, compute_interest, compute_interest_raw, compute_reward,
-    query_borrower_info, query_borrower_infos, repay_stable, repay_stable_from_liquidation,
+    query_accrued_borrower_infos, query_borrow_headroom, query_borrower_info, query_borrower_infos,
+    query_largest_borrowers, query_market_borrow_rate, query_simulate_borrow, query_simulate_repay,
+    reindex_loan_sizes, repay_stable, repay_stable_from_liquidation,
 };
-use crate::deposit::{compute_exchange_rate_raw, deposit_stable, redeem_stable};
+use crate::deposit::{
+    compute_exchange_rate_raw, deposit_stable, query_simulate_deposit, query_simulate_redeem,
+    query_simulate_redeem_exact_stable, redeem_exact_stable, redeem_stable,
+};
+use crate::emission::{query_emission_budget, update_emission_budget};
 use crate::error::ContractError;
-use crate::querier::{query_anc_emission_rate, query_borrow_rate, query_target_deposit_rate};
+use crate::fixed_loan::{borrow_stable_fixed, rebalance_fixed_rate};
+use crate::flash_loan::{assert_flash_loan_repaid, flash_loan, FLASH_LOAN_REPLY_ID};
+use crate::overview::query_market_overview;
+use crate::querier::{
+    query_anc_emission_rate, query_controller_state, query_depositor_emission_rate,
+    query_target_deposit_rate,
+};
+use crate::redemption::{
+    cancel_redemption, claim_redemption, query_redemption, query_redemption_queue, queue_redemption,
+};
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use crate::snapshot::{query_apy, query_snapshots};
+use crate::state::{
+    read_config, read_contract_version, read_flash_loan, read_legacy_config, read_legacy_state,
+    read_state, remove_borrow_cap, store_borrow_cap, store_config, store_contract_version,
+    store_snapshot, store_state, Config, Snapshot, State, CONTRACT_VERSION,
+};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Deps,
-    DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, Binary, CanonicalAddr, Coin, ContractResult,
+    CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult, SubMsg,
+    Uint128, WasmMsg,
 };
 use cw20::{Cw20Coin, Cw20ReceiveMsg, MinterResponse};
 
-use moneymarket::common::optional_addr_validate;
-use moneymarket::interest_model::BorrowRateResponse;
+use moneymarket::common::{estimate_block_time, optional_addr_validate, RateUnit};
+use moneymarket::distribution_model::ExecuteMsg as DistributionModelExecuteMsg;
+use moneymarket::interest_model::ExecuteMsg as InterestModelExecuteMsg;
 use moneymarket::market::{
-    ConfigResponse, Cw20HookMsg, EpochStateResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
-    StateResponse,
+    ConfigResponse, Cw20HookMsg, EpochStateResponse, ExecuteMsg, FixedRateConfig, InstantiateMsg,
+    MigrateMsg, QueryMsg, StateResponse,
 };
 use moneymarket::querier::{deduct_tax, query_balance, query_supply};
 use protobuf::Message;
 use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
 
 pub const INITIAL_DEPOSIT_AMOUNT: u128 = 1000000;
+pub const EPOCH_OPERATIONS_REPLY_ID: u64 = 3;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -51,6 +76,7 @@ pub fn instantiate(
         ));
     }
 
+    let rate_unit = msg.rate_unit.unwrap_or(RateUnit::Block);
     store_config(
         deps.storage,
         &Config {
@@ -64,6 +90,16 @@ pub fn instantiate(
             distributor_contract: CanonicalAddr::from(vec![]),
             stable_denom: msg.stable_denom.clone(),
             max_borrow_factor: msg.max_borrow_factor,
+            rate_unit,
+            flash_loan_fee: msg.flash_loan_fee,
+            fixed_rate: msg.fixed_rate,
+            debt_ceiling: None,
+            reserve_factor: Decimal256::zero(),
+            insurance_contract: None,
+            insurance_share: Decimal256::zero(),
+            origination_fee: Decimal256::zero(),
+            emission_budget: None,
+            epoch_emission_cap: None,
         },
     )?;
 
@@ -72,15 +108,26 @@ pub fn instantiate(
         &State {
             total_liabilities: Decimal256::zero(),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: rate_unit.now(&env.block),
+            last_reward_updated: rate_unit.now(&env.block),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: msg.anc_emission_rate,
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         },
     )?;
+    store_contract_version(deps.storage, CONTRACT_VERSION)?;
 
     Ok(
         Response::new().add_submessages(vec![SubMsg::reply_on_success(
@@ -118,6 +165,12 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // The market balance is off while a flash loan is
+    // in progress, so no other operation is allowed
+    if read_flash_loan(deps.storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::RegisterContracts {
@@ -142,6 +195,8 @@ pub fn execute(
             interest_model,
             distribution_model,
             max_borrow_factor,
+            flash_loan_fee,
+            fixed_rate,
         } => {
             let api = deps.api;
             update_config(
@@ -152,8 +207,46 @@ pub fn execute(
                 optional_addr_validate(api, interest_model)?,
                 optional_addr_validate(api, distribution_model)?,
                 max_borrow_factor,
+                flash_loan_fee,
+                fixed_rate,
+            )
+        }
+        ExecuteMsg::UpdateDebtCeiling { debt_ceiling } => {
+            update_debt_ceiling(deps, info, debt_ceiling)
+        }
+        ExecuteMsg::UpdateBorrowCap {
+            borrower,
+            borrow_cap,
+        } => {
+            let api = deps.api;
+            update_borrow_cap(deps, info, api.addr_validate(&borrower)?, borrow_cap)
+        }
+        ExecuteMsg::UpdateReserveConfig {
+            reserve_factor,
+            insurance_contract,
+            insurance_share,
+        } => {
+            let api = deps.api;
+            update_reserve_config(
+                deps,
+                env,
+                info,
+                reserve_factor,
+                optional_addr_validate(api, insurance_contract)?,
+                insurance_share,
             )
         }
+        ExecuteMsg::UpdateOriginationFee { origination_fee } => {
+            update_origination_fee(deps, info, origination_fee)
+        }
+        ExecuteMsg::UpdateEmissionBudget {
+            emission_budget,
+            epoch_emission_cap,
+        } => update_emission_budget(deps, env, info, emission_budget, epoch_emission_cap),
+        ExecuteMsg::WithdrawReserves { amount, to } => {
+            let api = deps.api;
+            withdraw_reserves(deps, env, info, amount, optional_addr_validate(api, to)?)
+        }
         ExecuteMsg::ExecuteEpochOperations {
             deposit_rate,
             target_deposit_rate,
@@ -168,7 +261,10 @@ pub fn execute(
             threshold_deposit_rate,
             distributed_interest,
         ),
-        ExecuteMsg::DepositStable {} => deposit_stable(deps, env, info),
+        ExecuteMsg::DepositStable { to } => {
+            let api = deps.api;
+            deposit_stable(deps, env, info, optional_addr_validate(api, to)?)
+        }
         ExecuteMsg::BorrowStable { borrow_amount, to } => {
             let api = deps.api;
             borrow_stable(
@@ -179,7 +275,24 @@ pub fn execute(
                 optional_addr_validate(api, to)?,
             )
         }
-        ExecuteMsg::RepayStable {} => repay_stable(deps, env, info),
+        ExecuteMsg::BorrowStableFixed { borrow_amount, to } => {
+            let api = deps.api;
+            borrow_stable_fixed(
+                deps,
+                env,
+                info,
+                borrow_amount,
+                optional_addr_validate(api, to)?,
+            )
+        }
+        ExecuteMsg::RebalanceFixedRate { borrower } => {
+            let api = deps.api;
+            rebalance_fixed_rate(deps, env, api.addr_validate(&borrower)?)
+        }
+        ExecuteMsg::RepayStable { borrower } => {
+            let api = deps.api;
+            repay_stable(deps, env, info, optional_addr_validate(api, borrower)?)
+        }
         ExecuteMsg::RepayStableFromLiquidation {
             borrower,
             prev_balance,
@@ -193,15 +306,33 @@ pub fn execute(
                 prev_balance,
             )
         }
+        ExecuteMsg::WriteOffBadDebt {
+            borrower,
+            prev_balance,
+        } => {
+            let api = deps.api;
+            write_off_bad_debt(deps, env, info, api.addr_validate(&borrower)?, prev_balance)
+        }
+        ExecuteMsg::UnbondATerra { amount } => unbond_aterra(deps, env, info, amount),
         ExecuteMsg::ClaimRewards { to } => {
             let api = deps.api;
             claim_rewards(deps, env, info, optional_addr_validate(api, to)?)
         }
+        ExecuteMsg::FlashLoan { amount, msg } => flash_loan(deps, env, info, amount, msg),
+        ExecuteMsg::ClaimRedemption { id } => claim_redemption(deps, info, id),
+        ExecuteMsg::CancelRedemption { id } => cancel_redemption(deps, env, info, id),
+        ExecuteMsg::AccrueInterest {
+            update_interest_model,
+        } => accrue_interest(deps, env, info, update_interest_model.unwrap_or(false)),
+        ExecuteMsg::ReindexLoanSizes { start_after, limit } => {
+            let api = deps.api;
+            reindex_loan_sizes(deps, env, optional_addr_validate(api, start_after)?, limit)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg.id {
         1 => {
             // get new token's contract address
@@ -218,10 +349,26 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 
             register_aterra(deps, token_addr)
         }
+        FLASH_LOAN_REPLY_ID => assert_flash_loan_repaid(deps, env),
+        EPOCH_OPERATIONS_REPLY_ID => skip_model_epoch_operations(msg),
         _ => Err(ContractError::InvalidReplyId {}),
     }
 }
 
+/// A model that does not run epoch operations rejects them;
+/// the market epoch goes on with the model left as it is
+fn skip_model_epoch_operations(msg: Reply) -> Result<Response, ContractError> {
+    let error = match msg.result {
+        ContractResult::Err(error) => error,
+        ContractResult::Ok(_) => return Ok(Response::default()),
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "skip_model_epoch_operations"),
+        attr("error", error),
+    ]))
+}
+
 pub fn receive_cw20(
     deps: DepsMut,
     env: Env,
@@ -230,7 +377,24 @@ pub fn receive_cw20(
 ) -> Result<Response, ContractError> {
     let contract_addr = info.sender;
     match from_binary(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::RedeemStable {}) => {
+        Ok(Cw20HookMsg::RedeemStable { to }) => {
+            // only asset contract can execute this message
+            let config: Config = read_config(deps.storage)?;
+            if deps.api.addr_canonicalize(contract_addr.as_str())? != config.aterra_contract {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let api = deps.api;
+            let cw20_sender_addr = api.addr_validate(&cw20_msg.sender)?;
+            redeem_stable(
+                deps,
+                env,
+                cw20_sender_addr,
+                cw20_msg.amount,
+                optional_addr_validate(api, to)?,
+            )
+        }
+        Ok(Cw20HookMsg::RedeemExactStable { stable_amount }) => {
             // only asset contract can execute this message
             let config: Config = read_config(deps.storage)?;
             if deps.api.addr_canonicalize(contract_addr.as_str())? != config.aterra_contract {
@@ -238,7 +402,27 @@ pub fn receive_cw20(
             }
 
             let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
-            redeem_stable(deps, env, cw20_sender_addr, cw20_msg.amount)
+            redeem_exact_stable(deps, env, cw20_sender_addr, cw20_msg.amount, stable_amount)
+        }
+        Ok(Cw20HookMsg::QueueRedemption {}) => {
+            // only asset contract can execute this message
+            let config: Config = read_config(deps.storage)?;
+            if deps.api.addr_canonicalize(contract_addr.as_str())? != config.aterra_contract {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+            queue_redemption(deps, env, cw20_sender_addr, cw20_msg.amount)
+        }
+        Ok(Cw20HookMsg::BondATerra {}) => {
+            // only asset contract can execute this message
+            let config: Config = read_config(deps.storage)?;
+            if deps.api.addr_canonicalize(contract_addr.as_str())? != config.aterra_contract {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+            bond_aterra(deps, env, cw20_sender_addr, cw20_msg.amount)
         }
         _ => Err(ContractError::MissingRedeemStableHook {}),
     }
@@ -284,6 +468,7 @@ pub fn register_contracts(
     Ok(Response::default())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
     env: Env,
@@ -292,6 +477,8 @@ pub fn update_config(
     interest_model: Option<Addr>,
     distribution_model: Option<Addr>,
     max_borrow_factor: Option<Decimal256>,
+    flash_loan_fee: Option<Decimal256>,
+    fixed_rate: Option<FixedRateConfig>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
 
@@ -306,7 +493,13 @@ pub fn update_config(
 
     if interest_model.is_some() {
         let mut state: State = read_state(deps.storage)?;
-        compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+        compute_interest(
+            deps.as_ref(),
+            &config,
+            &mut state,
+            config.rate_unit.now(&env.block),
+            None,
+        )?;
         store_state(deps.storage, &state)?;
 
         if let Some(interest_model) = interest_model {
@@ -322,10 +515,283 @@ pub fn update_config(
         config.max_borrow_factor = max_borrow_factor;
     }
 
+    if let Some(flash_loan_fee) = flash_loan_fee {
+        config.flash_loan_fee = Some(flash_loan_fee);
+    }
+
+    if let Some(fixed_rate) = fixed_rate {
+        config.fixed_rate = Some(fixed_rate);
+    }
+
     store_config(deps.storage, &config)?;
     Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
 }
 
+pub fn update_debt_ceiling(
+    deps: DepsMut,
+    info: MessageInfo,
+    debt_ceiling: Option<Uint256>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.debt_ceiling = debt_ceiling;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_debt_ceiling"),
+        attr(
+            "debt_ceiling",
+            debt_ceiling.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ),
+    ]))
+}
+
+pub fn update_borrow_cap(
+    deps: DepsMut,
+    info: MessageInfo,
+    borrower: Addr,
+    borrow_cap: Option<Uint256>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    match borrow_cap {
+        Some(borrow_cap) => store_borrow_cap(deps.storage, &borrower_raw, &borrow_cap)?,
+        None => remove_borrow_cap(deps.storage, &borrower_raw),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_borrow_cap"),
+        attr("borrower", borrower),
+        attr(
+            "borrow_cap",
+            borrow_cap.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ),
+    ]))
+}
+
+pub fn update_reserve_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    reserve_factor: Option<Decimal256>,
+    insurance_contract: Option<Addr>,
+    insurance_share: Option<Decimal256>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(reserve_factor) = reserve_factor {
+        if reserve_factor > Decimal256::one() {
+            return Err(ContractError::InvalidReserveConfig {});
+        }
+
+        // Accrue the interest with the previous reserve factor
+        let mut state: State = read_state(deps.storage)?;
+        compute_interest(
+            deps.as_ref(),
+            &config,
+            &mut state,
+            config.rate_unit.now(&env.block),
+            None,
+        )?;
+        store_state(deps.storage, &state)?;
+
+        config.reserve_factor = reserve_factor;
+    }
+
+    if let Some(insurance_contract) = insurance_contract {
+        config.insurance_contract = Some(deps.api.addr_canonicalize(insurance_contract.as_str())?);
+    }
+
+    if let Some(insurance_share) = insurance_share {
+        if insurance_share > Decimal256::one() {
+            return Err(ContractError::InvalidReserveConfig {});
+        }
+
+        config.insurance_share = insurance_share;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_reserve_config")]))
+}
+
+pub fn update_origination_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    origination_fee: Decimal256,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if origination_fee >= Decimal256::one() {
+        return Err(ContractError::InvalidOriginationFee {});
+    }
+
+    config.origination_fee = origination_fee;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_origination_fee"),
+        attr("origination_fee", origination_fee.to_string()),
+    ]))
+}
+
+pub fn withdraw_reserves(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Option<Uint256>,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        None,
+    )?;
+
+    let total_reserves = state.total_reserves * Uint256::one();
+    let amount = amount.unwrap_or(total_reserves);
+    if amount.is_zero() || amount > total_reserves {
+        return Err(ContractError::InsufficientReserves(total_reserves.into()));
+    }
+
+    let balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+    if balance < amount + state.claimable_redemptions {
+        return Err(ContractError::NoStableAvailable(config.stable_denom));
+    }
+
+    state.total_reserves = state.total_reserves - Decimal256::from_uint256(amount);
+    store_state(deps.storage, &state)?;
+
+    let recipient = to.unwrap_or(info.sender);
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom,
+                    amount: amount.into(),
+                },
+            )?],
+        }))
+        .add_attributes(vec![
+            attr("action", "withdraw_reserves"),
+            attr("recipient", recipient),
+            attr("amount", amount),
+        ]))
+}
+
+pub fn accrue_interest(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    update_interest_model: bool,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if update_interest_model
+        && deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        None,
+    )?;
+    compute_reward(&config, &mut state, config.rate_unit.now(&env.block));
+    store_state(deps.storage, &state)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let interest_model = deps.api.addr_humanize(&config.interest_model)?;
+    if update_interest_model
+        && controller_epoch_elapsed(
+            deps.as_ref(),
+            interest_model.clone(),
+            config.rate_unit.now(&env.block),
+        )
+    {
+        let balance: Uint256 = query_balance(
+            deps.as_ref(),
+            env.contract.address,
+            config.stable_denom.to_string(),
+        )?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: interest_model.to_string(),
+            funds: vec![],
+            msg: to_binary(&InterestModelExecuteMsg::ExecuteEpochOperations {
+                market_balance: balance - state.claimable_redemptions,
+                total_liabilities: state.total_liabilities + state.total_fixed_liabilities,
+                total_reserves: state.total_reserves,
+                block_height: config.rate_unit.now(&env.block),
+            })?,
+        }));
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "accrue_interest"),
+        attr(
+            "global_interest_index",
+            state.global_interest_index.to_string(),
+        ),
+        attr("exchange_rate", state.prev_exchange_rate.to_string()),
+    ]))
+}
+
+/// Whether the interest model controller can step again;
+/// false when no controller is configured
+fn controller_epoch_elapsed(deps: Deps, interest_model: Addr, block_height: u64) -> bool {
+    match query_controller_state(deps, interest_model) {
+        Ok(controller) => {
+            controller.last_executed_height == 0
+                || block_height
+                    >= controller
+                        .last_executed_height
+                        .saturating_add(controller.epoch_period)
+        }
+        Err(_) => false,
+    }
+}
+
 pub fn execute_epoch_operations(
     deps: DepsMut,
     env: Env,
@@ -353,20 +819,15 @@ pub fn execute_epoch_operations(
         config.stable_denom.to_string(),
     )? - distributed_interest;
 
-    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-        deps.as_ref(),
-        deps.api.addr_humanize(&config.interest_model)?,
-        balance,
-        state.total_liabilities,
-        state.total_reserves,
-    )?;
+    let borrow_rate = query_market_borrow_rate(deps.as_ref(), &config, &state, balance)?;
 
     compute_interest_raw(
         &mut state,
-        env.block.height,
+        config.rate_unit.now(&env.block),
         balance,
         aterra_supply,
-        borrow_rate_res.rate,
+        borrow_rate,
+        config.reserve_factor,
         target_deposit_rate,
     );
 
@@ -374,31 +835,98 @@ pub fn execute_epoch_operations(
     state.prev_exchange_rate =
         compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest);
 
-    compute_reward(&mut state, env.block.height);
+    store_snapshot(
+        deps.storage,
+        &Snapshot {
+            height: config.rate_unit.now(&env.block),
+            time: env.block.time.seconds(),
+            exchange_rate: state.prev_exchange_rate,
+            borrow_rate,
+        },
+    )?;
+
+    compute_reward(&config, &mut state, config.rate_unit.now(&env.block));
+    state.epoch_emitted_anc = Decimal256::zero();
 
-    // Compute total_reserves to fund collector contract
-    // Update total_reserves and send it to collector contract
-    // only when there is enough balance
+    // Let the interest model adjust its rate curve
+    // with the utilization of this epoch
+    let interest_model_msg = SubMsg::reply_on_error(
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.interest_model)?.to_string(),
+            funds: vec![],
+            msg: to_binary(&InterestModelExecuteMsg::ExecuteEpochOperations {
+                market_balance: balance - state.claimable_redemptions,
+                total_liabilities: state.total_liabilities + state.total_fixed_liabilities,
+                total_reserves: state.total_reserves,
+                block_height: config.rate_unit.now(&env.block),
+            })?,
+        }),
+        EPOCH_OPERATIONS_REPLY_ID,
+    );
+
+    // Compute total_reserves to fund collector and insurance contracts
+    // Update total_reserves and send it to the collector contract,
+    // less the insurance share, only when there is enough balance
     let total_reserves = state.total_reserves * Uint256::one();
-    let messages: Vec<CosmosMsg> = if !total_reserves.is_zero() && balance > total_reserves {
+    let mut messages: Vec<SubMsg> = vec![];
+    if !total_reserves.is_zero() && balance > total_reserves + state.claimable_redemptions {
         state.total_reserves = state.total_reserves - Decimal256::from_uint256(total_reserves);
 
-        vec![CosmosMsg::Bank(BankMsg::Send {
-            to_address: deps
+        let mut insurance_amount = Uint256::zero();
+        if let Some(insurance_contract) = config.insurance_contract.as_ref() {
+            insurance_amount = total_reserves * config.insurance_share;
+            if !insurance_amount.is_zero() {
+                messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: deps.api.addr_humanize(insurance_contract)?.to_string(),
+                    amount: vec![deduct_tax(
+                        deps.as_ref(),
+                        Coin {
+                            denom: config.stable_denom.to_string(),
+                            amount: insurance_amount.into(),
+                        },
+                    )?],
+                })));
+            }
+        }
+
+        let collector_amount = total_reserves - insurance_amount;
+        if !collector_amount.is_zero() {
+            messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps
+                    .api
+                    .addr_humanize(&config.collector_contract)?
+                    .to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: config.stable_denom.to_string(),
+                        amount: collector_amount.into(),
+                    },
+                )?],
+            })));
+        }
+    }
+    messages.push(interest_model_msg);
+
+    // Let the distribution model controller accumulate
+    // the deposit rate error of this epoch
+    messages.push(SubMsg::reply_on_error(
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps
                 .api
-                .addr_humanize(&config.collector_contract)?
+                .addr_humanize(&config.distribution_model)?
                 .to_string(),
-            amount: vec![deduct_tax(
-                deps.as_ref(),
-                Coin {
-                    denom: config.stable_denom,
-                    amount: total_reserves.into(),
-                },
-            )?],
-        })]
-    } else {
-        vec![]
-    };
+            funds: vec![],
+            msg: to_binary(&DistributionModelExecuteMsg::ExecuteEpochOperations {
+                deposit_rate,
+                target_deposit_rate,
+                threshold_deposit_rate,
+                current_emission_rate: state.anc_emission_rate,
+                block_height: config.rate_unit.now(&env.block),
+            })?,
+        }),
+        EPOCH_OPERATIONS_REPLY_ID,
+    ));
 
     // Query updated anc_emission_rate
     state.anc_emission_rate = query_anc_emission_rate(
@@ -410,14 +938,109 @@ pub fn execute_epoch_operations(
         state.anc_emission_rate,
     )?
     .emission_rate;
+    state.depositor_emission_rate = query_depositor_emission_rate(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.distribution_model)?,
+    )?
+    .emission_rate;
 
     store_state(deps.storage, &state)?;
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "execute_epoch_operations"),
-        attr("total_reserves", total_reserves),
-        attr("anc_emission_rate", state.anc_emission_rate.to_string()),
-    ]))
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attributes(vec![
+            attr("action", "execute_epoch_operations"),
+            attr("total_reserves", total_reserves),
+            attr("anc_emission_rate", state.anc_emission_rate.to_string()),
+        ]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    if msg.seconds_per_block == Some(Decimal256::zero()) {
+        return Err(ContractError::InvalidSecondsPerBlock {});
+    }
+
+    // Config and state are only rebuilt from the legacy layout
+    // once; later migrations keep every stored value
+    if read_contract_version(deps.storage)?.is_some() {
+        if msg.seconds_per_block.is_some() {
+            return Err(ContractError::RateConversionNotAllowed {});
+        }
+
+        store_contract_version(deps.storage, CONTRACT_VERSION)?;
+        return Ok(Response::new().add_attributes(vec![attr("action", "migrate")]));
+    }
+
+    let legacy_config = read_legacy_config(deps.storage)?;
+    let rate_unit = if msg.seconds_per_block.is_some() {
+        RateUnit::Second
+    } else {
+        RateUnit::Block
+    };
+
+    store_config(
+        deps.storage,
+        &Config {
+            contract_addr: legacy_config.contract_addr,
+            owner_addr: legacy_config.owner_addr,
+            aterra_contract: legacy_config.aterra_contract,
+            interest_model: legacy_config.interest_model,
+            distribution_model: legacy_config.distribution_model,
+            overseer_contract: legacy_config.overseer_contract,
+            collector_contract: legacy_config.collector_contract,
+            distributor_contract: legacy_config.distributor_contract,
+            stable_denom: legacy_config.stable_denom,
+            max_borrow_factor: legacy_config.max_borrow_factor,
+            rate_unit,
+            flash_loan_fee: None,
+            fixed_rate: None,
+            debt_ceiling: None,
+            reserve_factor: Decimal256::zero(),
+            insurance_contract: None,
+            insurance_share: Decimal256::zero(),
+            origination_fee: Decimal256::zero(),
+            emission_budget: None,
+            epoch_emission_cap: None,
+        },
+    )?;
+
+    let legacy_state = read_legacy_state(deps.storage)?;
+    let mut state = State {
+        total_liabilities: legacy_state.total_liabilities,
+        total_reserves: legacy_state.total_reserves,
+        last_interest_updated: legacy_state.last_interest_updated,
+        last_reward_updated: legacy_state.last_reward_updated,
+        global_interest_index: legacy_state.global_interest_index,
+        global_reward_index: legacy_state.global_reward_index,
+        anc_emission_rate: legacy_state.anc_emission_rate,
+        prev_aterra_supply: legacy_state.prev_aterra_supply,
+        prev_exchange_rate: legacy_state.prev_exchange_rate,
+        pending_redemptions: Uint256::zero(),
+        claimable_redemptions: Uint256::zero(),
+        total_fixed_liabilities: Decimal256::zero(),
+        fixed_interest_flow: Decimal256::zero(),
+        total_bonded_aterra: Uint256::zero(),
+        depositor_reward_index: Decimal256::zero(),
+        depositor_emission_rate: Decimal256::zero(),
+        total_emitted_anc: Decimal256::zero(),
+        epoch_emitted_anc: Decimal256::zero(),
+        total_claimed_anc: Uint256::zero(),
+    };
+
+    // Convert the accrual checkpoints and the per block
+    // emission rate into block time and per second rate
+    if let Some(seconds_per_block) = msg.seconds_per_block {
+        state.last_interest_updated =
+            estimate_block_time(&env.block, state.last_interest_updated, seconds_per_block);
+        state.last_reward_updated =
+            estimate_block_time(&env.block, state.last_reward_updated, seconds_per_block);
+        state.anc_emission_rate = state.anc_emission_rate / seconds_per_block;
+    }
+    store_state(deps.storage, &state)?;
+    store_contract_version(deps.storage, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "migrate")]))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -447,6 +1070,108 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             optional_addr_validate(deps.api, start_after)?,
             limit,
         )?),
+        QueryMsg::AccruedBorrowerInfos {
+            start_after,
+            limit,
+            block_height,
+        } => to_binary(&query_accrued_borrower_infos(
+            deps,
+            env,
+            optional_addr_validate(deps.api, start_after)?,
+            limit,
+            block_height,
+        )?),
+        QueryMsg::LargestBorrowers {
+            start_after,
+            limit,
+            block_height,
+        } => to_binary(&query_largest_borrowers(
+            deps,
+            env,
+            optional_addr_validate(deps.api, start_after)?,
+            limit,
+            block_height,
+        )?),
+        QueryMsg::DepositorInfo {
+            depositor,
+            block_height,
+        } => to_binary(&query_depositor_info(
+            deps,
+            env,
+            deps.api.addr_validate(&depositor)?,
+            block_height,
+        )?),
+        QueryMsg::SimulateRedeemExactStable {
+            stable_amount,
+            block_height,
+        } => to_binary(&query_simulate_redeem_exact_stable(
+            deps,
+            env,
+            stable_amount,
+            block_height,
+        )?),
+        QueryMsg::SimulateDeposit {
+            deposit_amount,
+            block_height,
+        } => to_binary(&query_simulate_deposit(
+            deps,
+            env,
+            deposit_amount,
+            block_height,
+        )?),
+        QueryMsg::SimulateRedeem {
+            burn_amount,
+            block_height,
+        } => to_binary(&query_simulate_redeem(
+            deps,
+            env,
+            burn_amount,
+            block_height,
+        )?),
+        QueryMsg::SimulateBorrow {
+            borrower,
+            borrow_amount,
+            block_height,
+        } => to_binary(&query_simulate_borrow(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            borrow_amount,
+            block_height,
+        )?),
+        QueryMsg::SimulateRepay {
+            borrower,
+            repay_amount,
+            block_height,
+        } => to_binary(&query_simulate_repay(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            repay_amount,
+            block_height,
+        )?),
+        QueryMsg::BorrowHeadroom {
+            borrower,
+            block_height,
+        } => to_binary(&query_borrow_headroom(
+            deps,
+            env,
+            optional_addr_validate(deps.api, borrower)?,
+            block_height,
+        )?),
+        QueryMsg::Snapshots { start_after, limit } => {
+            to_binary(&query_snapshots(deps, start_after, limit)?)
+        }
+        QueryMsg::Apy { window } => to_binary(&query_apy(deps, window)?),
+        QueryMsg::MarketOverview {} => to_binary(&query_market_overview(deps, env)?),
+        QueryMsg::BadDebts { start_after, limit } => {
+            to_binary(&query_bad_debts(deps, start_after, limit)?)
+        }
+        QueryMsg::EmissionBudget { block_height } => {
+            to_binary(&query_emission_budget(deps, env, block_height)?)
+        }
+        QueryMsg::RedemptionQueue {} => to_binary(&query_redemption_queue(deps)?),
+        QueryMsg::Redemption { id } => to_binary(&query_redemption(deps, id)?),
     }
 }
 
@@ -474,16 +1199,30 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
             .to_string(),
         stable_denom: config.stable_denom,
         max_borrow_factor: config.max_borrow_factor,
+        rate_unit: config.rate_unit,
+        flash_loan_fee: config.flash_loan_fee,
+        fixed_rate: config.fixed_rate,
+        debt_ceiling: config.debt_ceiling,
+        reserve_factor: config.reserve_factor,
+        insurance_contract: match config.insurance_contract {
+            Some(insurance_contract) => {
+                Some(deps.api.addr_humanize(&insurance_contract)?.to_string())
+            }
+            None => None,
+        },
+        insurance_share: config.insurance_share,
+        origination_fee: config.origination_fee,
     })
 }
 
 pub fn query_state(deps: Deps, env: Env, block_height: Option<u64>) -> StdResult<StateResponse> {
+    let config: Config = read_config(deps.storage)?;
     let mut state: State = read_state(deps.storage)?;
 
     let block_height = if let Some(block_height) = block_height {
         block_height
     } else {
-        env.block.height
+        config.rate_unit.now(&env.block)
     };
 
     if block_height < state.last_interest_updated {
@@ -498,13 +1237,11 @@ pub fn query_state(deps: Deps, env: Env, block_height: Option<u64>) -> StdResult
         ));
     }
 
-    let config: Config = read_config(deps.storage)?;
-
     // Compute interest rate with given block height
     compute_interest(deps, &config, &mut state, block_height, None)?;
 
     // Compute reward rate with given block height
-    compute_reward(&mut state, block_height);
+    compute_reward(&config, &mut state, block_height);
 
     Ok(StateResponse {
         total_liabilities: state.total_liabilities,
@@ -516,6 +1253,16 @@ pub fn query_state(deps: Deps, env: Env, block_height: Option<u64>) -> StdResult
         anc_emission_rate: state.anc_emission_rate,
         prev_aterra_supply: state.prev_aterra_supply,
         prev_exchange_rate: state.prev_exchange_rate,
+        pending_redemptions: state.pending_redemptions,
+        claimable_redemptions: state.claimable_redemptions,
+        total_fixed_liabilities: state.total_fixed_liabilities,
+        fixed_interest_flow: state.fixed_interest_flow,
+        total_bonded_aterra: state.total_bonded_aterra,
+        depositor_reward_index: state.depositor_reward_index,
+        depositor_emission_rate: state.depositor_emission_rate,
+        total_emitted_anc: state.total_emitted_anc,
+        epoch_emitted_anc: state.epoch_emitted_anc,
+        total_claimed_anc: state.total_claimed_anc,
     })
 }
 
@@ -542,13 +1289,7 @@ pub fn query_epoch_state(
             ));
         }
 
-        let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
-            deps,
-            deps.api.addr_humanize(&config.interest_model)?,
-            balance,
-            state.total_liabilities,
-            state.total_reserves,
-        )?;
+        let borrow_rate = query_market_borrow_rate(deps, &config, &state, balance)?;
 
         let target_deposit_rate: Decimal256 =
             query_target_deposit_rate(deps, deps.api.addr_humanize(&config.overseer_contract)?)?;
@@ -559,7 +1300,8 @@ pub fn query_epoch_state(
             block_height,
             balance,
             aterra_supply,
-            borrow_rate_res.rate,
+            borrow_rate,
+            config.reserve_factor,
             target_deposit_rate,
         );
     }
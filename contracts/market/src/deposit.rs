@@ -4,17 +4,22 @@ use cosmwasm_std::{
     StdResult, Uint128, WasmMsg,
 };
 
-use crate::borrow::{compute_interest, compute_reward};
+use crate::borrow::{compute_interest, compute_reward, simulation_height};
 use crate::error::ContractError;
+use crate::redemption::fill_redemptions;
 use crate::state::{read_config, read_state, store_state, Config, State};
 
 use cw20::Cw20ExecuteMsg;
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::market::{
+    SimulateDepositResponse, SimulateRedeemExactStableResponse, SimulateRedeemResponse,
+};
+use moneymarket::querier::{compute_amount_with_tax, deduct_tax, query_balance, query_supply};
 
 pub fn deposit_stable(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    to: Option<Addr>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
 
@@ -26,41 +31,42 @@ pub fn deposit_stable(
         .map(|c| Uint256::from(c.amount))
         .unwrap_or_else(Uint256::zero);
 
-    // Cannot deposit zero amount
-    if deposit_amount.is_zero() {
-        return Err(ContractError::ZeroDeposit(config.stable_denom));
-    }
-
-    // Update interest related state
     let mut state: State = read_state(deps.storage)?;
-    compute_interest(
+    let (mint_amount, _) = compute_deposit(
         deps.as_ref(),
         &config,
         &mut state,
-        env.block.height,
+        config.rate_unit.now(&env.block),
+        deposit_amount,
         Some(deposit_amount),
     )?;
-    compute_reward(&mut state, env.block.height);
-
-    // Load anchor token exchange rate with updated state
-    let exchange_rate =
-        compute_exchange_rate(deps.as_ref(), &config, &state, Some(deposit_amount))?;
-    let mint_amount = deposit_amount / exchange_rate;
 
     state.prev_aterra_supply += mint_amount;
+
+    // Queued redemptions are filled first
+    let balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+    fill_redemptions(deps.storage, &mut state, balance)?;
     store_state(deps.storage, &state)?;
+
+    // Mint to the sender unless a recipient is given
+    let recipient = to.unwrap_or_else(|| info.sender.clone());
     Ok(Response::new()
         .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
             funds: vec![],
             msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: info.sender.to_string(),
+                recipient: recipient.to_string(),
                 amount: mint_amount.into(),
             })?,
         }))
         .add_attributes(vec![
             attr("action", "deposit_stable"),
             attr("depositor", info.sender),
+            attr("recipient", recipient),
             attr("mint_amount", mint_amount),
             attr("deposit_amount", deposit_amount),
         ]))
@@ -71,27 +77,19 @@ pub fn redeem_stable(
     env: Env,
     sender: Addr,
     burn_amount: Uint128,
+    to: Option<Addr>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
 
-    // Update interest related state
     let mut state: State = read_state(deps.storage)?;
-    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
-    compute_reward(&mut state, env.block.height);
-
-    // Load anchor token exchange rate with updated state
-    let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, None)?;
-    let redeem_amount = Uint256::from(burn_amount) * exchange_rate;
-
-    let current_balance = query_balance(
+    let (redeem_amount, _) = compute_redeem(
         deps.as_ref(),
-        env.contract.address,
-        config.stable_denom.to_string(),
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        Uint256::from(burn_amount),
     )?;
 
-    // Assert redeem amount
-    assert_redeem_amount(&config, &state, current_balance, redeem_amount)?;
-
     state.prev_aterra_supply = state.prev_aterra_supply - Uint256::from(burn_amount);
     store_state(deps.storage, &state)?;
     Ok(Response::new()
@@ -104,7 +102,7 @@ pub fn redeem_stable(
                 })?,
             }),
             CosmosMsg::Bank(BankMsg::Send {
-                to_address: sender.to_string(),
+                to_address: to.unwrap_or(sender).to_string(),
                 amount: vec![deduct_tax(
                     deps.as_ref(),
                     Coin {
@@ -121,6 +119,308 @@ pub fn redeem_stable(
         ]))
 }
 
+pub fn redeem_exact_stable(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    sent_amount: Uint128,
+    stable_amount: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if stable_amount.is_zero() {
+        return Err(ContractError::ZeroRedeem(config.stable_denom));
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    let (burn_amount, redeem_amount, _) = compute_redeem_exact_stable(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        stable_amount,
+    )?;
+
+    let sent_amount = Uint256::from(sent_amount);
+    if burn_amount > sent_amount {
+        return Err(ContractError::InsufficientATerra(burn_amount.into()));
+    }
+
+    state.prev_aterra_supply = state.prev_aterra_supply - burn_amount;
+    store_state(deps.storage, &state)?;
+
+    let aterra_contract = deps.api.addr_humanize(&config.aterra_contract)?.to_string();
+    let mut messages = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: aterra_contract.clone(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Burn {
+            amount: burn_amount.into(),
+        })?,
+    })];
+
+    // Refund the aTerra not needed for the redemption
+    let refund_amount = sent_amount - burn_amount;
+    if !refund_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: aterra_contract,
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: sender.to_string(),
+                amount: refund_amount.into(),
+            })?,
+        }));
+    }
+
+    messages.push(CosmosMsg::Bank(BankMsg::Send {
+        to_address: sender.to_string(),
+        amount: vec![deduct_tax(
+            deps.as_ref(),
+            Coin {
+                denom: config.stable_denom,
+                amount: redeem_amount.into(),
+            },
+        )?],
+    }));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "redeem_exact_stable"),
+        attr("burn_amount", burn_amount),
+        attr("refund_amount", refund_amount),
+        attr("redeem_amount", redeem_amount),
+        attr("stable_amount", stable_amount),
+    ]))
+}
+
+pub fn query_simulate_deposit(
+    deps: Deps,
+    env: Env,
+    deposit_amount: Uint256,
+    block_height: Option<u64>,
+) -> StdResult<SimulateDepositResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_height = simulation_height(&config, &state, &env, block_height)?;
+
+    // The deposit is not part of the contract balance yet
+    Ok(
+        match compute_deposit(
+            deps,
+            &config,
+            &mut state,
+            block_height,
+            deposit_amount,
+            None,
+        ) {
+            Ok((mint_amount, exchange_rate)) => SimulateDepositResponse {
+                mint_amount,
+                exchange_rate,
+                error: None,
+            },
+            Err(err) => SimulateDepositResponse {
+                mint_amount: Uint256::zero(),
+                exchange_rate: Decimal256::zero(),
+                error: Some(err.to_string()),
+            },
+        },
+    )
+}
+
+pub fn query_simulate_redeem(
+    deps: Deps,
+    env: Env,
+    burn_amount: Uint256,
+    block_height: Option<u64>,
+) -> StdResult<SimulateRedeemResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_height = simulation_height(&config, &state, &env, block_height)?;
+
+    let res = compute_redeem(deps, &config, &mut state, block_height, burn_amount).and_then(
+        |(redeem_amount, exchange_rate)| {
+            let received_amount = deduct_tax(
+                deps,
+                Coin {
+                    denom: config.stable_denom.to_string(),
+                    amount: redeem_amount.into(),
+                },
+            )?
+            .amount;
+
+            Ok((redeem_amount, received_amount, exchange_rate))
+        },
+    );
+
+    Ok(match res {
+        Ok((redeem_amount, received_amount, exchange_rate)) => SimulateRedeemResponse {
+            redeem_amount,
+            received_amount: received_amount.into(),
+            exchange_rate,
+            error: None,
+        },
+        Err(err) => SimulateRedeemResponse {
+            redeem_amount: Uint256::zero(),
+            received_amount: Uint256::zero(),
+            exchange_rate: Decimal256::zero(),
+            error: Some(err.to_string()),
+        },
+    })
+}
+
+pub fn query_simulate_redeem_exact_stable(
+    deps: Deps,
+    env: Env,
+    stable_amount: Uint256,
+    block_height: Option<u64>,
+) -> StdResult<SimulateRedeemExactStableResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_height = simulation_height(&config, &state, &env, block_height)?;
+
+    let res = if stable_amount.is_zero() {
+        Err(ContractError::ZeroRedeem(config.stable_denom.to_string()))
+    } else {
+        compute_redeem_exact_stable(deps, &config, &mut state, block_height, stable_amount)
+    };
+
+    Ok(match res {
+        Ok((burn_amount, redeem_amount, exchange_rate)) => SimulateRedeemExactStableResponse {
+            burn_amount,
+            redeem_amount,
+            stable_amount,
+            exchange_rate,
+            error: None,
+        },
+        Err(err) => SimulateRedeemExactStableResponse {
+            burn_amount: Uint256::zero(),
+            redeem_amount: Uint256::zero(),
+            stable_amount: Uint256::zero(),
+            exchange_rate: Decimal256::zero(),
+            error: Some(err.to_string()),
+        },
+    })
+}
+
+/// Update the interest up to `block_height` and return the aTerra
+/// to mint for `deposit_amount` with the exchange rate used;
+/// `received_amount` is the part of the deposit which is
+/// already in the contract balance
+fn compute_deposit(
+    deps: Deps,
+    config: &Config,
+    state: &mut State,
+    block_height: u64,
+    deposit_amount: Uint256,
+    received_amount: Option<Uint256>,
+) -> Result<(Uint256, Decimal256), ContractError> {
+    // Cannot deposit zero amount
+    if deposit_amount.is_zero() {
+        return Err(ContractError::ZeroDeposit(config.stable_denom.to_string()));
+    }
+
+    // Update interest related state
+    compute_interest(deps, config, state, block_height, received_amount)?;
+    compute_reward(config, state, block_height);
+
+    // Load anchor token exchange rate with updated state
+    let exchange_rate = compute_exchange_rate(deps, config, state, received_amount)?;
+    if exchange_rate.is_zero() {
+        return Err(ContractError::ZeroExchangeRate {});
+    }
+
+    Ok((deposit_amount / exchange_rate, exchange_rate))
+}
+
+/// Update the interest up to `block_height` and return the stable
+/// to send before tax for `burn_amount` with the exchange rate used
+fn compute_redeem(
+    deps: Deps,
+    config: &Config,
+    state: &mut State,
+    block_height: u64,
+    burn_amount: Uint256,
+) -> Result<(Uint256, Decimal256), ContractError> {
+    // Update interest related state
+    compute_interest(deps, config, state, block_height, None)?;
+    compute_reward(config, state, block_height);
+
+    // Load anchor token exchange rate with updated state
+    let exchange_rate = compute_exchange_rate(deps, config, state, None)?;
+    let redeem_amount = burn_amount * exchange_rate;
+
+    let current_balance = query_balance(
+        deps,
+        deps.api.addr_humanize(&config.contract_addr)?,
+        config.stable_denom.to_string(),
+    )?;
+
+    // Assert redeem amount
+    assert_redeem_amount(config, state, current_balance, redeem_amount)?;
+
+    Ok((redeem_amount, exchange_rate))
+}
+
+/// Update the interest up to `block_height` and return the aTerra
+/// to burn and the stable to send before tax, so that the receiver
+/// gets `stable_amount`, with the exchange rate used
+fn compute_redeem_exact_stable(
+    deps: Deps,
+    config: &Config,
+    state: &mut State,
+    block_height: u64,
+    stable_amount: Uint256,
+) -> Result<(Uint256, Uint256, Decimal256), ContractError> {
+    // Update interest related state
+    compute_interest(deps, config, state, block_height, None)?;
+    compute_reward(config, state, block_height);
+
+    // Load anchor token exchange rate with updated state
+    let exchange_rate = compute_exchange_rate(deps, config, state, None)?;
+    let (burn_amount, redeem_amount) =
+        compute_exact_redeem_amounts(deps, config, exchange_rate, stable_amount)?;
+
+    let current_balance = query_balance(
+        deps,
+        deps.api.addr_humanize(&config.contract_addr)?,
+        config.stable_denom.to_string(),
+    )?;
+
+    // Assert redeem amount
+    assert_redeem_amount(config, state, current_balance, redeem_amount)?;
+
+    Ok((burn_amount, redeem_amount, exchange_rate))
+}
+
+/// Returns the aTerra to burn and the stable amount to send
+/// before tax, so that the receiver gets stable_amount
+fn compute_exact_redeem_amounts(
+    deps: Deps,
+    config: &Config,
+    exchange_rate: Decimal256,
+    stable_amount: Uint256,
+) -> Result<(Uint256, Uint256), ContractError> {
+    // No amount of aTerra covers a redemption at a zero rate
+    if exchange_rate.is_zero() {
+        return Err(ContractError::ZeroExchangeRate {});
+    }
+
+    let redeem_amount = compute_amount_with_tax(
+        deps,
+        &Coin {
+            denom: config.stable_denom.to_string(),
+            amount: stable_amount.into(),
+        },
+    )?;
+
+    // Round the burn amount up so the burnt aTerra
+    // always covers the redeem amount
+    let burn_amount = Uint256(
+        (redeem_amount.0 * Decimal256::DECIMAL_FRACTIONAL + exchange_rate.0 - Uint256::one().0)
+            / exchange_rate.0,
+    );
+    assert!(burn_amount * exchange_rate >= redeem_amount);
+
+    Ok((burn_amount, redeem_amount))
+}
+
 fn assert_redeem_amount(
     config: &Config,
     state: &State,
@@ -129,7 +429,7 @@ fn assert_redeem_amount(
 ) -> Result<(), ContractError> {
     let current_balance = Decimal256::from_uint256(current_balance);
     let redeem_amount = Decimal256::from_uint256(redeem_amount);
-    if redeem_amount + state.total_reserves > current_balance {
+    if redeem_amount + state.total_reserves + queued_redemptions(state) > current_balance {
         return Err(ContractError::NoStableAvailable(
             config.stable_denom.clone(),
         ));
@@ -164,7 +464,18 @@ pub fn compute_exchange_rate_raw(
     }
 
     // (aterra / stable_denom)
-    // exchange_rate = (balance + total_liabilities - total_reserves) / aterra_supply
-    (Decimal256::from_uint256(contract_balance) + state.total_liabilities - state.total_reserves)
+    // exchange_rate = (balance + total_liabilities + total_fixed_liabilities
+    //                  - total_reserves - queued_redemptions) / aterra_supply
+    (Decimal256::from_uint256(contract_balance)
+        + state.total_liabilities
+        + state.total_fixed_liabilities
+        - state.total_reserves
+        - queued_redemptions(state))
         / Decimal256::from_uint256(aterra_supply)
 }
+
+/// Stable owed to the redemption queue, which
+/// is no longer part of the deposits
+pub(crate) fn queued_redemptions(state: &State) -> Decimal256 {
+    Decimal256::from_uint256(state.pending_redemptions + state.claimable_redemptions)
+}
@@ -0,0 +1,89 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{attr, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+
+use crate::borrow::compute_reward;
+use crate::error::ContractError;
+use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use moneymarket::market::EmissionBudgetResponse;
+
+pub fn update_emission_budget(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    emission_budget: Option<Uint256>,
+    epoch_emission_cap: Option<Uint256>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Emit the rewards accrued so far under the previous limits
+    let mut state: State = read_state(deps.storage)?;
+    compute_reward(&config, &mut state, config.rate_unit.now(&env.block));
+    store_state(deps.storage, &state)?;
+
+    config.emission_budget = emission_budget;
+    config.epoch_emission_cap = epoch_emission_cap;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_emission_budget"),
+        attr(
+            "emission_budget",
+            emission_budget.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ),
+        attr(
+            "epoch_emission_cap",
+            epoch_emission_cap.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ),
+    ]))
+}
+
+/// ANC left to emit under the emission budget and
+/// the epoch emission cap, unlimited without both
+pub(crate) fn compute_remaining_emission(config: &Config, state: &State) -> Option<Decimal256> {
+    let remaining_budget = remaining(config.emission_budget, state.total_emitted_anc);
+    let remaining_epoch_emission = remaining(config.epoch_emission_cap, state.epoch_emitted_anc);
+
+    match (remaining_budget, remaining_epoch_emission) {
+        (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+pub fn query_emission_budget(
+    deps: Deps,
+    env: Env,
+    block_height: Option<u64>,
+) -> StdResult<EmissionBudgetResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let block_height = block_height.unwrap_or_else(|| config.rate_unit.now(&env.block));
+    compute_reward(&config, &mut state, block_height);
+
+    Ok(EmissionBudgetResponse {
+        emission_budget: config.emission_budget,
+        epoch_emission_cap: config.epoch_emission_cap,
+        total_emitted: state.total_emitted_anc,
+        epoch_emitted: state.epoch_emitted_anc,
+        total_claimed: state.total_claimed_anc,
+        remaining_budget: remaining(config.emission_budget, state.total_emitted_anc),
+        remaining_epoch_emission: remaining(config.epoch_emission_cap, state.epoch_emitted_anc),
+    })
+}
+
+fn remaining(limit: Option<Uint256>, emitted: Decimal256) -> Option<Decimal256> {
+    limit.map(|limit| {
+        let limit = Decimal256::from_uint256(limit);
+        if limit > emitted {
+            limit - emitted
+        } else {
+            Decimal256::zero()
+        }
+    })
+}
@@ -12,27 +12,96 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Borrow amount too high; Loan liability becomes greater than borrow cap: {0}")]
+    BorrowCapReached(u128),
+
     #[error("Borrow amount too high; Loan liability becomes greater than borrow limit: {0}")]
     BorrowExceedsLimit(u128),
 
+    #[error("Borrow amount too high; Total liabilities become greater than debt ceiling: {0}")]
+    DebtCeilingReached(u128),
+
+    #[error("Fixed rate loans are disabled")]
+    FixedRateDisabled {},
+
+    #[error("Fixed rate can be rebalanced only when the borrow rate moved above the threshold")]
+    FixedRateRebalanceNotAllowed {},
+
+    #[error("Flash loans are disabled")]
+    FlashLoanDisabled {},
+
+    #[error("A flash loan is in progress")]
+    FlashLoanInProgress {},
+
+    #[error("Flash loan not repaid; market balance must be at least {0}")]
+    FlashLoanNotRepaid(u128),
+
+    #[error("Not enough aTerra sent; redemption requires {0} aTerra")]
+    InsufficientATerra(u128),
+
     #[error("Must deposit initial funds {0}{0}")]
     InitialFundsNotDeposited(u128, String),
 
+    #[error("Not enough aTerra bonded; at most {0} can be unbonded")]
+    InsufficientBondedATerra(u128),
+
+    #[error("Not enough reserves; at most {0} can be withdrawn")]
+    InsufficientReserves(u128),
+
+    #[error("Origination fee must be less than 1")]
+    InvalidOriginationFee {},
+
+    #[error("Reserve factor and insurance share must not exceed 1")]
+    InvalidReserveConfig {},
+
     #[error("Invalid reply ID")]
     InvalidReplyId {},
 
+    #[error("Seconds per block must be greater than 0")]
+    InvalidSecondsPerBlock {},
+
+    #[error("Rates are only converted when migrating from the legacy version")]
+    RateConversionNotAllowed {},
+
     #[error("Exceeds {0} max borrow factor; borrow demand too high")]
     MaxBorrowFactorReached(String),
 
     #[error("Invalid request: \"redeem stable\" message not included in request")]
     MissingRedeemStableHook {},
 
+    #[error("Borrower has no loan to write off")]
+    NoBadDebt {},
+
+    #[error("Redemption {0} has nothing to claim")]
+    NoRedemptionClaimable(u64),
+
     #[error("Not enough {0} available; borrow demand too high")]
     NoStableAvailable(String),
 
+    #[error("Redemption {0} not found")]
+    RedemptionNotFound(u64),
+
+    #[error("Redemption amount too low; at least {0} stable must be queued")]
+    RedemptionTooSmall(u128),
+
+    #[error("Unbond amount must be greater than 0")]
+    ZeroUnbond {},
+
+    #[error("Borrow amount must be greater than 0 {0}")]
+    ZeroBorrow(String),
+
     #[error("Deposit amount must be greater than 0 {0}")]
     ZeroDeposit(String),
 
+    #[error("Flash loan amount must be greater than 0 {0}")]
+    ZeroFlashLoan(String),
+
+    #[error("Redeem amount must be greater than 0 {0}")]
+    ZeroRedeem(String),
+
     #[error("Repay amount must be greater than 0 {0}")]
     ZeroRepay(String),
+
+    #[error("Exchange rate is zero; aTerra cannot be minted or redeemed for stable")]
+    ZeroExchangeRate {},
 }
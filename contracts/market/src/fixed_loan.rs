@@ -0,0 +1,256 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, Addr, BankMsg, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+};
+use moneymarket::market::FixedRateConfig;
+use moneymarket::overseer::BorrowLimitResponse;
+use moneymarket::querier::{deduct_tax, query_balance};
+
+use crate::borrow::{
+    assert_borrow_caps, assert_max_borrow_factor, charge_origination_fee,
+    compute_borrower_interest, compute_borrower_reward, compute_interest, compute_reward,
+    query_market_borrow_rate,
+};
+use crate::error::ContractError;
+use crate::querier::query_borrow_limit;
+use crate::state::{
+    read_borrower_info, read_config, read_fixed_loan, read_state, store_borrower_info,
+    store_fixed_loan, store_loan_size, store_state, BorrowerInfo, Config, FixedLoan, State,
+};
+
+pub fn borrow_stable_fixed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrow_amount: Uint256,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let fixed_rate_config = load_fixed_rate_config(&config)?;
+    let block_height = config.rate_unit.now(&env.block);
+
+    if borrow_amount.is_zero() {
+        return Err(ContractError::ZeroBorrow(config.stable_denom));
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+
+    let borrower = info.sender;
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+
+    // Compute interest
+    compute_interest(deps.as_ref(), &config, &mut state, block_height, None)?;
+    compute_borrower_interest(&state, &mut liability);
+
+    // Compute ANC reward
+    compute_reward(&config, &mut state, block_height);
+    compute_borrower_reward(&state, &mut liability);
+
+    let mut fixed_loan = read_fixed_loan(deps.storage, &borrower_raw).unwrap_or(FixedLoan {
+        loan_amount: Uint256::zero(),
+        rate: Decimal256::zero(),
+        last_interest_updated: block_height,
+    });
+    accrue_fixed_loan(&mut state, &mut fixed_loan, block_height);
+
+    let overseer = deps.api.addr_humanize(&config.overseer_contract)?;
+    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+        deps.as_ref(),
+        overseer,
+        borrower.clone(),
+        Some(env.block.time.seconds()),
+    )?;
+
+    if borrow_limit_res.borrow_limit
+        < borrow_amount + liability.loan_amount + fixed_loan.loan_amount
+    {
+        return Err(ContractError::BorrowExceedsLimit(
+            borrow_limit_res.borrow_limit.into(),
+        ));
+    }
+
+    let current_balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+
+    // Assert borrow amount
+    assert_max_borrow_factor(&config, &state, current_balance, borrow_amount)?;
+    assert_borrow_caps(
+        deps.storage,
+        &config,
+        &state,
+        &borrower_raw,
+        liability.loan_amount + fixed_loan.loan_amount,
+        borrow_amount,
+    )?;
+
+    // Lock the current rate for the new amount and average
+    // it with the rate of the existing fixed rate loan
+    let borrow_rate = query_market_borrow_rate(deps.as_ref(), &config, &state, current_balance)?
+        + fixed_rate_config.premium;
+    let total_loan_amount = fixed_loan.loan_amount + borrow_amount;
+    fixed_loan.rate = (Decimal256::from_uint256(fixed_loan.loan_amount) * fixed_loan.rate
+        + Decimal256::from_uint256(borrow_amount) * borrow_rate)
+        / Decimal256::from_uint256(total_loan_amount);
+    fixed_loan.loan_amount = total_loan_amount;
+
+    add_fixed_interest_flow(&mut state, &fixed_loan);
+    state.total_fixed_liabilities += Decimal256::from_uint256(borrow_amount);
+    let origination_fee = charge_origination_fee(&config, &mut state, borrow_amount);
+    store_state(deps.storage, &state)?;
+    store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+    store_fixed_loan(deps.storage, &borrower_raw, &fixed_loan)?;
+    store_loan_size(deps.storage, &borrower_raw, state.global_interest_index)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: to.unwrap_or_else(|| borrower.clone()).to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom,
+                    amount: (borrow_amount - origination_fee).into(),
+                },
+            )?],
+        }))
+        .add_attributes(vec![
+            attr("action", "borrow_stable_fixed"),
+            attr("borrower", borrower),
+            attr("borrow_amount", borrow_amount),
+            attr("origination_fee", origination_fee),
+            attr("fixed_rate", fixed_loan.rate.to_string()),
+        ]))
+}
+
+pub fn rebalance_fixed_rate(
+    deps: DepsMut,
+    env: Env,
+    borrower: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let fixed_rate_config = load_fixed_rate_config(&config)?;
+    let block_height = config.rate_unit.now(&env.block);
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut fixed_loan = match read_fixed_loan(deps.storage, &borrower_raw) {
+        Some(fixed_loan) => fixed_loan,
+        None => return Err(ContractError::FixedRateRebalanceNotAllowed {}),
+    };
+
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(deps.as_ref(), &config, &mut state, block_height, None)?;
+
+    let current_balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+    let borrow_rate = query_market_borrow_rate(deps.as_ref(), &config, &state, current_balance)?;
+    if borrow_rate <= fixed_loan.rate + fixed_rate_config.rebalance_threshold {
+        return Err(ContractError::FixedRateRebalanceNotAllowed {});
+    }
+
+    accrue_fixed_loan(&mut state, &mut fixed_loan, block_height);
+    fixed_loan.rate = borrow_rate + fixed_rate_config.premium;
+    add_fixed_interest_flow(&mut state, &fixed_loan);
+
+    store_state(deps.storage, &state)?;
+    store_fixed_loan(deps.storage, &borrower_raw, &fixed_loan)?;
+    store_loan_size(deps.storage, &borrower_raw, state.global_interest_index)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "rebalance_fixed_rate"),
+        attr("borrower", borrower),
+        attr("fixed_rate", fixed_loan.rate.to_string()),
+    ]))
+}
+
+/// Repay the fixed rate loan with up to `amount` and return
+/// the repaid amount; the state interest must already be
+/// computed up to `block_height`. The caller stores the loan,
+/// or removes it once fully repaid
+pub(crate) fn repay_fixed_loan(
+    state: &mut State,
+    fixed_loan: &mut FixedLoan,
+    amount: Uint256,
+    block_height: u64,
+) -> Uint256 {
+    if amount.is_zero() {
+        return Uint256::zero();
+    }
+
+    accrue_fixed_loan(state, fixed_loan, block_height);
+
+    let repay_amount = std::cmp::min(amount, fixed_loan.loan_amount);
+    fixed_loan.loan_amount = fixed_loan.loan_amount - repay_amount;
+    state.total_fixed_liabilities =
+        state.total_fixed_liabilities - Decimal256::from_uint256(repay_amount);
+    add_fixed_interest_flow(state, fixed_loan);
+
+    repay_amount
+}
+
+/// Fixed rate loan of the borrower with the
+/// interest accrued up to `block_height`
+pub(crate) fn query_fixed_loan(
+    deps: Deps,
+    borrower: &CanonicalAddr,
+    block_height: u64,
+) -> Option<FixedLoan> {
+    read_fixed_loan(deps.storage, borrower).map(|mut fixed_loan| {
+        compute_fixed_loan_interest(&mut fixed_loan, block_height);
+        fixed_loan
+    })
+}
+
+/// Take the loan out of the fixed interest flow and apply its interest
+/// up to `block_height`; the total fixed liabilities accrued the exact
+/// interest through the flow, so the part truncated on the loan is
+/// dropped from them. The caller adds the loan back to the flow
+fn accrue_fixed_loan(state: &mut State, fixed_loan: &mut FixedLoan, block_height: u64) {
+    remove_fixed_interest_flow(state, fixed_loan);
+    let truncated_interest = compute_fixed_loan_interest(fixed_loan, block_height);
+    state.total_fixed_liabilities = if truncated_interest > state.total_fixed_liabilities {
+        Decimal256::zero()
+    } else {
+        state.total_fixed_liabilities - truncated_interest
+    };
+}
+
+/// Compute simple interest since the last update and apply it to
+/// the loan; the same interest accrues on the total fixed liabilities
+/// through the fixed interest flow. Returns the fraction of the
+/// interest truncated on the loan amount
+fn compute_fixed_loan_interest(fixed_loan: &mut FixedLoan, block_height: u64) -> Decimal256 {
+    if fixed_loan.last_interest_updated >= block_height {
+        return Decimal256::zero();
+    }
+
+    let passed_blocks = Decimal256::from_uint256(block_height - fixed_loan.last_interest_updated);
+    let interest =
+        Decimal256::from_uint256(fixed_loan.loan_amount) * (passed_blocks * fixed_loan.rate);
+    let loan_interest = interest * Uint256::one();
+    fixed_loan.loan_amount += loan_interest;
+    fixed_loan.last_interest_updated = block_height;
+
+    interest - Decimal256::from_uint256(loan_interest)
+}
+
+fn add_fixed_interest_flow(state: &mut State, fixed_loan: &FixedLoan) {
+    state.fixed_interest_flow += Decimal256::from_uint256(fixed_loan.loan_amount) * fixed_loan.rate;
+}
+
+fn remove_fixed_interest_flow(state: &mut State, fixed_loan: &FixedLoan) {
+    state.fixed_interest_flow = state.fixed_interest_flow
+        - Decimal256::from_uint256(fixed_loan.loan_amount) * fixed_loan.rate;
+}
+
+fn load_fixed_rate_config(config: &Config) -> Result<FixedRateConfig, ContractError> {
+    match config.fixed_rate.clone() {
+        Some(fixed_rate) => Ok(fixed_rate),
+        None => Err(ContractError::FixedRateDisabled {}),
+    }
+}
@@ -0,0 +1,113 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Binary, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response, SubMsg, WasmMsg,
+};
+use moneymarket::market::FlashLoanReceiverMsg;
+use moneymarket::querier::{deduct_tax, query_balance};
+
+use crate::borrow::compute_interest;
+use crate::deposit::queued_redemptions;
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_flash_loan, read_state, remove_flash_loan, store_flash_loan, store_state,
+    Config, FlashLoan, State,
+};
+
+pub const FLASH_LOAN_REPLY_ID: u64 = 2;
+
+pub fn flash_loan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint256,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let flash_loan_fee = match config.flash_loan_fee {
+        Some(flash_loan_fee) => flash_loan_fee,
+        None => return Err(ContractError::FlashLoanDisabled {}),
+    };
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroFlashLoan(config.stable_denom));
+    }
+
+    // Accrue interest with the balance before lending, so
+    // the loan does not affect the rates of this block
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        None,
+    )?;
+    store_state(deps.storage, &state)?;
+
+    let prev_balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+
+    if Decimal256::from_uint256(amount) + state.total_reserves + queued_redemptions(&state)
+        > Decimal256::from_uint256(prev_balance)
+    {
+        return Err(ContractError::NoStableAvailable(config.stable_denom));
+    }
+
+    let fee = amount * flash_loan_fee;
+    store_flash_loan(deps.storage, &FlashLoan { prev_balance, fee })?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: info.sender.to_string(),
+                funds: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: config.stable_denom,
+                        amount: amount.into(),
+                    },
+                )?],
+                msg: to_binary(&FlashLoanReceiverMsg::FlashLoanCallback { amount, fee, msg })?,
+            }),
+            FLASH_LOAN_REPLY_ID,
+        ))
+        .add_attributes(vec![
+            attr("action", "flash_loan"),
+            attr("receiver", info.sender),
+            attr("amount", amount),
+            attr("fee", fee),
+        ]))
+}
+
+/// Check the loan came back with the fee after the receiver
+/// callback; everything above the lent amount goes to reserves
+pub fn assert_flash_loan_repaid(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let flash_loan = match read_flash_loan(deps.storage)? {
+        Some(flash_loan) => flash_loan,
+        None => return Err(ContractError::InvalidReplyId {}),
+    };
+
+    let balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+
+    let required_balance = flash_loan.prev_balance + flash_loan.fee;
+    if balance < required_balance {
+        return Err(ContractError::FlashLoanNotRepaid(required_balance.into()));
+    }
+
+    let fee = balance - flash_loan.prev_balance;
+    let mut state: State = read_state(deps.storage)?;
+    state.total_reserves += Decimal256::from_uint256(fee);
+    store_state(deps.storage, &state)?;
+
+    remove_flash_loan(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![attr("action", "flash_loan_repaid"), attr("fee", fee)]))
+}
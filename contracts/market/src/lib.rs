@@ -1,9 +1,17 @@
+pub mod bad_debt;
+pub mod bonding;
 pub mod borrow;
 pub mod contract;
 pub mod deposit;
+pub mod emission;
 pub mod error;
+pub mod fixed_loan;
+pub mod flash_loan;
+pub mod overview;
 pub mod querier;
+pub mod redemption;
 pub mod response;
+pub mod snapshot;
 pub mod state;
 
 #[cfg(test)]
@@ -0,0 +1,80 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Deps, Env, StdResult};
+
+use crate::borrow::{compute_interest, query_market_borrow_rate};
+use crate::deposit::{compute_exchange_rate_raw, queued_redemptions};
+use crate::querier::query_target_deposit_rate;
+use crate::state::{read_config, read_state, Config, State};
+
+use moneymarket::market::MarketOverviewResponse;
+use moneymarket::querier::{query_balance, query_supply};
+
+pub fn query_market_overview(deps: Deps, env: Env) -> StdResult<MarketOverviewResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
+        deps,
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        None,
+    )?;
+
+    let balance = query_balance(deps, env.contract.address, config.stable_denom.to_string())?;
+    let aterra_supply = query_supply(deps, deps.api.addr_humanize(&config.aterra_contract)?)?;
+    let exchange_rate = compute_exchange_rate_raw(&state, aterra_supply, balance);
+
+    let total_liabilities = state.total_liabilities + state.total_fixed_liabilities;
+    let total_deposits = Decimal256::from_uint256(balance) + total_liabilities
+        - state.total_reserves
+        - queued_redemptions(&state);
+    let utilization = if total_deposits.is_zero() {
+        Decimal256::zero()
+    } else {
+        total_liabilities / total_deposits
+    };
+
+    // Interest paid to the depositors per unit, capped
+    // by the target deposit rate like the accrued interest
+    let borrow_rate = query_market_borrow_rate(deps, &config, &state, balance)?;
+    let deposit_rate = if total_deposits.is_zero() {
+        Decimal256::zero()
+    } else {
+        let interest_flow = state.total_liabilities * borrow_rate + state.fixed_interest_flow;
+        let target_deposit_rate =
+            query_target_deposit_rate(deps, deps.api.addr_humanize(&config.overseer_contract)?)?;
+        std::cmp::min(
+            interest_flow * (Decimal256::one() - config.reserve_factor) / total_deposits,
+            target_deposit_rate,
+        )
+    };
+
+    // Same limits as the borrow assertions
+    let max_liabilities = (Decimal256::from_uint256(balance) + total_liabilities
+        - state.total_reserves)
+        * config.max_borrow_factor;
+    let reserved = state.total_reserves + queued_redemptions(&state);
+    let borrow_capacity =
+        if max_liabilities > total_liabilities && Decimal256::from_uint256(balance) > reserved {
+            std::cmp::min(
+                max_liabilities - total_liabilities,
+                Decimal256::from_uint256(balance) - reserved,
+            ) * Uint256::one()
+        } else {
+            Uint256::zero()
+        };
+
+    let units_per_year = Decimal256::from_uint256(config.rate_unit.per_year());
+    Ok(MarketOverviewResponse {
+        stable_liquidity: balance,
+        utilization,
+        borrow_rate,
+        borrow_apr: borrow_rate * units_per_year,
+        deposit_rate,
+        deposit_apr: deposit_rate * units_per_year,
+        exchange_rate,
+        aterra_supply,
+        total_reserves: state.total_reserves,
+        borrow_capacity,
+    })
+}
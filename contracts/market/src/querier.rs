@@ -2,7 +2,9 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
 
 use moneymarket::distribution_model::{AncEmissionRateResponse, QueryMsg as DistributionQueryMsg};
-use moneymarket::interest_model::{BorrowRateResponse, QueryMsg as InterestQueryMsg};
+use moneymarket::interest_model::{
+    BorrowRateResponse, ControllerStateResponse, QueryMsg as InterestQueryMsg,
+};
 use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse, QueryMsg as OverseerQueryMsg};
 
 pub fn query_borrow_rate(
@@ -25,6 +27,19 @@ pub fn query_borrow_rate(
     Ok(borrow_rate)
 }
 
+pub fn query_controller_state(
+    deps: Deps,
+    interest_addr: Addr,
+) -> StdResult<ControllerStateResponse> {
+    let controller_state: ControllerStateResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: interest_addr.to_string(),
+            msg: to_binary(&InterestQueryMsg::ControllerState {})?,
+        }))?;
+
+    Ok(controller_state)
+}
+
 pub fn query_borrow_limit(
     deps: Deps,
     overseer_addr: Addr,
@@ -65,6 +80,19 @@ pub fn query_anc_emission_rate(
     Ok(anc_emission_rate)
 }
 
+pub fn query_depositor_emission_rate(
+    deps: Deps,
+    distribution_model: Addr,
+) -> StdResult<AncEmissionRateResponse> {
+    let depositor_emission_rate: AncEmissionRateResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: distribution_model.to_string(),
+            msg: to_binary(&DistributionQueryMsg::DepositorEmissionRate {})?,
+        }))?;
+
+    Ok(depositor_emission_rate)
+}
+
 pub fn query_target_deposit_rate(deps: Deps, overseer_contract: Addr) -> StdResult<Decimal256> {
     let overseer_config: ConfigResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
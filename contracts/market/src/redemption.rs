@@ -0,0 +1,302 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, Storage, Uint128, WasmMsg,
+};
+
+use crate::borrow::{compute_interest, compute_reward};
+use crate::deposit::compute_exchange_rate;
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_redemption, read_redemption_queue, read_redemptions, read_state,
+    remove_redemption, store_redemption, store_redemption_queue, store_state, Config, Redemption,
+    State,
+};
+
+use cw20::Cw20ExecuteMsg;
+use moneymarket::market::{RedemptionQueueResponse, RedemptionResponse};
+use moneymarket::querier::{deduct_tax, query_balance};
+
+// smallest stable amount which can be queued
+const MIN_REDEMPTION_AMOUNT: u128 = 100000;
+// most redemptions filled by a single call; the rest
+// is filled by the following deposits and repayments
+const MAX_FILLS_PER_CALL: usize = 10;
+
+pub fn queue_redemption(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    burn_amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // Update interest related state
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        None,
+    )?;
+    compute_reward(&config, &mut state, config.rate_unit.now(&env.block));
+
+    // Lock the stable amount at the current exchange rate
+    let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, None)?;
+    let amount = Uint256::from(burn_amount) * exchange_rate;
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRedeem(config.stable_denom));
+    }
+
+    if amount < Uint256::from(MIN_REDEMPTION_AMOUNT) {
+        return Err(ContractError::RedemptionTooSmall(MIN_REDEMPTION_AMOUNT));
+    }
+
+    let mut queue = read_redemption_queue(deps.storage)?;
+    let id = queue.next_id;
+    store_redemption(
+        deps.storage,
+        id,
+        &Redemption {
+            owner: deps.api.addr_canonicalize(sender.as_str())?,
+            amount,
+            filled_amount: Uint256::zero(),
+            offset: queue.queued_total,
+        },
+    )?;
+    queue.next_id += 1;
+    queue.pending_count += 1;
+    queue.queued_total += amount;
+    store_redemption_queue(deps.storage, &queue)?;
+
+    state.prev_aterra_supply = state.prev_aterra_supply - Uint256::from(burn_amount);
+    state.pending_redemptions += amount;
+
+    // Fill right away with the liquidity left, if any
+    let balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+    fill_redemptions(deps.storage, &mut state, balance)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: burn_amount,
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "queue_redemption"),
+            attr("redemption_id", id.to_string()),
+            attr("burn_amount", burn_amount),
+            attr("redeem_amount", amount),
+        ]))
+}
+
+pub fn claim_redemption(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut redemption = load_owned_redemption(deps.as_ref(), &info.sender, id)?;
+
+    let claim_amount = redemption.filled_amount;
+    if claim_amount.is_zero() {
+        return Err(ContractError::NoRedemptionClaimable(id));
+    }
+
+    redemption.amount = redemption.amount - claim_amount;
+    redemption.filled_amount = Uint256::zero();
+    redemption.offset += claim_amount;
+    if redemption.amount.is_zero() {
+        remove_redemption(deps.storage, id);
+    } else {
+        store_redemption(deps.storage, id, &redemption)?;
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    state.claimable_redemptions = state.claimable_redemptions - claim_amount;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom,
+                    amount: claim_amount.into(),
+                },
+            )?],
+        }))
+        .add_attributes(vec![
+            attr("action", "claim_redemption"),
+            attr("redemption_id", id.to_string()),
+            attr("claim_amount", claim_amount),
+        ]))
+}
+
+pub fn cancel_redemption(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let redemption = load_owned_redemption(deps.as_ref(), &info.sender, id)?;
+
+    // Update interest related state
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        config.rate_unit.now(&env.block),
+        None,
+    )?;
+    compute_reward(&config, &mut state, config.rate_unit.now(&env.block));
+
+    // Mint the unfilled part back at the current exchange rate
+    let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, None)?;
+    let unfilled_amount = redemption.amount - redemption.filled_amount;
+    let mint_amount = unfilled_amount / exchange_rate;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !mint_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.aterra_contract)?.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: info.sender.to_string(),
+                amount: mint_amount.into(),
+            })?,
+        }));
+    }
+
+    if !redemption.filled_amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom,
+                    amount: redemption.filled_amount.into(),
+                },
+            )?],
+        }));
+    }
+
+    if !unfilled_amount.is_zero() {
+        let mut queue = read_redemption_queue(deps.storage)?;
+        queue.pending_count -= 1;
+        store_redemption_queue(deps.storage, &queue)?;
+    }
+    remove_redemption(deps.storage, id);
+
+    state.prev_aterra_supply += mint_amount;
+    state.pending_redemptions = state.pending_redemptions - unfilled_amount;
+    state.claimable_redemptions = state.claimable_redemptions - redemption.filled_amount;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "cancel_redemption"),
+        attr("redemption_id", id.to_string()),
+        attr("mint_amount", mint_amount),
+        attr("claim_amount", redemption.filled_amount),
+    ]))
+}
+
+/// Set aside the liquidity over reserves for queued
+/// redemptions, oldest first and at most
+/// `MAX_FILLS_PER_CALL` of them
+pub(crate) fn fill_redemptions(
+    storage: &mut dyn Storage,
+    state: &mut State,
+    balance: Uint256,
+) -> StdResult<()> {
+    let reserved = state.total_reserves + Decimal256::from_uint256(state.claimable_redemptions);
+    if state.pending_redemptions.is_zero() || Decimal256::from_uint256(balance) <= reserved {
+        return Ok(());
+    }
+
+    let mut available = (Decimal256::from_uint256(balance) - reserved) * Uint256::one();
+    let mut queue = read_redemption_queue(storage)?;
+    for (id, mut redemption) in read_redemptions(storage, queue.head_id, MAX_FILLS_PER_CALL)? {
+        if available.is_zero() {
+            break;
+        }
+
+        let fill_amount = std::cmp::min(available, redemption.amount - redemption.filled_amount);
+        redemption.filled_amount += fill_amount;
+        store_redemption(storage, id, &redemption)?;
+
+        // Skips the amounts of cancelled redemptions too
+        queue.filled_total = redemption.offset + redemption.filled_amount;
+
+        available = available - fill_amount;
+        state.pending_redemptions = state.pending_redemptions - fill_amount;
+        state.claimable_redemptions += fill_amount;
+
+        if redemption.filled_amount == redemption.amount {
+            queue.head_id = id + 1;
+            queue.pending_count -= 1;
+        } else {
+            queue.head_id = id;
+        }
+    }
+
+    store_redemption_queue(storage, &queue)
+}
+
+pub fn query_redemption_queue(deps: Deps) -> StdResult<RedemptionQueueResponse> {
+    let state: State = read_state(deps.storage)?;
+    let queue = read_redemption_queue(deps.storage)?;
+
+    Ok(RedemptionQueueResponse {
+        head_id: queue.head_id,
+        pending_count: queue.pending_count,
+        pending_amount: state.pending_redemptions,
+        claimable_amount: state.claimable_redemptions,
+    })
+}
+
+pub fn query_redemption(deps: Deps, id: u64) -> StdResult<RedemptionResponse> {
+    let redemption = match read_redemption(deps.storage, id)? {
+        Some(redemption) => redemption,
+        None => return Err(StdError::not_found("redemption")),
+    };
+
+    // Cancelled redemptions ahead are counted until the fills pass them
+    let queue = read_redemption_queue(deps.storage)?;
+    let position = id.saturating_sub(queue.head_id);
+    let amount_ahead = if redemption.offset > queue.filled_total {
+        redemption.offset - queue.filled_total
+    } else {
+        Uint256::zero()
+    };
+
+    Ok(RedemptionResponse {
+        id,
+        owner: deps.api.addr_humanize(&redemption.owner)?.to_string(),
+        amount: redemption.amount,
+        filled_amount: redemption.filled_amount,
+        position,
+        amount_ahead,
+    })
+}
+
+fn load_owned_redemption(deps: Deps, owner: &Addr, id: u64) -> Result<Redemption, ContractError> {
+    match read_redemption(deps.storage, id)? {
+        Some(redemption) if redemption.owner == deps.api.addr_canonicalize(owner.as_str())? => {
+            Ok(redemption)
+        }
+        Some(_) => Err(ContractError::Unauthorized {}),
+        None => Err(ContractError::RedemptionNotFound(id)),
+    }
+}
@@ -0,0 +1,86 @@
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{Deps, StdError, StdResult};
+
+use crate::state::{read_snapshot, read_snapshot_count, SNAPSHOT_CAPACITY};
+use moneymarket::common::SECONDS_PER_YEAR;
+use moneymarket::market::{ApyResponse, SnapshotResponse, SnapshotsResponse};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn query_snapshots(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SnapshotsResponse> {
+    let count = read_snapshot_count(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
+
+    // Snapshots older than the capacity are overwritten
+    let mut start = count.saturating_sub(SNAPSHOT_CAPACITY);
+    if let Some(start_after) = start_after {
+        start = start.max(start_after + 1);
+    }
+
+    let snapshots = (start..count.min(start + limit))
+        .map(|id| {
+            let snapshot = read_snapshot(deps.storage, id)?;
+            Ok(SnapshotResponse {
+                id,
+                height: snapshot.height,
+                time: snapshot.time,
+                exchange_rate: snapshot.exchange_rate,
+                borrow_rate: snapshot.borrow_rate,
+            })
+        })
+        .collect::<StdResult<Vec<SnapshotResponse>>>()?;
+
+    Ok(SnapshotsResponse { snapshots })
+}
+
+pub fn query_apy(deps: Deps, window: u64) -> StdResult<ApyResponse> {
+    let count = read_snapshot_count(deps.storage)?;
+    if count < 2 {
+        return Err(StdError::generic_err("Not enough snapshots"));
+    }
+
+    let last = read_snapshot(deps.storage, count - 1)?;
+    let earliest_id = count.saturating_sub(SNAPSHOT_CAPACITY);
+
+    // Walk back to the oldest snapshot of the window and
+    // accumulate the borrow rate over each epoch on the way
+    let mut first = last.clone();
+    let mut borrow_rate_accrued = Decimal256::zero();
+    let mut id = count - 1;
+    while id > earliest_id {
+        let prev = read_snapshot(deps.storage, id - 1)?;
+        if last.time.saturating_sub(window) > prev.time {
+            break;
+        }
+
+        borrow_rate_accrued +=
+            prev.borrow_rate * Decimal256::from_uint256(first.height - prev.height);
+        first = prev;
+        id -= 1;
+    }
+
+    let elapsed = last.time - first.time;
+    if elapsed == 0 {
+        return Err(StdError::generic_err("Not enough snapshots in the window"));
+    }
+
+    let year_factor = Decimal256::from_ratio(SECONDS_PER_YEAR, elapsed);
+    let deposit_rate_accrued = if last.exchange_rate > first.exchange_rate {
+        last.exchange_rate / first.exchange_rate - Decimal256::one()
+    } else {
+        Decimal256::zero()
+    };
+
+    Ok(ApyResponse {
+        from_time: first.time,
+        to_time: last.time,
+        deposit_apy: deposit_rate_accrued * year_factor,
+        borrow_apy: borrow_rate_accrued * year_factor,
+    })
+}
@@ -5,12 +5,34 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{CanonicalAddr, Deps, Order, StdResult, Storage};
 use cosmwasm_storage::{bucket, bucket_read, ReadonlyBucket, ReadonlySingleton, Singleton};
 
-use moneymarket::market::BorrowerInfoResponse;
+use moneymarket::common::RateUnit;
+use moneymarket::market::{BorrowerInfoResponse, FixedRateConfig};
 
 pub const KEY_CONFIG: &[u8] = b"config";
 pub const KEY_STATE: &[u8] = b"state";
+pub const KEY_CONTRACT_VERSION: &[u8] = b"contract_version";
+const KEY_FLASH_LOAN: &[u8] = b"flash_loan";
+const KEY_REDEMPTION_QUEUE: &[u8] = b"redemption_queue";
+const KEY_SNAPSHOT_COUNT: &[u8] = b"snapshot_count";
+const KEY_BAD_DEBT_COUNT: &[u8] = b"bad_debt_count";
 
-const PREFIX_LIABILITY: &[u8] = b"liability";
+pub const PREFIX_LIABILITY: &[u8] = b"liability";
+const PREFIX_FIXED_LIABILITY: &[u8] = b"fixed_liability";
+const PREFIX_BORROW_CAP: &[u8] = b"borrow_cap";
+const PREFIX_REDEMPTION: &[u8] = b"redemption";
+const PREFIX_SNAPSHOT: &[u8] = b"snapshot";
+const PREFIX_DEPOSITOR: &[u8] = b"depositor";
+const PREFIX_BAD_DEBT: &[u8] = b"bad_debt";
+const PREFIX_LOAN_SIZE: &[u8] = b"loan_size";
+const PREFIX_LOAN_SIZE_INDEX: &[u8] = b"loan_size_index";
+
+/// Version of the storage layout; contracts instantiated
+/// before it was stored have the legacy config and state
+pub const CONTRACT_VERSION: u64 = 1;
+
+/// Number of snapshots kept, the oldest
+/// one is overwritten by each new snapshot
+pub const SNAPSHOT_CAPACITY: u64 = 512;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -24,8 +46,45 @@ pub struct Config {
     pub distributor_contract: CanonicalAddr,
     pub stable_denom: String,
     pub max_borrow_factor: Decimal256,
+    pub rate_unit: RateUnit,
+    pub flash_loan_fee: Option<Decimal256>,
+    pub fixed_rate: Option<FixedRateConfig>,
+    /// Limit of the total liabilities, variable and fixed rate
+    pub debt_ceiling: Option<Uint256>,
+    /// Share of the accrued interest added to the reserves
+    pub reserve_factor: Decimal256,
+    /// Receives `insurance_share` of the reserves swept every
+    /// epoch, the collector contract receives the rest
+    pub insurance_contract: Option<CanonicalAddr>,
+    pub insurance_share: Decimal256,
+    /// Share of the borrowed amount charged upfront on
+    /// variable and fixed rate borrows, added to the reserves
+    pub origination_fee: Decimal256,
+    /// Limit of the ANC emitted to the borrowers
+    /// and the bonded aTerra over the market life
+    pub emission_budget: Option<Uint256>,
+    /// Limit of the ANC emitted between two epoch operations
+    pub epoch_emission_cap: Option<Uint256>,
+}
+
+/// Config layout of the previous contract version,
+/// only used to migrate the stored config
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub contract_addr: CanonicalAddr,
+    pub owner_addr: CanonicalAddr,
+    pub aterra_contract: CanonicalAddr,
+    pub interest_model: CanonicalAddr,
+    pub distribution_model: CanonicalAddr,
+    pub overseer_contract: CanonicalAddr,
+    pub collector_contract: CanonicalAddr,
+    pub distributor_contract: CanonicalAddr,
+    pub stable_denom: String,
+    pub max_borrow_factor: Decimal256,
 }
 
+/// `last_interest_updated` and `last_reward_updated` are
+/// measured in the config `rate_unit`
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub total_liabilities: Decimal256,
@@ -37,6 +96,112 @@ pub struct State {
     pub anc_emission_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
+    /// Stable owed to queued redemptions not yet filled
+    pub pending_redemptions: Uint256,
+    /// Stable set aside for filled redemptions not yet claimed
+    pub claimable_redemptions: Uint256,
+    pub total_fixed_liabilities: Decimal256,
+    /// Interest accrued per rate unit by all fixed rate loans,
+    /// the sum of their loan amounts times their rates
+    pub fixed_interest_flow: Decimal256,
+    pub total_bonded_aterra: Uint256,
+    /// ANC reward per bonded aTerra, updated with `last_reward_updated`
+    pub depositor_reward_index: Decimal256,
+    pub depositor_emission_rate: Decimal256,
+    /// ANC added to the reward indexes, claimed or not
+    pub total_emitted_anc: Decimal256,
+    /// ANC added to the reward indexes since the last epoch operations
+    pub epoch_emitted_anc: Decimal256,
+    /// ANC sent from the distributor to the claimers
+    pub total_claimed_anc: Uint256,
+}
+
+/// State layout of the previous contract version,
+/// only used to migrate the stored state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyState {
+    pub total_liabilities: Decimal256,
+    pub total_reserves: Decimal256,
+    pub last_interest_updated: u64,
+    pub last_reward_updated: u64,
+    pub global_interest_index: Decimal256,
+    pub global_reward_index: Decimal256,
+    pub anc_emission_rate: Decimal256,
+    pub prev_aterra_supply: Uint256,
+    pub prev_exchange_rate: Decimal256,
+}
+
+/// Redemption queue pointers; `head_id` is the oldest
+/// redemption that is not completely filled yet.
+/// `queued_total` is the stable amount ever queued and
+/// `filled_total` the point of it the fills have reached
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionQueue {
+    pub head_id: u64,
+    pub next_id: u64,
+    pub pending_count: u64,
+    pub queued_total: Uint256,
+    pub filled_total: Uint256,
+}
+
+/// `amount` is the stable owed at the exchange rate of the
+/// time the aTerra was locked, `filled_amount` the part of it
+/// which can already be claimed and `offset` the point of
+/// the queue where the unclaimed amount starts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Redemption {
+    pub owner: CanonicalAddr,
+    pub amount: Uint256,
+    pub filled_amount: Uint256,
+    pub offset: Uint256,
+}
+
+/// Flash loan in progress, removed once it is repaid
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoan {
+    pub prev_balance: Uint256,
+    pub fee: Uint256,
+}
+
+/// Fixed rate loan of a borrower; `loan_amount` includes the
+/// interest accrued until `last_interest_updated`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FixedLoan {
+    pub loan_amount: Uint256,
+    pub rate: Decimal256,
+    pub last_interest_updated: u64,
+}
+
+/// Market rates recorded at an epoch operation;
+/// `height` is measured in the config `rate_unit`
+/// and `borrow_rate` is the variable borrow rate per unit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Snapshot {
+    pub height: u64,
+    pub time: u64,
+    pub exchange_rate: Decimal256,
+    pub borrow_rate: Decimal256,
+}
+
+/// Loan written off after the borrower ran out of collateral;
+/// `amount` is split into the parts covered by the overseer
+/// interest buffer, the reserves and the depositors
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebt {
+    pub borrower: CanonicalAddr,
+    pub height: u64,
+    pub amount: Uint256,
+    pub buffer_amount: Uint256,
+    pub reserve_amount: Uint256,
+    pub loss_amount: Uint256,
+}
+
+/// aTerra bonded by a depositor to earn ANC rewards
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositorInfo {
+    pub bonded_amount: Uint256,
+    pub reward_index: Decimal256,
+    pub pending_rewards: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -55,6 +220,10 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
+pub fn read_legacy_config(storage: &dyn Storage) -> StdResult<LegacyConfig> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
 pub fn store_state(storage: &mut dyn Storage, data: &State) -> StdResult<()> {
     Singleton::new(storage, KEY_STATE).save(data)
 }
@@ -63,6 +232,127 @@ pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
     ReadonlySingleton::new(storage, KEY_STATE).load()
 }
 
+pub fn read_legacy_state(storage: &dyn Storage) -> StdResult<LegacyState> {
+    ReadonlySingleton::new(storage, KEY_STATE).load()
+}
+
+pub fn store_contract_version(storage: &mut dyn Storage, version: u64) -> StdResult<()> {
+    Singleton::new(storage, KEY_CONTRACT_VERSION).save(&version)
+}
+
+pub fn read_contract_version(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    ReadonlySingleton::new(storage, KEY_CONTRACT_VERSION).may_load()
+}
+
+pub fn store_flash_loan(storage: &mut dyn Storage, data: &FlashLoan) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_LOAN).save(data)
+}
+
+pub fn read_flash_loan(storage: &dyn Storage) -> StdResult<Option<FlashLoan>> {
+    ReadonlySingleton::new(storage, KEY_FLASH_LOAN).may_load()
+}
+
+pub fn remove_flash_loan(storage: &mut dyn Storage) {
+    Singleton::<FlashLoan>::new(storage, KEY_FLASH_LOAN).remove()
+}
+
+pub fn store_redemption_queue(storage: &mut dyn Storage, data: &RedemptionQueue) -> StdResult<()> {
+    Singleton::new(storage, KEY_REDEMPTION_QUEUE).save(data)
+}
+
+pub fn read_redemption_queue(storage: &dyn Storage) -> StdResult<RedemptionQueue> {
+    ReadonlySingleton::new(storage, KEY_REDEMPTION_QUEUE)
+        .may_load()
+        .map(|queue| {
+            queue.unwrap_or(RedemptionQueue {
+                head_id: 1,
+                next_id: 1,
+                pending_count: 0,
+                queued_total: Uint256::zero(),
+                filled_total: Uint256::zero(),
+            })
+        })
+}
+
+pub fn store_redemption(
+    storage: &mut dyn Storage,
+    id: u64,
+    redemption: &Redemption,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_REDEMPTION).save(&id.to_be_bytes(), redemption)
+}
+
+pub fn read_redemption(storage: &dyn Storage, id: u64) -> StdResult<Option<Redemption>> {
+    bucket_read(storage, PREFIX_REDEMPTION).may_load(&id.to_be_bytes())
+}
+
+pub fn remove_redemption(storage: &mut dyn Storage, id: u64) {
+    bucket::<Redemption>(storage, PREFIX_REDEMPTION).remove(&id.to_be_bytes())
+}
+
+/// Queued redemptions from `start_id` in queue order
+pub fn read_redemptions(
+    storage: &dyn Storage,
+    start_id: u64,
+    limit: usize,
+) -> StdResult<Vec<(u64, Redemption)>> {
+    let redemption_bucket: ReadonlyBucket<Redemption> = bucket_read(storage, PREFIX_REDEMPTION);
+
+    redemption_bucket
+        .range(Some(&start_id.to_be_bytes()), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(id), v))
+        })
+        .collect()
+}
+
+/// Store the snapshot in the ring buffer
+/// under the next id and return it
+pub fn store_snapshot(storage: &mut dyn Storage, snapshot: &Snapshot) -> StdResult<u64> {
+    let id = read_snapshot_count(storage)?;
+    bucket(storage, PREFIX_SNAPSHOT).save(&(id % SNAPSHOT_CAPACITY).to_be_bytes(), snapshot)?;
+    Singleton::new(storage, KEY_SNAPSHOT_COUNT).save(&(id + 1))?;
+
+    Ok(id)
+}
+
+/// Number of snapshots ever stored, which is also the next id
+pub fn read_snapshot_count(storage: &dyn Storage) -> StdResult<u64> {
+    ReadonlySingleton::new(storage, KEY_SNAPSHOT_COUNT)
+        .may_load()
+        .map(|count| count.unwrap_or(0))
+}
+
+/// The caller must make sure the snapshot
+/// has not been overwritten yet
+pub fn read_snapshot(storage: &dyn Storage, id: u64) -> StdResult<Snapshot> {
+    bucket_read(storage, PREFIX_SNAPSHOT).load(&(id % SNAPSHOT_CAPACITY).to_be_bytes())
+}
+
+/// Store the bad debt under the next id and return it
+pub fn store_bad_debt(storage: &mut dyn Storage, bad_debt: &BadDebt) -> StdResult<u64> {
+    let id = read_bad_debt_count(storage)?;
+    bucket(storage, PREFIX_BAD_DEBT).save(&id.to_be_bytes(), bad_debt)?;
+    Singleton::new(storage, KEY_BAD_DEBT_COUNT).save(&(id + 1))?;
+
+    Ok(id)
+}
+
+/// Number of bad debts ever written off, which is also the next id
+pub fn read_bad_debt_count(storage: &dyn Storage) -> StdResult<u64> {
+    ReadonlySingleton::new(storage, KEY_BAD_DEBT_COUNT)
+        .may_load()
+        .map(|count| count.unwrap_or(0))
+}
+
+pub fn read_bad_debt(storage: &dyn Storage, id: u64) -> StdResult<BadDebt> {
+    bucket_read(storage, PREFIX_BAD_DEBT).load(&id.to_be_bytes())
+}
+
 pub fn store_borrower_info(
     storage: &mut dyn Storage,
     borrower: &CanonicalAddr,
@@ -83,6 +373,158 @@ pub fn read_borrower_info(storage: &dyn Storage, borrower: &CanonicalAddr) -> Bo
     }
 }
 
+pub fn store_depositor_info(
+    storage: &mut dyn Storage,
+    depositor: &CanonicalAddr,
+    depositor_info: &DepositorInfo,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_DEPOSITOR).save(depositor.as_slice(), depositor_info)
+}
+
+pub fn read_depositor_info(storage: &dyn Storage, depositor: &CanonicalAddr) -> DepositorInfo {
+    match bucket_read(storage, PREFIX_DEPOSITOR).load(depositor.as_slice()) {
+        Ok(v) => v,
+        _ => DepositorInfo {
+            bonded_amount: Uint256::zero(),
+            reward_index: Decimal256::zero(),
+            pending_rewards: Decimal256::zero(),
+        },
+    }
+}
+
+pub fn store_fixed_loan(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    fixed_loan: &FixedLoan,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_FIXED_LIABILITY).save(borrower.as_slice(), fixed_loan)
+}
+
+pub fn read_fixed_loan(storage: &dyn Storage, borrower: &CanonicalAddr) -> Option<FixedLoan> {
+    bucket_read(storage, PREFIX_FIXED_LIABILITY)
+        .load(borrower.as_slice())
+        .ok()
+}
+
+pub fn remove_fixed_loan(storage: &mut dyn Storage, borrower: &CanonicalAddr) {
+    bucket::<FixedLoan>(storage, PREFIX_FIXED_LIABILITY).remove(borrower.as_slice())
+}
+
+/// Keep the loan size index in line with the loans stored for the
+/// borrower, once they are stored. Loan sizes are measured in units
+/// of the global interest index: the variable rate loan is divided by
+/// the index of its last update and the fixed rate loan by the current
+/// `global_interest_index`, so borrowers compare by their accrued loans
+pub fn store_loan_size(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    global_interest_index: Decimal256,
+) -> StdResult<()> {
+    let liability = read_borrower_info(storage, borrower);
+    let fixed_loan_amount = read_fixed_loan(storage, borrower)
+        .map(|fixed_loan| fixed_loan.loan_amount)
+        .unwrap_or_else(Uint256::zero);
+    let loan_size = liability.loan_amount / liability.interest_index
+        + fixed_loan_amount / global_interest_index;
+
+    let prev_loan_size = read_loan_size(storage, borrower);
+    if prev_loan_size == Some(loan_size) {
+        return Ok(());
+    }
+
+    if let Some(prev_loan_size) = prev_loan_size {
+        bucket::<CanonicalAddr>(storage, PREFIX_LOAN_SIZE_INDEX)
+            .remove(&loan_size_key(prev_loan_size, borrower));
+    }
+
+    if loan_size.is_zero() {
+        bucket::<Uint256>(storage, PREFIX_LOAN_SIZE).remove(borrower.as_slice());
+        return Ok(());
+    }
+
+    bucket(storage, PREFIX_LOAN_SIZE_INDEX).save(&loan_size_key(loan_size, borrower), borrower)?;
+    bucket(storage, PREFIX_LOAN_SIZE).save(borrower.as_slice(), &loan_size)
+}
+
+fn read_loan_size(storage: &dyn Storage, borrower: &CanonicalAddr) -> Option<Uint256> {
+    bucket_read(storage, PREFIX_LOAN_SIZE)
+        .load(borrower.as_slice())
+        .ok()
+}
+
+// loan size in 32 bytes big endian, so the keys
+// sort by size, then the borrower
+fn loan_size_key(loan_size: Uint256, borrower: &CanonicalAddr) -> Vec<u8> {
+    let mut key = vec![0u8; 32];
+    loan_size.0.to_big_endian(&mut key);
+    key.extend_from_slice(borrower.as_slice());
+    key
+}
+
+/// Borrowers ordered by the principal of their loans,
+/// largest first, after `start_after` if given
+pub fn read_largest_borrowers(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<CanonicalAddr>> {
+    let index_bucket: ReadonlyBucket<CanonicalAddr> = bucket_read(storage, PREFIX_LOAN_SIZE_INDEX);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let end = match start_after {
+        Some(start_after) => match read_loan_size(storage, &start_after) {
+            Some(loan_size) => Some(loan_size_key(loan_size, &start_after)),
+            None => return Ok(vec![]),
+        },
+        None => None,
+    };
+
+    index_bucket
+        .range(None, end.as_deref(), Order::Descending)
+        .take(limit)
+        .map(|elem| Ok(elem?.1))
+        .collect()
+}
+
+/// Stored borrower infos in address order, after `start_after` if given
+pub fn read_borrower_info_range(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(CanonicalAddr, BorrowerInfo)>> {
+    let liability_bucket: ReadonlyBucket<BorrowerInfo> = bucket_read(storage, PREFIX_LIABILITY);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    liability_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect()
+}
+
+pub fn store_borrow_cap(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    borrow_cap: &Uint256,
+) -> StdResult<()> {
+    bucket(storage, PREFIX_BORROW_CAP).save(borrower.as_slice(), borrow_cap)
+}
+
+pub fn read_borrow_cap(storage: &dyn Storage, borrower: &CanonicalAddr) -> Option<Uint256> {
+    bucket_read(storage, PREFIX_BORROW_CAP)
+        .load(borrower.as_slice())
+        .ok()
+}
+
+pub fn remove_borrow_cap(storage: &mut dyn Storage, borrower: &CanonicalAddr) {
+    bucket::<Uint256>(storage, PREFIX_BORROW_CAP).remove(borrower.as_slice())
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -102,13 +544,19 @@ pub fn read_borrower_infos(
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let borrower = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
+            let borrower = CanonicalAddr::from(k);
+            let fixed_loan = read_fixed_loan(deps.storage, &borrower);
             Ok(BorrowerInfoResponse {
-                borrower,
+                borrower: deps.api.addr_humanize(&borrower)?.to_string(),
                 interest_index: v.interest_index,
                 reward_index: v.reward_index,
                 loan_amount: v.loan_amount,
                 pending_rewards: v.pending_rewards,
+                fixed_loan_amount: fixed_loan
+                    .as_ref()
+                    .map(|l| l.loan_amount)
+                    .unwrap_or_else(Uint256::zero),
+                fixed_rate: fixed_loan.map(|l| l.rate).unwrap_or_else(Decimal256::zero),
             })
         })
         .collect()
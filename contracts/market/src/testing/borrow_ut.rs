@@ -4,6 +4,7 @@ use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{Api, Coin, Uint128};
+use moneymarket::common::RateUnit;
 
 #[test]
 fn proper_compute_borrower_interest() {
@@ -18,6 +19,16 @@ fn proper_compute_borrower_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        pending_redemptions: Uint256::zero(),
+        claimable_redemptions: Uint256::zero(),
+        total_fixed_liabilities: Decimal256::zero(),
+        fixed_interest_flow: Decimal256::zero(),
+        total_bonded_aterra: Uint256::zero(),
+        depositor_reward_index: Decimal256::zero(),
+        depositor_emission_rate: Decimal256::zero(),
+        total_emitted_anc: Decimal256::zero(),
+        epoch_emitted_anc: Decimal256::zero(),
+        total_claimed_anc: Uint256::zero(),
     };
     let mut liability1 = BorrowerInfo {
         interest_index: Decimal256::one(),
@@ -44,6 +55,16 @@ fn proper_compute_borrower_interest() {
         anc_emission_rate: Decimal256::zero(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        pending_redemptions: Uint256::zero(),
+        claimable_redemptions: Uint256::zero(),
+        total_fixed_liabilities: Decimal256::zero(),
+        fixed_interest_flow: Decimal256::zero(),
+        total_bonded_aterra: Uint256::zero(),
+        depositor_reward_index: Decimal256::zero(),
+        depositor_emission_rate: Decimal256::zero(),
+        total_emitted_anc: Decimal256::zero(),
+        epoch_emitted_anc: Decimal256::zero(),
+        total_claimed_anc: Uint256::zero(),
     };
     let mut liability3 = BorrowerInfo {
         interest_index: Decimal256::from_uint256(4u128),
@@ -86,6 +107,16 @@ fn proper_compute_interest() {
         overseer_contract: deps.api.addr_canonicalize("overseer").unwrap(),
         stable_denom: "uusd".to_string(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: RateUnit::Block,
+        flash_loan_fee: None,
+        fixed_rate: None,
+        debt_ceiling: None,
+        reserve_factor: Decimal256::zero(),
+        insurance_contract: None,
+        insurance_share: Decimal256::zero(),
+        origination_fee: Decimal256::zero(),
+        emission_budget: None,
+        epoch_emission_cap: None,
     };
 
     deps.querier
@@ -101,6 +132,16 @@ fn proper_compute_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        pending_redemptions: Uint256::zero(),
+        claimable_redemptions: Uint256::zero(),
+        total_fixed_liabilities: Decimal256::zero(),
+        fixed_interest_flow: Decimal256::zero(),
+        total_bonded_aterra: Uint256::zero(),
+        depositor_reward_index: Decimal256::zero(),
+        depositor_emission_rate: Decimal256::zero(),
+        total_emitted_anc: Decimal256::zero(),
+        epoch_emitted_anc: Decimal256::zero(),
+        total_claimed_anc: Uint256::zero(),
     };
     store_state(&mut deps.storage, &mock_state).unwrap();
 
@@ -126,6 +167,16 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         }
     );
 
@@ -151,6 +202,16 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::from_ratio(19995, 10000),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         }
     );
 
@@ -165,6 +226,16 @@ fn proper_compute_interest() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::from(2000000u128),
         prev_exchange_rate: Decimal256::one(),
+        pending_redemptions: Uint256::zero(),
+        claimable_redemptions: Uint256::zero(),
+        total_fixed_liabilities: Decimal256::zero(),
+        fixed_interest_flow: Decimal256::zero(),
+        total_bonded_aterra: Uint256::zero(),
+        depositor_reward_index: Decimal256::zero(),
+        depositor_emission_rate: Decimal256::zero(),
+        total_emitted_anc: Decimal256::zero(),
+        epoch_emitted_anc: Decimal256::zero(),
+        total_claimed_anc: Uint256::zero(),
     };
     store_state(&mut deps.storage, &mock_state).unwrap();
 
@@ -200,6 +271,16 @@ fn proper_compute_interest() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::from_uint256(2u64),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         }
     );
 }
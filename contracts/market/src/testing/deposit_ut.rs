@@ -4,6 +4,7 @@ use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{Api, Coin, Uint128};
+use moneymarket::common::RateUnit;
 
 #[test]
 fn proper_compute_exchange_rate() {
@@ -24,6 +25,16 @@ fn proper_compute_exchange_rate() {
         overseer_contract: deps.api.addr_canonicalize("overseer").unwrap(),
         stable_denom: "uusd".to_string(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: RateUnit::Block,
+        flash_loan_fee: None,
+        fixed_rate: None,
+        debt_ceiling: None,
+        reserve_factor: Decimal256::zero(),
+        insurance_contract: None,
+        insurance_share: Decimal256::zero(),
+        origination_fee: Decimal256::zero(),
+        emission_budget: None,
+        epoch_emission_cap: None,
     };
     deps.querier.with_token_balances(&[(
         &"AT-uusd".to_string(),
@@ -40,6 +51,16 @@ fn proper_compute_exchange_rate() {
         anc_emission_rate: Decimal256::one(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
+        pending_redemptions: Uint256::zero(),
+        claimable_redemptions: Uint256::zero(),
+        total_fixed_liabilities: Decimal256::zero(),
+        fixed_interest_flow: Decimal256::zero(),
+        total_bonded_aterra: Uint256::zero(),
+        depositor_reward_index: Decimal256::zero(),
+        depositor_emission_rate: Decimal256::zero(),
+        total_emitted_anc: Decimal256::zero(),
+        epoch_emitted_anc: Decimal256::zero(),
+        total_claimed_anc: Uint256::zero(),
     };
     let mock_deposit_amount = Some(Uint256::from(1000000u128));
 
@@ -11,8 +11,9 @@ use cosmwasm_storage::to_length_prefixed;
 use std::collections::HashMap;
 
 use cw20::TokenInfoResponse;
+use moneymarket::common::RateUnit;
 use moneymarket::distribution_model::AncEmissionRateResponse;
-use moneymarket::interest_model::BorrowRateResponse;
+use moneymarket::interest_model::{BorrowRateResponse, ControllerStateResponse};
 use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse};
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
@@ -25,6 +26,8 @@ pub enum QueryMsg {
         total_liabilities: Decimal256,
         total_reserves: Decimal256,
     },
+    /// Query controller state to interest model contract
+    ControllerState {},
     /// Query borrow limit to overseer contract
     BorrowLimit {
         borrower: String,
@@ -37,6 +40,8 @@ pub enum QueryMsg {
         threshold_deposit_rate: Decimal256,
         current_emission_rate: Decimal256,
     },
+    /// Query depositor ANC emission rate to distribution model contract
+    DepositorEmissionRate {},
     /// Query overseer config to get target deposit rate
     Config {},
     /// Query cw20 Token Info
@@ -64,6 +69,7 @@ pub struct WasmMockQuerier {
     tax_querier: TaxQuerier,
     borrow_rate_querier: BorrowRateQuerier,
     borrow_limit_querier: BorrowLimitQuerier,
+    controller_state: Option<ControllerStateResponse>,
 }
 
 #[derive(Clone, Default)]
@@ -228,6 +234,13 @@ impl WasmMockQuerier {
                             }),
                         }
                     }
+                    QueryMsg::ControllerState {} => match &self.controller_state {
+                        Some(v) => SystemResult::Ok(ContractResult::from(to_binary(v))),
+                        None => SystemResult::Err(SystemError::InvalidRequest {
+                            error: "No controller exists".to_string(),
+                            request: msg.as_slice().into(),
+                        }),
+                    },
                     QueryMsg::BorrowLimit {
                         borrower,
                         block_time: _,
@@ -253,6 +266,11 @@ impl WasmMockQuerier {
                             emission_rate: Decimal256::from_uint256(5u64),
                         },
                     ))),
+                    QueryMsg::DepositorEmissionRate {} => SystemResult::Ok(ContractResult::from(
+                        to_binary(&AncEmissionRateResponse {
+                            emission_rate: Decimal256::one(),
+                        }),
+                    )),
                     QueryMsg::Config {} => {
                         SystemResult::Ok(ContractResult::from(to_binary(&ConfigResponse {
                             owner_addr: "".to_string(),
@@ -267,6 +285,7 @@ impl WasmMockQuerier {
                             stable_denom: "uusd".to_string(),
                             epoch_period: 100u64,
                             price_timeframe: 100u64,
+                            rate_unit: RateUnit::Block,
                         })))
                     }
                     QueryMsg::TokenInfo {} => {
@@ -342,6 +361,7 @@ impl WasmMockQuerier {
             tax_querier: TaxQuerier::default(),
             borrow_rate_querier: BorrowRateQuerier::default(),
             borrow_limit_querier: BorrowLimitQuerier::default(),
+            controller_state: None,
         }
     }
 
@@ -371,4 +391,8 @@ impl WasmMockQuerier {
     pub fn with_borrow_limit(&mut self, borrow_limit: &[(&String, &Uint256)]) {
         self.borrow_limit_querier = BorrowLimitQuerier::new(borrow_limit);
     }
+
+    pub fn with_controller_state(&mut self, controller_state: Option<ControllerStateResponse>) {
+        self.controller_state = controller_state;
+    }
 }
@@ -1,19 +1,36 @@
-use crate::contract::{execute, instantiate, query, reply, INITIAL_DEPOSIT_AMOUNT};
+use crate::borrow::compute_interest_raw;
+use crate::contract::{
+    execute, instantiate, migrate, query, reply, EPOCH_OPERATIONS_REPLY_ID, INITIAL_DEPOSIT_AMOUNT,
+};
 use crate::error::ContractError;
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::{read_borrower_infos, read_state, store_state, State};
-use crate::testing::mock_querier::mock_dependencies;
+use crate::state::{
+    read_borrower_infos, read_config, read_largest_borrowers, read_state, store_borrower_info,
+    store_config, store_loan_size, store_state, BorrowerInfo, LegacyConfig, LegacyState, State,
+    KEY_CONFIG, KEY_CONTRACT_VERSION, KEY_STATE, PREFIX_LIABILITY,
+};
+use crate::testing::mock_querier::{mock_dependencies, WasmMockQuerier};
 
 use anchor_token::distributor::ExecuteMsg as FaucetExecuteMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, BankMsg, Coin, ContractResult, CosmosMsg, Decimal, Reply,
-    SubMsg, SubMsgExecutionResponse, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, Api, BankMsg, Binary, Coin, ContractResult, CosmosMsg,
+    Decimal, Deps, DepsMut, Env, OwnedDeps, Reply, StdError, SubMsg, SubMsgExecutionResponse,
+    Uint128, WasmMsg,
 };
+use cosmwasm_storage::{Bucket, Singleton};
 use cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use moneymarket::common::RateUnit;
+use moneymarket::distribution_model::ExecuteMsg as DistributionModelExecuteMsg;
+use moneymarket::interest_model::{ControllerStateResponse, ExecuteMsg as InterestModelExecuteMsg};
 use moneymarket::market::{
-    BorrowerInfoResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
+    ApyResponse, BadDebtResponse, BadDebtsResponse, BorrowHeadroomResponse, BorrowerInfoResponse,
+    BorrowerInfosResponse, ConfigResponse, Cw20HookMsg, DepositorInfoResponse,
+    EmissionBudgetResponse, ExecuteMsg, FixedRateConfig, FlashLoanReceiverMsg, InstantiateMsg,
+    MarketOverviewResponse, MigrateMsg, QueryMsg, RedemptionQueueResponse, RedemptionResponse,
+    SimulateBorrowResponse, SimulateDepositResponse, SimulateRedeemExactStableResponse,
+    SimulateRedeemResponse, SimulateRepayResponse, SnapshotResponse, SnapshotsResponse,
     StateResponse,
 };
 use moneymarket::querier::deduct_tax;
@@ -21,6 +38,96 @@ use protobuf::Message;
 use std::str::FromStr;
 use terraswap::token::InstantiateMsg as TokenInstantiateMsg;
 
+/// Instantiate the market with the initial deposit and
+/// register the aTerra token and the other contracts
+fn setup_market() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    deps
+}
+
+/// Store the config and the state in the layout
+/// before rate units, as a contract that was never
+/// migrated to a versioned layout
+fn store_legacy_layout(deps: DepsMut, last_interest_updated: u64) {
+    let config = read_config(deps.storage).unwrap();
+    Singleton::new(deps.storage, KEY_CONFIG)
+        .save(&LegacyConfig {
+            contract_addr: config.contract_addr,
+            owner_addr: config.owner_addr,
+            aterra_contract: config.aterra_contract,
+            interest_model: config.interest_model,
+            distribution_model: config.distribution_model,
+            overseer_contract: config.overseer_contract,
+            collector_contract: config.collector_contract,
+            distributor_contract: config.distributor_contract,
+            stable_denom: config.stable_denom,
+            max_borrow_factor: config.max_borrow_factor,
+        })
+        .unwrap();
+
+    let state = read_state(deps.storage).unwrap();
+    Singleton::new(deps.storage, KEY_STATE)
+        .save(&LegacyState {
+            total_liabilities: state.total_liabilities,
+            total_reserves: state.total_reserves,
+            last_interest_updated,
+            last_reward_updated: state.last_reward_updated,
+            global_interest_index: state.global_interest_index,
+            global_reward_index: state.global_reward_index,
+            anc_emission_rate: Decimal256::from_uint256(12u64),
+            prev_aterra_supply: state.prev_aterra_supply,
+            prev_exchange_rate: state.prev_exchange_rate,
+        })
+        .unwrap();
+
+    Singleton::<u64>::new(deps.storage, KEY_CONTRACT_VERSION).remove();
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[Coin {
@@ -34,6 +141,9 @@ fn proper_initialization() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let info = mock_info(
@@ -153,6 +263,9 @@ fn update_config() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let info = mock_info(
@@ -196,6 +309,8 @@ fn update_config() {
         interest_model: None,
         distribution_model: None,
         max_borrow_factor: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -213,6 +328,8 @@ fn update_config() {
         interest_model: Some("interest2".to_string()),
         distribution_model: Some("distribution2".to_string()),
         max_borrow_factor: Some(Decimal256::percent(100)),
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -233,6 +350,8 @@ fn update_config() {
         interest_model: None,
         distribution_model: None,
         max_borrow_factor: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -255,6 +374,9 @@ fn deposit_stable_huge_amount() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let info = mock_info(
@@ -292,7 +414,7 @@ fn deposit_stable_huge_amount() {
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Must deposit stable_denom
-    let msg = ExecuteMsg::DepositStable {};
+    let msg = ExecuteMsg::DepositStable { to: None };
     let info = mock_info(
         "addr0000",
         &[Coin {
@@ -339,6 +461,7 @@ fn deposit_stable_huge_amount() {
         vec![
             attr("action", "deposit_stable"),
             attr("depositor", "addr0000"),
+            attr("recipient", "addr0000"),
             attr("mint_amount", "55555555000000"),
             attr("deposit_amount", "55555555000000"),
         ]
@@ -379,6 +502,7 @@ fn deposit_stable_huge_amount() {
         vec![
             attr("action", "deposit_stable"),
             attr("depositor", "addr0000"),
+            attr("recipient", "addr0000"),
             attr("mint_amount", "55555555000000"),
             attr("deposit_amount", "55555555000000"),
         ]
@@ -411,6 +535,9 @@ fn deposit_stable() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let info = mock_info(
@@ -448,7 +575,7 @@ fn deposit_stable() {
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Must deposit stable_denom
-    let msg = ExecuteMsg::DepositStable {};
+    let msg = ExecuteMsg::DepositStable { to: None };
     let info = mock_info(
         "addr0000",
         &[Coin {
@@ -521,6 +648,16 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(1000000u64),
             prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         }
     );
 
@@ -529,6 +666,7 @@ fn deposit_stable() {
         vec![
             attr("action", "deposit_stable"),
             attr("depositor", "addr0000"),
+            attr("recipient", "addr0000"),
             attr("mint_amount", "1000000"),
             attr("deposit_amount", "1000000"),
         ]
@@ -560,6 +698,16 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         },
     )
     .unwrap();
@@ -570,6 +718,7 @@ fn deposit_stable() {
         vec![
             attr("action", "deposit_stable"),
             attr("depositor", "addr0000"),
+            attr("recipient", "addr0000"),
             attr("mint_amount", "2000000"),
             attr("deposit_amount", "1000000"),
         ]
@@ -610,12 +759,22 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         },
     )
     .unwrap();
 
     env.block.height += 100;
-    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     // State: global_interest_index: 1
     // balance: 1000000
@@ -637,8 +796,47 @@ fn deposit_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1818181),
             prev_exchange_rate: Decimal256::from_ratio(55u64, 100u64),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::from_uint256(100u64),
+            epoch_emitted_anc: Decimal256::from_uint256(100u64),
+            total_claimed_anc: Uint256::zero(),
         }
     );
+
+    // Deposit on behalf of a recipient
+    let msg = ExecuteMsg::DepositStable {
+        to: Some("addr0001".to_string()),
+    };
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "deposit_stable"),
+            attr("depositor", "addr0000"),
+            attr("recipient", "addr0001"),
+            attr("mint_amount", "1818181"),
+            attr("deposit_amount", "1000000"),
+        ]
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "AT-uusd".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(1818181u128),
+            })
+            .unwrap(),
+        }))]
+    );
 }
 
 #[test]
@@ -654,6 +852,9 @@ fn redeem_stable() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let info = mock_info(
@@ -691,7 +892,7 @@ fn redeem_stable() {
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Deposit 1000000
-    let msg = ExecuteMsg::DepositStable {};
+    let msg = ExecuteMsg::DepositStable { to: None };
     let info = mock_info(
         "addr0000",
         &[Coin {
@@ -725,7 +926,7 @@ fn redeem_stable() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(1000000u128),
-        msg: to_binary(&Cw20HookMsg::RedeemStable {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::RedeemStable { to: None }).unwrap(),
     });
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
@@ -774,6 +975,16 @@ fn redeem_stable() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         },
     )
     .unwrap();
@@ -802,7 +1013,7 @@ fn redeem_stable() {
         }],
     );
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     assert_eq!(
         res.messages,
         vec![
@@ -827,18 +1038,39 @@ fn redeem_stable() {
             }))
         ]
     );
+
+    // Redeem to another recipient
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {
+            to: Some("addr0001".to_string()),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0001".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(500000u128),
+                }
+            )
+            .unwrap(),]
+        }))
+    );
 }
 
 #[test]
-fn borrow_stable() {
+fn redeem_exact_stable() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
 
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
@@ -846,6 +1078,9 @@ fn borrow_stable() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let info = mock_info(
@@ -879,220 +1114,269 @@ fn borrow_stable() {
         collector_contract: "collector".to_string(),
         distributor_contract: "distributor".to_string(),
     };
-    let mut env = mock_env();
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-
-    deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
-    deps.querier
-        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
+    // make exchange rate to 50%
     store_state(
         deps.as_mut().storage,
         &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            total_liabilities: Decimal256::from_uint256(500000u128),
+            total_reserves: Decimal256::from_uint256(100000u128),
+            last_interest_updated: mock_env().block.height,
+            last_reward_updated: mock_env().block.height,
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
+            prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         },
     )
     .unwrap();
 
-    let msg = ExecuteMsg::BorrowStable {
-        borrow_amount: Uint256::from(500000u64),
-        to: None,
-    };
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(2000000u128))],
+    )]);
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(600000u128),
+        }],
+    );
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
 
-    env.block.height += 100;
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    // 101000 uusd is sent so 100000 uusd is received after tax,
+    // which burns 202000 aTerra at the 50% exchange rate
+    let res: SimulateRedeemExactStableResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateRedeemExactStable {
+                stable_amount: Uint256::from(100000u64),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateRedeemExactStableResponse {
+            burn_amount: Uint256::from(202000u64),
+            redeem_amount: Uint256::from(101000u64),
+            stable_amount: Uint256::from(100000u64),
+            exchange_rate: Decimal256::percent(50),
+            error: None,
+        }
+    );
 
-    // interest_factor = 1% * 100blocks = 1
-    // interest_accrued = 1000000
-    // global_interest_index = 2
-    // total_liabilities = 2500000
-    // total_reserves = 3000
-    // last_interest_updated = 100
-    // reward_accrued = 100
-    // global_reward_index = 0.00002
-    // last_rewards_updated = 100
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(300000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemExactStable {
+            stable_amount: Uint256::from(100000u64),
+        })
+        .unwrap(),
+    });
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info("AT-uusd", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     assert_eq!(
-        res.attributes,
+        res.messages,
         vec![
-            attr("action", "borrow_stable"),
-            attr("borrower", "addr0000"),
-            attr("borrow_amount", "500000")
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "AT-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(202000u128),
+                })
+                .unwrap()
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "AT-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(98000u128),
+                })
+                .unwrap()
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }]
+            }))
         ]
     );
 
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.prev_aterra_supply, Uint256::from(1798000u64));
+
+    // tax cap bounds the amount sent
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(500u128))],
+    );
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(201000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemExactStable {
+            stable_amount: Uint256::from(100000u64),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "addr0000".to_string(),
-            amount: vec![deduct_tax(
-                deps.as_ref(),
-                Coin {
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "AT-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(201000u128),
+                })
+                .unwrap()
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(500000u128),
-                }
-            )
-            .unwrap()],
-        }))]
+                    amount: Uint128::from(100000u128),
+                }]
+            }))
+        ]
     );
 
-    assert_eq!(
-        from_binary::<State>(
-            &query(
-                deps.as_ref(),
-                env.clone(),
-                QueryMsg::State { block_height: None }
-            )
-            .unwrap()
-        )
+    // not enough aTerra sent
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(200000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemExactStable {
+            stable_amount: Uint256::from(100000u64),
+        })
         .unwrap(),
-        State {
-            total_liabilities: Decimal256::from_uint256(2500000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::from_uint256(2u128),
-            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
-            anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        }
-    );
-
-    // after 1 block state
-    assert_eq!(
-        from_binary::<State>(
-            &query(
-                deps.as_ref(),
-                mock_env(),
-                QueryMsg::State {
-                    block_height: Some(env.block.height + 1u64)
-                }
-            )
-            .unwrap()
+    });
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::InsufficientATerra(201000u128)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn redeem_exact_stable_zero_exchange_rate() {
+    let mut deps = setup_market();
+
+    // the reserves take the whole balance, so aTerra is worth nothing
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(INITIAL_DEPOSIT_AMOUNT);
+    state.prev_aterra_supply = Uint256::from(1000000u64);
+    store_state(deps.as_mut().storage, &state).unwrap();
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
+    )]);
+
+    let res: SimulateRedeemExactStableResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateRedeemExactStable {
+                stable_amount: Uint256::from(100000u64),
+                block_height: None,
+            },
         )
         .unwrap(),
-        State {
-            total_liabilities: Decimal256::from_uint256(2525000u128),
-            total_reserves: Decimal256::from_uint256(0u128),
-            last_interest_updated: env.block.height + 1u64,
-            last_reward_updated: env.block.height + 1u64,
-            global_interest_index: Decimal256::from_str("2.02").unwrap(),
-            global_reward_index: Decimal256::from_str("0.0001008").unwrap(),
-            anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        }
-    );
-
-    let res = query(
-        deps.as_ref(),
-        mock_env(),
-        QueryMsg::BorrowerInfo {
-            borrower: "addr0000".to_string(),
-            block_height: None,
-        },
     )
     .unwrap();
-
-    let liability: BorrowerInfoResponse = from_binary(&res).unwrap();
     assert_eq!(
-        liability,
-        BorrowerInfoResponse {
-            borrower: "addr0000".to_string(),
-            interest_index: Decimal256::from_uint256(2u128),
-            reward_index: Decimal256::from_str("0.0001").unwrap(),
-            loan_amount: Uint256::from(500000u64),
-            pending_rewards: Decimal256::zero(),
-        }
+        res.error,
+        Some(ContractError::ZeroExchangeRate {}.to_string())
     );
 
-    let res = query(
-        deps.as_ref(),
-        mock_env(),
-        QueryMsg::BorrowerInfo {
-            borrower: "addr0000".to_string(),
-            block_height: Some(env.block.height),
-        },
-    )
-    .unwrap();
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(300000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemExactStable {
+            stable_amount: Uint256::from(100000u64),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("AT-uusd", &[]), msg);
+    match res {
+        Err(ContractError::ZeroExchangeRate {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
-    assert_eq!(
-        borrower_info,
-        BorrowerInfoResponse {
-            borrower: "addr0000".to_string(),
-            interest_index: Decimal256::from_uint256(2u128),
-            reward_index: Decimal256::from_str("0.0001").unwrap(),
-            loan_amount: Uint256::from(500000u64),
-            pending_rewards: Decimal256::zero(),
-        }
+    // deposits cannot be minted at a zero rate either
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+        }],
     );
-
-    // Query to future blocks
-    // interest_factor is 100%
-    let res = query(
-        deps.as_ref(),
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
         mock_env(),
-        QueryMsg::BorrowerInfo {
-            borrower: "addr0000".to_string(),
-            block_height: Some(env.block.height + 100),
-        },
-    )
-    .unwrap();
-
-    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
-    assert_eq!(
-        borrower_info,
-        BorrowerInfoResponse {
-            borrower: "addr0000".to_string(),
-            interest_index: Decimal256::from_uint256(4u128),
-            reward_index: Decimal256::from_str("0.00018").unwrap(),
-            loan_amount: Uint256::from(1000000u64),
-            pending_rewards: Decimal256::from_uint256(20u64),
-        }
+        info,
+        ExecuteMsg::DepositStable { to: None },
     );
-
-    // Cannot borrow more than borrow limit
-    let msg = ExecuteMsg::BorrowStable {
-        borrow_amount: Uint256::from(500001u64),
-        to: None,
-    };
-    let res = execute(deps.as_mut(), env, info, msg);
     match res {
-        Err(ContractError::BorrowExceedsLimit(1000000)) => (),
+        Err(ContractError::ZeroExchangeRate {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 }
 
 #[test]
-fn assert_max_borrow_factor() {
+fn redemption_queue() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
 
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
         stable_denom: "uusd".to_string(),
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::percent(1),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
-
     let info = mock_info(
         "addr0000",
         &[Coin {
@@ -1100,8 +1384,6 @@ fn assert_max_borrow_factor() {
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
-
-    // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
@@ -1125,250 +1407,456 @@ fn assert_max_borrow_factor() {
         distributor_contract: "distributor".to_string(),
     };
     let info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-
-    deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
-    deps.querier
-        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
+    // 1:1 exchange rate with only 100000 uusd liquidity left
     store_state(
         deps.as_mut().storage,
         &State {
-            total_liabilities: Decimal256::zero(),
+            total_liabilities: Decimal256::from_uint256(1900000u128),
             total_reserves: Decimal256::zero(),
             last_interest_updated: mock_env().block.height,
             last_reward_updated: mock_env().block.height,
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
+            prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         },
     )
     .unwrap();
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(2000000u128))],
+    )]);
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
 
-    let msg = ExecuteMsg::BorrowStable {
-        borrow_amount: Uint256::from(10000u64),
-        to: None,
-    };
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(300000u128),
+        msg: to_binary(&Cw20HookMsg::QueueRedemption {}).unwrap(),
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    // the available liquidity fills the redemption right away
+    let res = execute(deps.as_mut(), mock_env(), mock_info("AT-uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "AT-uusd".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: Uint128::from(300000u128),
+            })
+            .unwrap()
+        }))]
+    );
     assert_eq!(
         res.attributes,
         vec![
-            attr("action", "borrow_stable"),
-            attr("borrower", "addr0000"),
-            attr("borrow_amount", "10000")
+            attr("action", "queue_redemption"),
+            attr("redemption_id", "1"),
+            attr("burn_amount", "300000"),
+            attr("redeem_amount", "300000"),
         ]
     );
 
-    // subtract borrow amount
-    deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR,
-        vec![Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT - 10000u128),
-        }],
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1700000u128))],
+    )]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0001".to_string(),
+        amount: Uint128::from(100000u128),
+        msg: to_binary(&Cw20HookMsg::QueueRedemption {}).unwrap(),
+    });
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("AT-uusd", &[]), msg).unwrap();
+
+    let res: RedemptionQueueResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::RedemptionQueue {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        RedemptionQueueResponse {
+            head_id: 1,
+            pending_count: 2,
+            pending_amount: Uint256::from(300000u64),
+            claimable_amount: Uint256::from(100000u64),
+        }
     );
 
-    let msg = ExecuteMsg::BorrowStable {
-        borrow_amount: Uint256::from(1u64),
-        to: None,
-    };
-    let res = execute(deps.as_mut(), mock_env(), info, msg);
-    let _uusd_string = "uusd";
+    let res: RedemptionResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Redemption { id: 2 }).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        RedemptionResponse {
+            id: 2,
+            owner: "addr0001".to_string(),
+            amount: Uint256::from(100000u64),
+            filled_amount: Uint256::zero(),
+            position: 1,
+            amount_ahead: Uint256::from(200000u64),
+        }
+    );
+
+    // redemptions cannot jump the queue
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1600000u128))],
+    )]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0002".to_string(),
+        amount: Uint128::from(1u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable { to: None }).unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("AT-uusd", &[]), msg);
     match res {
-        Err(ContractError::MaxBorrowFactorReached(_uusd_string)) => (),
+        Err(ContractError::NoStableAvailable(_)) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
-}
 
-#[test]
-fn repay_stable() {
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-    }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    // the deposit fills the first redemption and part of the second
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(350000u128),
+        }],
     );
-
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        anc_emission_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
+    let msg = ExecuteMsg::DepositStable { to: None };
     let info = mock_info(
-        "addr0000",
+        "addr0002",
         &[Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            amount: Uint128::from(250000u128),
         }],
     );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "AT-uusd".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0002".to_string(),
+                amount: Uint128::from(250000u128),
+            })
+            .unwrap(),
+        }))]
+    );
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.pending_redemptions, Uint256::from(50000u64));
+    assert_eq!(state.claimable_redemptions, Uint256::from(350000u64));
+
+    let res: RedemptionResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Redemption { id: 2 }).unwrap())
+            .unwrap();
+    assert_eq!(res.filled_amount, Uint256::from(50000u64));
+    assert_eq!(res.position, 0);
+
+    let msg = ExecuteMsg::ClaimRedemption { id: 1 };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-    // Register anchor token contract
-    let mut token_inst_res = MsgInstantiateContractResponse::new();
-    token_inst_res.set_contract_address("AT-uusd".to_string());
-    let reply_msg = Reply {
-        id: 1,
-        result: ContractResult::Ok(SubMsgExecutionResponse {
-            events: vec![],
-            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
-        }),
-    };
-    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(300000u128),
+                }
+            )
+            .unwrap()],
+        }))]
+    );
 
-    // Register overseer contract
-    let msg = ExecuteMsg::RegisterContracts {
-        overseer_contract: "overseer".to_string(),
-        interest_model: "interest".to_string(),
-        distribution_model: "distribution".to_string(),
-        collector_contract: "collector".to_string(),
-        distributor_contract: "distributor".to_string(),
-    };
-    let mut env = mock_env();
-    let mut info = mock_info("addr0000", &[]);
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::RedemptionNotFound(1)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-    deps.querier
-        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
-    deps.querier
-        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    // cancel pays the filled part and mints back the rest
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1850000u128))],
+    )]);
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(50000u128),
+        }],
+    );
+    let msg = ExecuteMsg::CancelRedemption { id: 2 };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "AT-uusd".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: "addr0001".to_string(),
+                    amount: Uint128::from(50000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0001".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(50000u128),
+                    }
+                )
+                .unwrap()],
+            })),
+        ]
+    );
 
-    store_state(
-        deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
-    )
-    .unwrap();
+    let res: RedemptionQueueResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::RedemptionQueue {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res,
+        RedemptionQueueResponse {
+            head_id: 2,
+            pending_count: 0,
+            pending_amount: Uint256::zero(),
+            claimable_amount: Uint256::zero(),
+        }
+    );
+}
 
-    let msg = ExecuteMsg::BorrowStable {
-        borrow_amount: Uint256::from(500000u64),
-        to: None,
-    };
+/// Market without liquidity over a 2000000 aTerra
+/// supply at a 1:1 exchange rate
+fn setup_redemptions() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = setup_market();
 
-    env.block.height += 100;
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.total_liabilities = Decimal256::from_uint256(2000000u64);
+    state.prev_aterra_supply = Uint256::from(2000000u64);
+    store_state(deps.as_mut().storage, &state).unwrap();
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(2000000u128))],
+    )]);
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR.to_string(), vec![]);
 
-    let msg = ExecuteMsg::RepayStable {};
-    info.funds = vec![Coin {
-        denom: "ukrw".to_string(),
-        amount: Uint128::from(100000u128),
-    }];
+    deps
+}
 
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-    let _uusd_string = "uusd";
-    match res {
-        Err(ContractError::ZeroRepay(_uusd_string)) => (),
-        _ => panic!("DO NOT ENTER HERE"),
+/// Queue `count` redemptions of 100000 aTerra from addr0000,
+/// burning the aTerra from the supply as the token would
+fn queue_redemptions(deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>, count: u64) {
+    for _ in 0..count {
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(100000u128),
+            msg: to_binary(&Cw20HookMsg::QueueRedemption {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("AT-uusd", &[]), msg).unwrap();
+
+        let supply = read_state(deps.as_ref().storage)
+            .unwrap()
+            .prev_aterra_supply;
+        deps.querier.with_token_balances(&[(
+            &"AT-uusd".to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &supply.into())],
+        )]);
     }
+}
 
-    info.funds = vec![Coin {
-        denom: "uusd".to_string(),
-        amount: Uint128::zero(),
-    }];
+#[test]
+fn queue_redemption_below_minimum() {
+    let mut deps = setup_redemptions();
 
-    let res2 = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-    match res2 {
-        Err(ContractError::ZeroRepay(_uusd_string)) => (),
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(99999u128),
+        msg: to_binary(&Cw20HookMsg::QueueRedemption {}).unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("AT-uusd", &[]), msg);
+    match res {
+        Err(ContractError::RedemptionTooSmall(100000)) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
+}
 
+#[test]
+fn fill_redemptions_bounded_per_call() {
+    let mut deps = setup_redemptions();
+    queue_redemptions(&mut deps, 12);
+
+    let res: RedemptionResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Redemption { id: 12 }).unwrap())
+            .unwrap();
+    assert_eq!(res.position, 11);
+    assert_eq!(res.amount_ahead, Uint256::from(1100000u64));
+
+    // a deposit covering the whole queue fills the first ten
     deps.querier.update_balance(
         MOCK_CONTRACT_ADDR.to_string(),
         vec![Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+            amount: Uint128::from(1200000u128),
         }],
     );
-
-    info.funds = vec![Coin {
-        denom: "uusd".to_string(),
-        amount: Uint128::from(100000u128),
-    }];
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-    assert_eq!(
-        res.attributes,
-        vec![
-            attr("action", "repay_stable"),
-            attr("borrower", "addr0000"),
-            attr("repay_amount", "100000"),
-        ]
+    let msg = ExecuteMsg::DepositStable { to: None };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1200000u128),
+        }],
     );
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    //Loan amount and Total liability have decreased according to the repayment
-    let res_loan = read_borrower_infos(deps.as_ref(), None, None)
-        .unwrap()
-        .get(0)
-        .unwrap()
-        .loan_amount;
-    assert_eq!(res_loan, Uint256::from(400000u128));
+    let res: RedemptionQueueResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::RedemptionQueue {}).unwrap())
+            .unwrap();
     assert_eq!(
-        read_state(deps.as_ref().storage).unwrap().total_liabilities,
-        Decimal256::from_uint256(2400000u128)
+        res,
+        RedemptionQueueResponse {
+            head_id: 11,
+            pending_count: 2,
+            pending_amount: Uint256::from(200000u64),
+            claimable_amount: Uint256::from(1000000u64),
+        }
     );
 
-    info.funds = vec![Coin {
-        denom: "uusd".to_string(),
-        amount: Uint128::from(500000u128),
-    }];
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    assert_eq!(
-        res.attributes,
-        vec![
-            attr("action", "repay_stable"),
-            attr("borrower", "addr0000"),
-            attr("repay_amount", "400000"),
-        ]
+    let res: RedemptionResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Redemption { id: 12 }).unwrap())
+            .unwrap();
+    assert_eq!(res.position, 1);
+    assert_eq!(res.amount_ahead, Uint256::from(100000u64));
+
+    // the next deposit fills the rest
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1300000u128),
+        }],
+    );
+    let msg = ExecuteMsg::DepositStable { to: None };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
     );
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    //Loan amount and Total liability have decreased according to the repayment
-    let res_loan = read_borrower_infos(deps.as_ref(), None, None)
-        .unwrap()
-        .get(0)
-        .unwrap()
-        .loan_amount;
-    assert_eq!(res_loan, Uint256::zero());
+    let res: RedemptionQueueResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::RedemptionQueue {}).unwrap())
+            .unwrap();
     assert_eq!(
-        read_state(deps.as_ref().storage).unwrap().total_liabilities,
-        Decimal256::from_uint256(2000000u128)
+        res,
+        RedemptionQueueResponse {
+            head_id: 13,
+            pending_count: 0,
+            pending_amount: Uint256::zero(),
+            claimable_amount: Uint256::from(1200000u64),
+        }
     );
+}
 
-    assert_eq!(
-        res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "addr0000".to_string(),
-            amount: vec![deduct_tax(
-                deps.as_ref(),
-                Coin {
-                    denom: "uusd".to_string(),
-                    amount: Uint128::from(100000u128),
-                }
-            )
-            .unwrap()]
-        }))]
+#[test]
+fn fill_redemptions_skips_cancelled() {
+    let mut deps = setup_redemptions();
+    queue_redemptions(&mut deps, 3);
+
+    // the cancelled redemption is counted until the fills pass it
+    let msg = ExecuteMsg::CancelRedemption { id: 2 };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let res: RedemptionResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Redemption { id: 3 }).unwrap())
+            .unwrap();
+    assert_eq!(res.position, 2);
+    assert_eq!(res.amount_ahead, Uint256::from(200000u64));
+
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1800000u128))],
+    )]);
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(150000u128),
+        }],
+    );
+    let msg = ExecuteMsg::DepositStable { to: None };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(150000u128),
+        }],
     );
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res: RedemptionResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Redemption { id: 3 }).unwrap())
+            .unwrap();
+    assert_eq!(res.filled_amount, Uint256::from(50000u64));
+    assert_eq!(res.position, 0);
+    assert_eq!(res.amount_ahead, Uint256::zero());
 }
 
 #[test]
-fn repay_stable_from_liquidation() {
+fn borrow_stable() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
@@ -1384,6 +1872,9 @@ fn repay_stable_from_liquidation() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
 
     let info = mock_info(
@@ -1438,6 +1929,16 @@ fn repay_stable_from_liquidation() {
             anc_emission_rate: Decimal256::one(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
         },
     )
     .unwrap();
@@ -1448,106 +1949,3654 @@ fn repay_stable_from_liquidation() {
     };
 
     env.block.height += 100;
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-    // update balance to make repay
-    deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
+    // interest_factor = 1% * 100blocks = 1
+    // interest_accrued = 1000000
+    // global_interest_index = 2
+    // total_liabilities = 2500000
+    // total_reserves = 3000
+    // last_interest_updated = 100
+    // reward_accrued = 100
+    // global_reward_index = 0.00002
+    // last_rewards_updated = 100
+    assert_eq!(
+        res.attributes,
         vec![
-            Coin {
-                denom: "ukrw".to_string(),
-                amount: Uint128::from(100000u128),
-            },
-            Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-            },
-        ],
-    );
-
+            attr("action", "borrow_stable"),
+            attr("borrower", "addr0000"),
+            attr("borrow_amount", "500000"),
+            attr("origination_fee", "0"),
+        ]
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(500000u128),
+                }
+            )
+            .unwrap()],
+        }))]
+    );
+
+    assert_eq!(
+        from_binary::<State>(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::State { block_height: None }
+            )
+            .unwrap()
+        )
+        .unwrap(),
+        State {
+            total_liabilities: Decimal256::from_uint256(2500000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::from_uint256(2u128),
+            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::from_uint256(100u64),
+            epoch_emitted_anc: Decimal256::from_uint256(100u64),
+            total_claimed_anc: Uint256::zero(),
+        }
+    );
+
+    // after 1 block state
+    assert_eq!(
+        from_binary::<State>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::State {
+                    block_height: Some(env.block.height + 1u64)
+                }
+            )
+            .unwrap()
+        )
+        .unwrap(),
+        State {
+            total_liabilities: Decimal256::from_uint256(2525000u128),
+            total_reserves: Decimal256::from_uint256(0u128),
+            last_interest_updated: env.block.height + 1u64,
+            last_reward_updated: env.block.height + 1u64,
+            global_interest_index: Decimal256::from_str("2.02").unwrap(),
+            global_reward_index: Decimal256::from_str("0.0001008").unwrap(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::from_uint256(101u64),
+            epoch_emitted_anc: Decimal256::from_uint256(101u64),
+            total_claimed_anc: Uint256::zero(),
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_height: None,
+        },
+    )
+    .unwrap();
+
+    let liability: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        liability,
+        BorrowerInfoResponse {
+            borrower: "addr0000".to_string(),
+            interest_index: Decimal256::from_uint256(2u128),
+            reward_index: Decimal256::from_str("0.0001").unwrap(),
+            loan_amount: Uint256::from(500000u64),
+            pending_rewards: Decimal256::zero(),
+            fixed_loan_amount: Uint256::zero(),
+            fixed_rate: Decimal256::zero(),
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_height: Some(env.block.height),
+        },
+    )
+    .unwrap();
+
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrower_info,
+        BorrowerInfoResponse {
+            borrower: "addr0000".to_string(),
+            interest_index: Decimal256::from_uint256(2u128),
+            reward_index: Decimal256::from_str("0.0001").unwrap(),
+            loan_amount: Uint256::from(500000u64),
+            pending_rewards: Decimal256::zero(),
+            fixed_loan_amount: Uint256::zero(),
+            fixed_rate: Decimal256::zero(),
+        }
+    );
+
+    // Query to future blocks
+    // interest_factor is 100%
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_height: Some(env.block.height + 100),
+        },
+    )
+    .unwrap();
+
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrower_info,
+        BorrowerInfoResponse {
+            borrower: "addr0000".to_string(),
+            interest_index: Decimal256::from_uint256(4u128),
+            reward_index: Decimal256::from_str("0.00018").unwrap(),
+            loan_amount: Uint256::from(1000000u64),
+            pending_rewards: Decimal256::from_uint256(20u64),
+            fixed_loan_amount: Uint256::zero(),
+            fixed_rate: Decimal256::zero(),
+        }
+    );
+
+    // Cannot borrow more than borrow limit
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500001u64),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), env, info, msg);
+    match res {
+        Err(ContractError::BorrowExceedsLimit(1000000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn fixed_rate_loan() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: Some(FixedRateConfig {
+            premium: Decimal256::from_str("0.005").unwrap(),
+            rebalance_threshold: Decimal256::percent(1),
+        }),
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    // the current borrow rate plus the premium is locked
+    let msg = ExecuteMsg::BorrowStableFixed {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "borrow_stable_fixed"),
+            attr("borrower", "addr0000"),
+            attr("borrow_amount", "100000"),
+            attr("origination_fee", "0"),
+            attr("fixed_rate", "0.015"),
+        ]
+    );
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state.total_fixed_liabilities,
+        Decimal256::from_uint256(100000u64)
+    );
+    assert_eq!(state.fixed_interest_flow, Decimal256::from_uint256(1500u64));
+
+    // fixed rate interest accrues on the loan and on the market
+    env.block.height += 10;
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::zero());
+    assert_eq!(res.fixed_loan_amount, Uint256::from(115000u64));
+    assert_eq!(res.fixed_rate, Decimal256::from_str("0.015").unwrap());
+
+    let res: StateResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::State { block_height: None },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.total_fixed_liabilities,
+        Decimal256::from_uint256(115000u64)
+    );
+
+    // the fixed rate loan counts in the borrow limit
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(900000u64),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::BorrowExceedsLimit(1000000u128)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // rebalance only once the borrow rate exceeds the fixed rate by the threshold
+    let msg = ExecuteMsg::RebalanceFixedRate {
+        borrower: "addr0000".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::FixedRateRebalanceNotAllowed {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(3))]);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "rebalance_fixed_rate"),
+            attr("borrower", "addr0000"),
+            attr("fixed_rate", "0.035"),
+        ]
+    );
+
+    // the variable rate loan is repaid first
+    let msg = ExecuteMsg::RepayStable { borrower: None };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(150000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("payer", "addr0000"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "150000"),
+        ]
+    );
+
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::zero());
+    assert_eq!(res.fixed_loan_amount, Uint256::from(65000u64));
+    assert_eq!(res.fixed_rate, Decimal256::from_str("0.035").unwrap());
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+    assert_eq!(
+        state.total_fixed_liabilities,
+        Decimal256::from_uint256(65000u64)
+    );
+    assert_eq!(state.fixed_interest_flow, Decimal256::from_uint256(2275u64));
+}
+
+#[test]
+fn fixed_rate_loan_full_repay() {
+    let mut deps = setup_market();
+    let mut config = read_config(deps.as_ref().storage).unwrap();
+    config.fixed_rate = Some(FixedRateConfig {
+        premium: Decimal256::permille(5),
+        rebalance_threshold: Decimal256::percent(1),
+    });
+    store_config(deps.as_mut().storage, &config).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    let mut env = mock_env();
+    let msg = ExecuteMsg::BorrowStableFixed {
+        borrow_amount: Uint256::from(33333u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // 33333 * 0.015 * 7 = 3499.965 accrues, 3499 of it on the loan
+    env.block.height += 7;
+    let msg = ExecuteMsg::RepayStable { borrower: None };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(40000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(res.attributes[3], attr("repay_amount", "36832"));
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_fixed_liabilities, Decimal256::zero());
+    assert_eq!(state.fixed_interest_flow, Decimal256::zero());
+}
+
+#[test]
+fn assert_max_borrow_factor() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::percent(1),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::zero(),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: mock_env().block.height,
+            last_reward_updated: mock_env().block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(10000u64),
+        to: None,
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "borrow_stable"),
+            attr("borrower", "addr0000"),
+            attr("borrow_amount", "10000"),
+            attr("origination_fee", "0"),
+        ]
+    );
+
+    // subtract borrow amount
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT - 10000u128),
+        }],
+    );
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(1u64),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    let _uusd_string = "uusd";
+    match res {
+        Err(ContractError::MaxBorrowFactorReached(_uusd_string)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn repay_stable() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let mut info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+
+    env.block.height += 100;
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::RepayStable { borrower: None };
+    info.funds = vec![Coin {
+        denom: "ukrw".to_string(),
+        amount: Uint128::from(100000u128),
+    }];
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    let _uusd_string = "uusd";
+    match res {
+        Err(ContractError::ZeroRepay(_uusd_string)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    info.funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::zero(),
+    }];
+
+    let res2 = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res2 {
+        Err(ContractError::ZeroRepay(_uusd_string)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+        }],
+    );
+
+    info.funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(100000u128),
+    }];
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("payer", "addr0000"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "100000"),
+        ]
+    );
+
+    //Loan amount and Total liability have decreased according to the repayment
+    let res_loan = read_borrower_infos(deps.as_ref(), None, None)
+        .unwrap()
+        .get(0)
+        .unwrap()
+        .loan_amount;
+    assert_eq!(res_loan, Uint256::from(400000u128));
+    assert_eq!(
+        read_state(deps.as_ref().storage).unwrap().total_liabilities,
+        Decimal256::from_uint256(2400000u128)
+    );
+
+    info.funds = vec![Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(500000u128),
+    }];
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("payer", "addr0000"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "400000"),
+        ]
+    );
+
+    //Loan amount and Total liability have decreased according to the repayment
+    let res_loan = read_borrower_infos(deps.as_ref(), None, None)
+        .unwrap()
+        .get(0)
+        .unwrap()
+        .loan_amount;
+    assert_eq!(res_loan, Uint256::zero());
+    assert_eq!(
+        read_state(deps.as_ref().storage).unwrap().total_liabilities,
+        Decimal256::from_uint256(2000000u128)
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }
+            )
+            .unwrap()]
+        }))]
+    );
+
+    // Repay the loan of another borrower
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::RepayStable {
+        borrower: Some("addr0000".to_string()),
+    };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(600000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("payer", "addr0001"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "500000"),
+        ]
+    );
+
+    // Overpayment is refunded to the payer
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0001".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }
+            )
+            .unwrap()]
+        }))]
+    );
+
+    let res_loan = read_borrower_infos(deps.as_ref(), None, None)
+        .unwrap()
+        .first()
+        .unwrap()
+        .loan_amount;
+    assert_eq!(res_loan, Uint256::zero());
+}
+
+#[test]
+fn repay_stable_from_liquidation() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+
+    env.block.height += 100;
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // update balance to make repay
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(100000u128),
+            },
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            },
+        ],
+    );
+
     let msg = ExecuteMsg::RepayStableFromLiquidation {
         borrower: "addr0000".to_string(),
-        prev_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+        prev_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+    };
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info("overseer", &[]);
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    let _uusd_string = "uusd";
+    match res {
+        Err(ContractError::ZeroRepay(__uusd_string)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // update balance to make repay
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
+        }],
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("payer", "addr0000"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "100000"),
+        ]
+    );
+
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 500000u128),
+        }],
+    );
+
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("payer", "addr0000"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "400000"),
+        ]
+    );
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }
+            )
+            .unwrap()]
+        }))]
+    );
+}
+
+#[test]
+fn claim_rewards() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let mut env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    // zero loan claim, will return empty messages
+    let msg = ExecuteMsg::ClaimRewards { to: None };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // zero block passed
+    let msg = ExecuteMsg::ClaimRewards {
+        to: Some("addr0001".to_string()),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    // 100 blocks passed
+    env.block.height += 100;
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "distributor".to_string(),
+            funds: vec![],
+            msg: to_binary(&FaucetExecuteMsg::Spend {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(33u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.pending_rewards,
+        Decimal256::from_str("0.333333333333").unwrap()
+    );
+    assert_eq!(
+        res.reward_index,
+        Decimal256::from_str("0.000066666666666666").unwrap()
+    );
+}
+
+#[test]
+fn execute_epoch_operations() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let mut info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    env.block.height += 100;
+
+    // reserve == 3000
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::one(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+
+    // only overseer can execute this
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    info.sender = Addr::unchecked("overseer");
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "collector".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(2970u128), // 1% tax
+                }],
+            })),
+            SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "interest".to_string(),
+                    funds: vec![],
+                    msg: to_binary(&InterestModelExecuteMsg::ExecuteEpochOperations {
+                        market_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+                        total_liabilities: Decimal256::from_uint256(2000000u128),
+                        total_reserves: Decimal256::from_uint256(3000u128),
+                        block_height: env.block.height,
+                    })
+                    .unwrap(),
+                }),
+                EPOCH_OPERATIONS_REPLY_ID,
+            ),
+            SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "distribution".to_string(),
+                    funds: vec![],
+                    msg: to_binary(&DistributionModelExecuteMsg::ExecuteEpochOperations {
+                        deposit_rate: Decimal256::one(),
+                        target_deposit_rate: Decimal256::one(),
+                        threshold_deposit_rate: Decimal256::one(),
+                        current_emission_rate: Decimal256::one(),
+                        block_height: env.block.height,
+                    })
+                    .unwrap(),
+                }),
+                EPOCH_OPERATIONS_REPLY_ID,
+            ),
+        ]
+    );
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state,
+        State {
+            total_liabilities: Decimal256::from_uint256(2000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::from_uint256(2u64),
+            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
+            anc_emission_rate: Decimal256::from_uint256(5u64),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::one(),
+            total_emitted_anc: Decimal256::from_uint256(100u64),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        }
+    );
+
+    // When there is not enough balance to cover reserve
+    // no message will be sent and reserve will be left as same
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(2999u128),
+        }],
+    );
+
+    let mut env = mock_env();
+    let info = mock_info("overseer", &[]);
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    env.block.height += 100;
+
+    // reserve == 3000
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::one(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+
+    // only the interest and distribution model hooks are sent
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(res.messages.len(), 2);
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state,
+        State {
+            total_liabilities: Decimal256::from_uint256(2000000u128),
+            total_reserves: Decimal256::from_uint256(3000u128),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::from_uint256(2u64),
+            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
+            anc_emission_rate: Decimal256::from_uint256(5u64),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::one(),
+            total_emitted_anc: Decimal256::from_uint256(100u64),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        }
+    );
+}
+
+// #[test]
+// fn borrow_repay_execute_operations() {
+//     let mut deps = mock_dependencies(
+//         20,
+//         &[Coin {
+//             denom: "uusd".to_string(),
+//             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+//         }],
+//     );
+
+//     let msg = InstantiateMsg {
+//         owner_addr: "owner".to_string(),
+//         stable_denom: "uusd".to_string(),
+//         aterra_code_id: 123u64,
+//         anc_emission_rate: Decimal256::one(),
+//         max_borrow_factor: Decimal256::one(),
+//     };
+
+//     let env = mock_env(
+//         "addr0000",
+//         &[Coin {
+//             denom: "uusd".to_string(),
+//             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+//         }],
+//     );
+
+//     // we can just call .unwrap() to assert this was a success
+//     let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+//     // Register anchor token contract
+//     let msg = ExecuteMsg::RegisterATerra {};
+//     let env = mock_env("AT-uusd", &[]);
+//     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+//     // Register overseer contract
+//     let msg = ExecuteMsg::RegisterContracts {
+//         overseer_contract: "overseer".to_string(),
+//         interest_model: "interest".to_string(),
+//         distribution_model: "distribution".to_string(),
+//         collector_contract: "collector".to_string(),
+//         distributor_contract: "distributor".to_string(),
+//     };
+//     let env = mock_env("addr0000", &[]);
+//     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+//     deps.querier
+//         .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+//     deps.querier.with_token_balances(&[(
+//         &HumanAddr::from("AT-uusd"),
+//         &[(
+//             &MOCK_CONTRACT_ADDR.to_string(),
+//             &Uint128::from(1000000u128),
+//         )],
+//     )]);
+//     deps.querier.update_balance(
+//         MOCK_CONTRACT_ADDR.to_string(),
+//         vec![Coin {
+//             denom: "uusd".to_string(),
+//             amount: Uint128::from(373025692u128),
+//         }],
+//     );
+
+//     let mut env = mock_env("overseer", &[]);
+
+//     store_state(
+//         deps.as_mut().storage,
+//         &State {
+//             total_liabilities: Decimal256::from_str("8.198749212085782102").unwrap(),
+//             total_reserves: Decimal256::from_str("372025697.802295205294219818").unwrap(),
+//             last_interest_updated: env.block.height,
+//             last_reward_updated: env.block.height,
+//             global_interest_index: Decimal256::from_str("1.000005078160215988").unwrap(),
+//             global_reward_index: Decimal256::from_str("119531.277425251814227128").unwrap(),
+//             anc_emission_rate: Decimal256::from_str("980001.99").unwrap(),
+//             prev_aterra_supply: Uint256::from(1000000001000000u128),
+//             prev_exchange_rate: Decimal256::from_str("1.0000022").unwrap(),
+//         },
+//     )
+//     .unwrap();
+
+//     env.block.height += 100;
+
+//     let msg = ExecuteMsg::ExecuteEpochOperations {
+//         deposit_rate: Decimal256::one(),
+//         target_deposit_rate: Decimal256::from_str("0.000000040762727704").unwrap(),
+//         threshold_deposit_rate: Decimal256::from_str("0.000000030572045778").unwrap(),
+//     };
+
+//     // only overseer can execute this
+//     let _ = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+// }
+
+#[test]
+fn migrate_to_seconds() {
+    let mut deps = setup_market();
+    store_legacy_layout(deps.as_mut(), mock_env().block.height - 100);
+
+    let res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: Some(Decimal256::zero()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InvalidSecondsPerBlock {});
+
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: Some(Decimal256::from_uint256(6u64)),
+        },
+    )
+    .unwrap();
+
+    let config = read_config(deps.as_ref().storage).unwrap();
+    assert_eq!(config.rate_unit, RateUnit::Second);
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state.last_interest_updated,
+        mock_env().block.time.seconds() - 600
+    );
+    assert_eq!(state.last_reward_updated, mock_env().block.time.seconds());
+    assert_eq!(state.anc_emission_rate, Decimal256::from_uint256(2u64));
+    assert_eq!(state.pending_redemptions, Uint256::zero());
+    assert_eq!(state.claimable_redemptions, Uint256::zero());
+}
+
+#[test]
+fn migrate_twice() {
+    let mut deps = setup_market();
+    store_legacy_layout(deps.as_mut(), mock_env().block.height);
+
+    let msg = MigrateMsg {
+        seconds_per_block: Some(Decimal256::from_uint256(6u64)),
+    };
+    let _res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+    let config = read_config(deps.as_ref().storage).unwrap();
+    let state = read_state(deps.as_ref().storage).unwrap();
+
+    // rates are not converted twice
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert_eq!(res, ContractError::RateConversionNotAllowed {});
+
+    // a later code upgrade keeps the config and the state
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(read_config(deps.as_ref().storage).unwrap(), config);
+    assert_eq!(read_state(deps.as_ref().storage).unwrap(), state);
+    assert_eq!(config.rate_unit, RateUnit::Second);
+    assert_eq!(state.anc_emission_rate, Decimal256::from_uint256(2u64));
+
+    // a market instantiated with the current layout is never converted
+    let mut deps = setup_market();
+    let config = read_config(deps.as_ref().storage).unwrap();
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(read_config(deps.as_ref().storage).unwrap(), config);
+}
+
+#[test]
+fn migrate_keeps_redemptions() {
+    let mut deps = setup_market();
+
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.pending_redemptions = Uint256::from(300000u64);
+    state.claimable_redemptions = Uint256::from(200000u64);
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: None,
+        },
+    )
+    .unwrap();
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.pending_redemptions, Uint256::from(300000u64));
+    assert_eq!(state.claimable_redemptions, Uint256::from(200000u64));
+}
+
+#[test]
+fn migrate_keeps_fixed_rate_loans() {
+    let mut deps = setup_market();
+
+    let mut config = read_config(deps.as_ref().storage).unwrap();
+    config.fixed_rate = Some(FixedRateConfig {
+        premium: Decimal256::permille(5),
+        rebalance_threshold: Decimal256::percent(1),
+    });
+    store_config(deps.as_mut().storage, &config).unwrap();
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.total_fixed_liabilities = Decimal256::from_uint256(500000u64);
+    state.fixed_interest_flow = Decimal256::from_uint256(5u64);
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: None,
+        },
+    )
+    .unwrap();
+
+    let config = read_config(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        config.fixed_rate,
+        Some(FixedRateConfig {
+            premium: Decimal256::permille(5),
+            rebalance_threshold: Decimal256::percent(1),
+        })
+    );
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state.total_fixed_liabilities,
+        Decimal256::from_uint256(500000u64)
+    );
+    assert_eq!(state.fixed_interest_flow, Decimal256::from_uint256(5u64));
+}
+
+#[test]
+fn migrate_keeps_market_parameters() {
+    let mut deps = setup_market();
+
+    let mut config = read_config(deps.as_ref().storage).unwrap();
+    config.flash_loan_fee = Some(Decimal256::permille(1));
+    config.emission_budget = Some(Uint256::from(1000000u64));
+    config.epoch_emission_cap = Some(Uint256::from(10000u64));
+    store_config(deps.as_mut().storage, &config).unwrap();
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.total_bonded_aterra = Uint256::from(400000u64);
+    state.depositor_reward_index = Decimal256::percent(3);
+    state.depositor_emission_rate = Decimal256::percent(20);
+    state.total_emitted_anc = Decimal256::from_uint256(50000u64);
+    state.epoch_emitted_anc = Decimal256::from_uint256(5000u64);
+    state.total_claimed_anc = Uint256::from(40000u64);
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(read_config(deps.as_ref().storage).unwrap(), config);
+    assert_eq!(read_state(deps.as_ref().storage).unwrap(), state);
+}
+
+#[test]
+fn flash_loan() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
+    )]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: Some(Decimal256::permille(1)),
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Cannot lend more than the available balance
+    let info = mock_info("receiver", &[]);
+    let msg = ExecuteMsg::FlashLoan {
+        amount: Uint256::from(1000001u64),
+        msg: Binary::from(b"{}".to_vec()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::NoStableAvailable(_)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::FlashLoan {
+        amount: Uint256::from(500000u64),
+        msg: Binary::from(b"{}".to_vec()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "receiver".to_string(),
+                funds: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(500000u128),
+                }],
+                msg: to_binary(&FlashLoanReceiverMsg::FlashLoanCallback {
+                    amount: Uint256::from(500000u64),
+                    fee: Uint256::from(500u64),
+                    msg: Binary::from(b"{}".to_vec()),
+                })
+                .unwrap(),
+            }),
+            2
+        )]
+    );
+
+    // No other operation while the loan is in progress
+    let msg = ExecuteMsg::DepositStable { to: None };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::FlashLoanInProgress {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let reply_msg = Reply {
+        id: 2,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+
+    // balance did not come back with the fee
+    let res = reply(deps.as_mut(), mock_env(), reply_msg.clone());
+    match res {
+        Err(ContractError::FlashLoanNotRepaid(1000500u128)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000600u128),
+        }],
+    );
+    let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "flash_loan_repaid"), attr("fee", "600"),]
+    );
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_reserves, Decimal256::from_uint256(600u64));
+
+    // operations are allowed again
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+#[test]
+fn debt_ceiling_and_borrow_cap() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_limit(&[
+        (&"addr0000".to_string(), &Uint256::from(1000000u64)),
+        (&"addr0001".to_string(), &Uint256::from(1000000u64)),
+    ]);
+
+    // only the owner can adjust the limits
+    let msg = ExecuteMsg::UpdateDebtCeiling {
+        debt_ceiling: Some(Uint256::from(500000u64)),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UpdateBorrowCap {
+        borrower: "addr0000".to_string(),
+        borrow_cap: Some(Uint256::from(200000u64)),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let owner_info = mock_info("owner", &[]);
+    let msg = ExecuteMsg::UpdateDebtCeiling {
+        debt_ceiling: Some(Uint256::from(500000u64)),
+    };
+    let res = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_debt_ceiling"),
+            attr("debt_ceiling", "500000"),
+        ]
+    );
+
+    let msg = ExecuteMsg::UpdateBorrowCap {
+        borrower: "addr0000".to_string(),
+        borrow_cap: Some(Uint256::from(200000u64)),
+    };
+    let res = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_borrow_cap"),
+            attr("borrower", "addr0000"),
+            attr("borrow_cap", "200000"),
+        ]
+    );
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.debt_ceiling, Some(Uint256::from(500000u64)));
+
+    // borrow cap of the borrower
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(250000u64),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::BorrowCapReached(200000u128)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(150000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // debt ceiling of the market
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(400000u64),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg);
+    match res {
+        Err(ContractError::DebtCeilingReached(500000u128)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: BorrowHeadroomResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowHeadroom {
+                borrower: Some("addr0000".to_string()),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        BorrowHeadroomResponse {
+            debt_ceiling: Some(Uint256::from(500000u64)),
+            debt_ceiling_headroom: Some(Uint256::from(350000u64)),
+            borrow_cap: Some(Uint256::from(200000u64)),
+            borrow_cap_headroom: Some(Uint256::from(50000u64)),
+        }
+    );
+
+    // lift both limits
+    let msg = ExecuteMsg::UpdateDebtCeiling { debt_ceiling: None };
+    let _res = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::UpdateBorrowCap {
+        borrower: "addr0000".to_string(),
+        borrow_cap: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), owner_info, msg).unwrap();
+
+    let res: BorrowHeadroomResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowHeadroom {
+                borrower: Some("addr0000".to_string()),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        BorrowHeadroomResponse {
+            debt_ceiling: None,
+            debt_ceiling_headroom: None,
+            borrow_cap: None,
+            borrow_cap_headroom: None,
+        }
+    );
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(400000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+}
+
+#[test]
+fn reserve_factor() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    // only the owner can update the reserve config
+    let msg = ExecuteMsg::UpdateReserveConfig {
+        reserve_factor: Some(Decimal256::percent(10)),
+        insurance_contract: Some("insurance".to_string()),
+        insurance_share: Some(Decimal256::percent(40)),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let owner_info = mock_info("owner", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        owner_info.clone(),
+        ExecuteMsg::UpdateReserveConfig {
+            reserve_factor: None,
+            insurance_contract: None,
+            insurance_share: Some(Decimal256::percent(150)),
+        },
+    );
+    match res {
+        Err(ContractError::InvalidReserveConfig {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap();
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.reserve_factor, Decimal256::percent(10));
+    assert_eq!(res.insurance_contract, Some("insurance".to_string()));
+    assert_eq!(res.insurance_share, Decimal256::percent(40));
+
+    // 10% of the 1000000 interest is set aside
+    // and split between the insurance and the collector
+    env.block.height += 100;
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::zero(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("overseer", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "insurance".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(40000u128),
+                    }
+                )
+                .unwrap()],
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "collector".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(60000u128),
+                    }
+                )
+                .unwrap()],
+            })),
+            SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "interest".to_string(),
+                    funds: vec![],
+                    msg: to_binary(&InterestModelExecuteMsg::ExecuteEpochOperations {
+                        market_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+                        total_liabilities: Decimal256::from_uint256(2000000u128),
+                        total_reserves: Decimal256::from_uint256(100000u128),
+                        block_height: env.block.height,
+                    })
+                    .unwrap(),
+                }),
+                EPOCH_OPERATIONS_REPLY_ID,
+            ),
+            SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "distribution".to_string(),
+                    funds: vec![],
+                    msg: to_binary(&DistributionModelExecuteMsg::ExecuteEpochOperations {
+                        deposit_rate: Decimal256::zero(),
+                        target_deposit_rate: Decimal256::one(),
+                        threshold_deposit_rate: Decimal256::one(),
+                        current_emission_rate: Decimal256::one(),
+                        block_height: env.block.height,
+                    })
+                    .unwrap(),
+                }),
+                EPOCH_OPERATIONS_REPLY_ID,
+            ),
+        ]
+    );
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_reserves, Decimal256::zero());
+
+    // the owner can withdraw the reserves accrued since
+    env.block.height += 100;
+    let msg = ExecuteMsg::WithdrawReserves {
+        amount: Some(Uint256::from(50000u64)),
+        to: Some("treasury".to_string()),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        owner_info.clone(),
+        ExecuteMsg::WithdrawReserves {
+            amount: Some(Uint256::from(300000u64)),
+            to: None,
+        },
+    );
+    match res {
+        Err(ContractError::InsufficientReserves(200000u128)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), owner_info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(50000u128),
+                }
+            )
+            .unwrap()],
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw_reserves"),
+            attr("recipient", "treasury"),
+            attr("amount", "50000"),
+        ]
+    );
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_reserves, Decimal256::from_uint256(150000u64));
+}
+
+#[test]
+fn snapshots_and_apy() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(2000000u128))],
+    )]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.height,
+            last_reward_updated: env.block.height,
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            prev_aterra_supply: Uint256::from(2000000u64),
+            prev_exchange_rate: Decimal256::one(),
+            pending_redemptions: Uint256::zero(),
+            claimable_redemptions: Uint256::zero(),
+            total_fixed_liabilities: Decimal256::zero(),
+            fixed_interest_flow: Decimal256::zero(),
+            total_bonded_aterra: Uint256::zero(),
+            depositor_reward_index: Decimal256::zero(),
+            depositor_emission_rate: Decimal256::zero(),
+            total_emitted_anc: Decimal256::zero(),
+            epoch_emitted_anc: Decimal256::zero(),
+            total_claimed_anc: Uint256::zero(),
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::zero(),
+        target_deposit_rate: Decimal256::one(),
+        threshold_deposit_rate: Decimal256::one(),
+        distributed_interest: Uint256::zero(),
+    };
+    let overseer_info = mock_info("overseer", &[]);
+    let start_time = env.block.time.seconds();
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        overseer_info.clone(),
+        msg.clone(),
+    )
+    .unwrap();
+
+    // a single snapshot is not enough
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Apy { window: 3153600 },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Not enough snapshots"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a tenth of a year per epoch
+    for _ in 0..2 {
+        env.block.height += 100;
+        env.block.time = env.block.time.plus_seconds(3153600);
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            overseer_info.clone(),
+            msg.clone(),
+        )
+        .unwrap();
+    }
+
+    let res: SnapshotsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Snapshots {
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.snapshots,
+        vec![
+            SnapshotResponse {
+                id: 0,
+                height: env.block.height - 200,
+                time: start_time,
+                exchange_rate: Decimal256::one(),
+                borrow_rate: Decimal256::percent(1),
+            },
+            SnapshotResponse {
+                id: 1,
+                height: env.block.height - 100,
+                time: start_time + 3153600,
+                exchange_rate: Decimal256::from_str("1.5").unwrap(),
+                borrow_rate: Decimal256::percent(1),
+            },
+        ]
+    );
+
+    let res: SnapshotsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Snapshots {
+                start_after: Some(1),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.snapshots,
+        vec![SnapshotResponse {
+            id: 2,
+            height: env.block.height,
+            time: start_time + 6307200,
+            exchange_rate: Decimal256::from_str("2.5").unwrap(),
+            borrow_rate: Decimal256::percent(1),
+        }]
+    );
+
+    let res: ApyResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Apy { window: 6307200 },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ApyResponse {
+            from_time: start_time,
+            to_time: start_time + 6307200,
+            deposit_apy: Decimal256::from_str("7.5").unwrap(),
+            borrow_apy: Decimal256::from_uint256(10u64),
+        }
+    );
+
+    // a window longer than the chain history covers every snapshot
+    let res: ApyResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Apy { window: u64::MAX },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.from_time, start_time);
+
+    // only the last epoch is in the window
+    let res: ApyResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::Apy { window: 3153600 }).unwrap())
+            .unwrap();
+    assert_eq!(res.from_time, start_time + 3153600);
+    assert_eq!(res.borrow_apy, Decimal256::from_uint256(10u64));
+}
+
+#[test]
+fn simulate_operations() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    // deposit
+    let res: SimulateDepositResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateDeposit {
+                deposit_amount: Uint256::from(1000000u64),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateDepositResponse {
+            mint_amount: Uint256::from(1000000u64),
+            exchange_rate: Decimal256::one(),
+            error: None,
+        }
+    );
+
+    let res: SimulateDepositResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateDeposit {
+                deposit_amount: Uint256::zero(),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.error,
+        Some(ContractError::ZeroDeposit("uusd".to_string()).to_string())
+    );
+
+    // borrow
+    let res: SimulateBorrowResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateBorrow {
+                borrower: "addr0000".to_string(),
+                borrow_amount: Uint256::from(500000u64),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let received_amount = deduct_tax(
+        deps.as_ref(),
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(500000u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateBorrowResponse {
+            received_amount: received_amount.amount.into(),
+            origination_fee: Uint256::zero(),
+            loan_amount: Uint256::from(500000u64),
+            error: None,
+        }
+    );
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![received_amount],
+        }))]
+    );
+
+    let res: SimulateBorrowResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateBorrow {
+                borrower: "addr0000".to_string(),
+                borrow_amount: Uint256::from(600000u64),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.error,
+        Some(ContractError::BorrowExceedsLimit(1000000u128).to_string())
+    );
+
+    // redeem
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(
+            &MOCK_CONTRACT_ADDR.to_string(),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        )],
+    )]);
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.prev_aterra_supply = Uint256::from(INITIAL_DEPOSIT_AMOUNT);
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    let res: SimulateRedeemResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateRedeem {
+                burn_amount: Uint256::from(100000u64),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let received_amount = deduct_tax(
+        deps.as_ref(),
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(150000u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateRedeemResponse {
+            redeem_amount: Uint256::from(150000u64),
+            received_amount: received_amount.amount.into(),
+            exchange_rate: Decimal256::from_str("1.5").unwrap(),
+            error: None,
+        }
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(100000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable { to: None }).unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("AT-uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![received_amount],
+        }))
+    );
+
+    // repay more than the loan
+    let res: SimulateRepayResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateRepay {
+                borrower: "addr0000".to_string(),
+                repay_amount: Uint256::from(600000u64),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let refund_amount = deduct_tax(
+        deps.as_ref(),
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateRepayResponse {
+            repay_amount: Uint256::from(500000u64),
+            refund_amount: refund_amount.amount.into(),
+            loan_amount: Uint256::zero(),
+            fixed_loan_amount: Uint256::zero(),
+            error: None,
+        }
+    );
+
+    // the loan doubles at 1% per block over 100 blocks
+    let res: SimulateRepayResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateRepay {
+                borrower: "addr0000".to_string(),
+                repay_amount: Uint256::from(600000u64),
+                block_height: Some(env.block.height + 100),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateRepayResponse {
+            repay_amount: Uint256::from(600000u64),
+            refund_amount: Uint256::zero(),
+            loan_amount: Uint256::from(400000u64),
+            fixed_loan_amount: Uint256::zero(),
+            error: None,
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::SimulateRepay {
+            borrower: "addr0000".to_string(),
+            repay_amount: Uint256::from(600000u64),
+            block_height: Some(env.block.height - 1),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "block_height must bigger than last_interest_updated")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: SimulateRepayResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::SimulateRepay {
+                borrower: "addr0000".to_string(),
+                repay_amount: Uint256::zero(),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.error,
+        Some(ContractError::ZeroRepay("uusd".to_string()).to_string())
+    );
+}
+
+#[test]
+fn bond_aterra_rewards() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.depositor_emission_rate = Decimal256::one();
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    // only aterra can be bonded
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000u128),
+        msg: to_binary(&Cw20HookMsg::BondATerra {}).unwrap(),
+    });
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("token", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("AT-uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "bond_aterra"),
+            attr("depositor", "addr0000"),
+            attr("amount", "1000"),
+        ]
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0001".to_string(),
+        amount: Uint128::from(3000u128),
+        msg: to_binary(&Cw20HookMsg::BondATerra {}).unwrap(),
+    });
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("AT-uusd", &[]), msg).unwrap();
+
+    // 100 blocks passed; rewards are split by the bonded amount
+    env.block.height += 100;
+    let res: DepositorInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::DepositorInfo {
+                depositor: "addr0000".to_string(),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        DepositorInfoResponse {
+            depositor: "addr0000".to_string(),
+            bonded_amount: Uint256::from(1000u64),
+            reward_index: Decimal256::from_ratio(100, 4000),
+            pending_rewards: Decimal256::from_uint256(25u64),
+        }
+    );
+
+    let msg = ExecuteMsg::ClaimRewards { to: None };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "distributor".to_string(),
+            funds: vec![],
+            msg: to_binary(&FaucetExecuteMsg::Spend {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(75u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // unbond
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::UnbondATerra {
+        amount: Uint256::zero(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::ZeroUnbond {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UnbondATerra {
+        amount: Uint256::from(1001u64),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::InsufficientBondedATerra(1000)) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UnbondATerra {
+        amount: Uint256::from(1000u64),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "AT-uusd".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // pending rewards are kept after unbonding
+    let res: DepositorInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::DepositorInfo {
+                depositor: "addr0000".to_string(),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.bonded_amount, Uint256::zero());
+    assert_eq!(res.pending_rewards, Decimal256::from_uint256(25u64));
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_bonded_aterra, Uint256::from(3000u64));
+}
+
+#[test]
+fn origination_fee() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    // only the owner can update the fee
+    let msg = ExecuteMsg::UpdateOriginationFee {
+        origination_fee: Decimal256::percent(1),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateOriginationFee {
+            origination_fee: Decimal256::one(),
+        },
+    );
+    match res {
+        Err(ContractError::InvalidOriginationFee {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_origination_fee"),
+            attr("origination_fee", "0.01"),
+        ]
+    );
+
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(res.origination_fee, Decimal256::percent(1));
+
+    let res: SimulateBorrowResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateBorrow {
+                borrower: "addr0000".to_string(),
+                borrow_amount: Uint256::from(500000u64),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let received_amount = deduct_tax(
+        deps.as_ref(),
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(495000u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateBorrowResponse {
+            received_amount: received_amount.amount.into(),
+            origination_fee: Uint256::from(5000u64),
+            loan_amount: Uint256::from(500000u64),
+            error: None,
+        }
+    );
+
+    // the fee is kept from the amount sent and added to the reserves
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![received_amount],
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "borrow_stable"),
+            attr("borrower", "addr0000"),
+            attr("borrow_amount", "500000"),
+            attr("origination_fee", "5000"),
+        ]
+    );
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::from_uint256(500000u64));
+    assert_eq!(state.total_reserves, Decimal256::from_uint256(5000u64));
+}
+
+/// Market with 1000000 aTerra, a 500000 uusd loan of addr0000 and
+/// 1000 uusd of reserves, after the overseer sent 100000 uusd of the
+/// interest buffer; writing the loan off costs the depositors 399000
+fn setup_bad_debt() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = setup_market();
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&"addr0000".to_string(), &Uint128::from(1000000u128))],
+    )]);
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(1000u64);
+    state.prev_aterra_supply = Uint256::from(1000000u64);
+    state.prev_exchange_rate = Decimal256::from_str("0.999").unwrap();
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    // 500000 uusd left after the borrow and 100000 uusd from the buffer
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(600000u128),
+        }],
+    );
+
+    deps
+}
+
+fn bad_debt_msg() -> ExecuteMsg {
+    ExecuteMsg::WriteOffBadDebt {
+        borrower: "addr0000".to_string(),
+        prev_balance: Uint256::from(500000u64),
+    }
+}
+
+#[test]
+fn write_off_bad_debt() {
+    let mut deps = setup_bad_debt();
+
+    let info = mock_info("overseer", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, bad_debt_msg()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "write_off_bad_debt"),
+            attr("bad_debt_id", "0"),
+            attr("borrower", "addr0000"),
+            attr("amount", "500000"),
+            attr("buffer_amount", "100000"),
+            attr("reserve_amount", "1000"),
+            attr("loss_amount", "399000"),
+        ]
+    );
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+    assert_eq!(state.total_reserves, Decimal256::zero());
+
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::zero());
+
+    let res: BadDebtsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BadDebts {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        BadDebtsResponse {
+            bad_debts: vec![BadDebtResponse {
+                id: 0,
+                borrower: "addr0000".to_string(),
+                height: mock_env().block.height,
+                amount: Uint256::from(500000u64),
+                buffer_amount: Uint256::from(100000u64),
+                reserve_amount: Uint256::from(1000u64),
+                loss_amount: Uint256::from(399000u64),
+            }],
+        }
+    );
+}
+
+#[test]
+fn write_off_bad_debt_unauthorized() {
+    let mut deps = setup_bad_debt();
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, bad_debt_msg());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn write_off_bad_debt_twice() {
+    let mut deps = setup_bad_debt();
+
+    let info = mock_info("overseer", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), bad_debt_msg()).unwrap();
+
+    // nothing left to write off
+    let res = execute(deps.as_mut(), mock_env(), info, bad_debt_msg());
+    match res {
+        Err(ContractError::NoBadDebt {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn write_off_bad_debt_rebases_exchange_rate() {
+    let mut deps = setup_bad_debt();
+
+    let info = mock_info("overseer", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, bad_debt_msg()).unwrap();
+
+    // 600000 uusd left for 1000000 aTerra
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state.prev_exchange_rate,
+        Decimal256::from_str("0.6").unwrap()
+    );
+}
+
+#[test]
+fn accrue_interest_after_bad_debt_loss() {
+    let mut deps = setup_bad_debt();
+
+    let info = mock_info("overseer", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, bad_debt_msg()).unwrap();
+
+    let mut env = mock_env();
+    env.block.height += 100;
+    let msg = ExecuteMsg::AccrueInterest {
+        update_interest_model: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(res.attributes[2], attr("exchange_rate", "0.6"));
+}
+
+#[test]
+fn epoch_operations_after_bad_debt_loss() {
+    let mut deps = setup_bad_debt();
+
+    let info = mock_info("overseer", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), bad_debt_msg()).unwrap();
+
+    let mut env = mock_env();
+    env.block.height += 100;
+    let msg = ExecuteMsg::ExecuteEpochOperations {
+        deposit_rate: Decimal256::zero(),
+        target_deposit_rate: Decimal256::percent(1),
+        threshold_deposit_rate: Decimal256::percent(1),
+        distributed_interest: Uint256::zero(),
+    };
+    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state.prev_exchange_rate,
+        Decimal256::from_str("0.6").unwrap()
+    );
+}
+
+#[test]
+fn deposit_rate_after_exchange_rate_drop() {
+    let mut state = State {
+        total_liabilities: Decimal256::zero(),
+        total_reserves: Decimal256::zero(),
+        last_interest_updated: 100u64,
+        last_reward_updated: 100u64,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        anc_emission_rate: Decimal256::one(),
+        prev_aterra_supply: Uint256::from(1000000u64),
+        prev_exchange_rate: Decimal256::one(),
+        pending_redemptions: Uint256::zero(),
+        claimable_redemptions: Uint256::zero(),
+        total_fixed_liabilities: Decimal256::zero(),
+        fixed_interest_flow: Decimal256::zero(),
+        total_bonded_aterra: Uint256::zero(),
+        depositor_reward_index: Decimal256::zero(),
+        depositor_emission_rate: Decimal256::zero(),
+        total_emitted_anc: Decimal256::zero(),
+        epoch_emitted_anc: Decimal256::zero(),
+        total_claimed_anc: Uint256::zero(),
+    };
+
+    // a drop from 1 to 0.6 is not an excess deposit rate
+    compute_interest_raw(
+        &mut state,
+        200u64,
+        Uint256::from(600000u64),
+        Uint256::from(1000000u64),
+        Decimal256::percent(1),
+        Decimal256::zero(),
+        Decimal256::zero(),
+    );
+    assert_eq!(state.total_reserves, Decimal256::zero());
+    assert_eq!(
+        state.prev_exchange_rate,
+        Decimal256::from_str("0.6").unwrap()
+    );
+
+    // nor is a rate falling to zero, and a zero rate is no base
+    compute_interest_raw(
+        &mut state,
+        300u64,
+        Uint256::zero(),
+        Uint256::from(1000000u64),
+        Decimal256::percent(1),
+        Decimal256::zero(),
+        Decimal256::zero(),
+    );
+    assert_eq!(state.prev_exchange_rate, Decimal256::zero());
+    compute_interest_raw(
+        &mut state,
+        400u64,
+        Uint256::from(100000u64),
+        Uint256::from(1000000u64),
+        Decimal256::percent(1),
+        Decimal256::zero(),
+        Decimal256::zero(),
+    );
+    assert_eq!(state.prev_exchange_rate, Decimal256::percent(10));
+}
+
+/// Market with 1000000 aTerra and 500000 uusd of the deposits lent at
+/// 1% per block, a 20% reserve factor and an 80% max borrow factor
+fn setup_overview() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = setup_market();
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&"addr0000".to_string(), &Uint128::from(1000000u128))],
+    )]);
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(500000u128),
+        }],
+    );
+
+    let msg = ExecuteMsg::UpdateReserveConfig {
+        reserve_factor: Some(Decimal256::percent(20)),
+        insurance_contract: None,
+        insurance_share: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: Some(Decimal256::percent(80)),
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    deps
+}
+
+fn query_overview(deps: Deps) -> MarketOverviewResponse {
+    from_binary(&query(deps, mock_env(), QueryMsg::MarketOverview {}).unwrap()).unwrap()
+}
+
+#[test]
+fn market_overview() {
+    let deps = setup_overview();
+
+    assert_eq!(
+        query_overview(deps.as_ref()),
+        MarketOverviewResponse {
+            stable_liquidity: Uint256::from(500000u64),
+            utilization: Decimal256::percent(50),
+            borrow_rate: Decimal256::percent(1),
+            // 0.01 * 4,656,810 blocks
+            borrow_apr: Decimal256::from_str("46568.1").unwrap(),
+            // 500,000 * 0.01 * 0.8 / 1,000,000
+            deposit_rate: Decimal256::permille(4),
+            deposit_apr: Decimal256::from_str("18627.24").unwrap(),
+            exchange_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(1000000u64),
+            total_reserves: Decimal256::zero(),
+            // (500,000 + 500,000) * 0.8 - 500,000
+            borrow_capacity: Uint256::from(300000u64),
+        }
+    );
+}
+
+#[test]
+fn market_overview_capacity_bound_by_liquidity() {
+    let mut deps = setup_overview();
+
+    // queued redemptions keep 400000 of the 500000 uusd
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.pending_redemptions = Uint256::from(400000u64);
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    let res = query_overview(deps.as_ref());
+    assert_eq!(res.borrow_capacity, Uint256::from(100000u64));
+    // 500,000 / (500,000 + 500,000 - 400,000)
+    assert_eq!(res.utilization, Decimal256::from_ratio(5u64, 6u64));
+}
+
+#[test]
+fn market_overview_empty_market() {
+    let mut deps = setup_market();
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR.to_string(), vec![]);
+
+    assert_eq!(
+        query_overview(deps.as_ref()),
+        MarketOverviewResponse {
+            stable_liquidity: Uint256::zero(),
+            utilization: Decimal256::zero(),
+            borrow_rate: Decimal256::percent(1),
+            borrow_apr: Decimal256::from_str("46568.1").unwrap(),
+            deposit_rate: Decimal256::zero(),
+            deposit_apr: Decimal256::zero(),
+            exchange_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
+            total_reserves: Decimal256::zero(),
+            borrow_capacity: Uint256::zero(),
+        }
+    );
+}
+
+#[test]
+fn market_overview_zero_exchange_rate() {
+    let mut deps = setup_market();
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_token_balances(&[(
+        &"AT-uusd".to_string(),
+        &[(&"addr0000".to_string(), &Uint128::from(1000000u128))],
+    )]);
+
+    // the reserves take the whole balance
+    let mut state = read_state(deps.as_ref().storage).unwrap();
+    state.total_reserves = Decimal256::from_uint256(INITIAL_DEPOSIT_AMOUNT);
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    let res = query_overview(deps.as_ref());
+    assert_eq!(res.exchange_rate, Decimal256::zero());
+    assert_eq!(res.utilization, Decimal256::zero());
+    assert_eq!(res.deposit_rate, Decimal256::zero());
+    assert_eq!(res.borrow_capacity, Uint256::zero());
+}
+
+#[test]
+fn accrue_interest() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("AT-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
     };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
 
-    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
-    match res {
-        Err(ContractError::Unauthorized {}) => (),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
-
-    let info = mock_info("overseer", &[]);
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-    let _uusd_string = "uusd";
-    match res {
-        Err(ContractError::ZeroRepay(__uusd_string)) => (),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
 
-    // update balance to make repay
-    deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
-        vec![Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 100000u128),
-        }],
-    );
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    // anyone can accrue the interest
+    env.block.height += 100;
+    let info = mock_info("addr0001", &[]);
+    let msg = ExecuteMsg::AccrueInterest {
+        update_interest_model: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages, vec![]);
     assert_eq!(
         res.attributes,
         vec![
-            attr("action", "repay_stable"),
-            attr("borrower", "addr0000"),
-            attr("repay_amount", "100000"),
+            attr("action", "accrue_interest"),
+            attr("global_interest_index", "2"),
+            attr("exchange_rate", "1"),
         ]
     );
 
-    deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
-        vec![Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 500000u128),
-        }],
-    );
-
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.last_interest_updated, env.block.height);
+    assert_eq!(state.last_reward_updated, env.block.height);
     assert_eq!(
-        res.attributes,
-        vec![
-            attr("action", "repay_stable"),
-            attr("borrower", "addr0000"),
-            attr("repay_amount", "400000"),
-        ]
+        state.total_liabilities,
+        Decimal256::from_uint256(1000000u64)
     );
 
+    // only the owner can update the interest model
+    let msg = ExecuteMsg::AccrueInterest {
+        update_interest_model: Some(true),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => {}
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // no controller to step
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![]);
+
+    let controller_state = ControllerStateResponse {
+        target_utilization: Decimal256::percent(80),
+        utilization: Decimal256::percent(50),
+        base_rate: Decimal256::percent(2),
+        last_step: Decimal256::zero(),
+        last_step_up: false,
+        last_executed_height: env.block.height - 50,
+        epoch_period: 100,
+    };
+
+    // the controller stepped less than an epoch ago
+    deps.querier
+        .with_controller_state(Some(controller_state.clone()));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![]);
+
+    deps.querier
+        .with_controller_state(Some(ControllerStateResponse {
+            last_executed_height: env.block.height - 100,
+            ..controller_state
+        }));
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "addr0000".to_string(),
-            amount: vec![deduct_tax(
-                deps.as_ref(),
-                Coin {
-                    denom: "uusd".to_string(),
-                    amount: Uint128::from(100000u128),
-                }
-            )
-            .unwrap()]
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "interest".to_string(),
+            funds: vec![],
+            msg: to_binary(&InterestModelExecuteMsg::ExecuteEpochOperations {
+                market_balance: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+                total_liabilities: Decimal256::from_uint256(1000000u64),
+                total_reserves: Decimal256::zero(),
+                block_height: env.block.height,
+            })
+            .unwrap(),
         }))]
     );
 }
 
 #[test]
-fn claim_rewards() {
+fn accrued_and_largest_borrowers() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
 
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
@@ -1555,8 +5604,10 @@ fn claim_rewards() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
-
     let info = mock_info(
         "addr0000",
         &[Coin {
@@ -1564,8 +5615,6 @@ fn claim_rewards() {
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
-
-    // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
@@ -1588,97 +5637,348 @@ fn claim_rewards() {
         collector_contract: "collector".to_string(),
         distributor_contract: "distributor".to_string(),
     };
-    let info = mock_info("addr0000", &[]);
     let mut env = mock_env();
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     deps.querier
         .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
-    deps.querier
-        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_borrow_limit(&[
+        (&"addr0000".to_string(), &Uint256::from(1000000u64)),
+        (&"addr0001".to_string(), &Uint256::from(1000000u64)),
+        (&"addr0002".to_string(), &Uint256::from(1000000u64)),
+    ]);
+
+    for (borrower, borrow_amount) in [
+        ("addr0000", 500000u64),
+        ("addr0001", 100000u64),
+        ("addr0002", 300000u64),
+    ] {
+        let msg = ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(borrow_amount),
+            to: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(borrower, &[]), msg).unwrap();
+    }
 
-    store_state(
-        deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
+    // the global interest index doubles after 100 blocks
+    env.block.height += 100;
+
+    let loans = |res: Binary| -> Vec<(String, Uint256)> {
+        let res: BorrowerInfosResponse = from_binary(&res).unwrap();
+        res.borrower_infos
+            .into_iter()
+            .map(|info| (info.borrower, info.loan_amount))
+            .collect()
+    };
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AccruedBorrowerInfos {
+            start_after: None,
+            limit: None,
+            block_height: None,
         },
     )
     .unwrap();
+    assert_eq!(
+        loans(res),
+        vec![
+            ("addr0000".to_string(), Uint256::from(1000000u64)),
+            ("addr0001".to_string(), Uint256::from(200000u64)),
+            ("addr0002".to_string(), Uint256::from(600000u64)),
+        ]
+    );
 
-    // zero loan claim, will return empty messages
-    let msg = ExecuteMsg::ClaimRewards { to: None };
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-    assert_eq!(res.messages.len(), 0);
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AccruedBorrowerInfos {
+            start_after: Some("addr0000".to_string()),
+            limit: Some(1),
+            block_height: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        loans(res),
+        vec![("addr0001".to_string(), Uint256::from(200000u64))]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::LargestBorrowers {
+            start_after: None,
+            limit: Some(2),
+            block_height: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        loans(res),
+        vec![
+            ("addr0000".to_string(), Uint256::from(1000000u64)),
+            ("addr0002".to_string(), Uint256::from(600000u64)),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::LargestBorrowers {
+            start_after: Some("addr0002".to_string()),
+            limit: None,
+            block_height: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        loans(res),
+        vec![("addr0001".to_string(), Uint256::from(200000u64))]
+    );
+
+    // fully repaid borrowers leave the loan size index
+    let info = mock_info(
+        "addr0002",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(600000u64),
+        }],
+    );
+    let msg = ExecuteMsg::RepayStable { borrower: None };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::LargestBorrowers {
+            start_after: None,
+            limit: None,
+            block_height: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        loans(res),
+        vec![
+            ("addr0000".to_string(), Uint256::from(1000000u64)),
+            ("addr0001".to_string(), Uint256::from(200000u64)),
+        ]
+    );
+}
+
+fn largest_borrowers(deps: Deps, env: Env) -> Vec<String> {
+    let res: BorrowerInfosResponse = from_binary(
+        &query(
+            deps,
+            env,
+            QueryMsg::LargestBorrowers {
+                start_after: None,
+                limit: None,
+                block_height: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.borrower_infos
+        .into_iter()
+        .map(|info| info.borrower)
+        .collect()
+}
+
+#[test]
+fn largest_borrowers_by_principal() {
+    let mut deps = setup_market();
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_limit(&[
+        (&"addr0000".to_string(), &Uint256::from(1000000u64)),
+        (&"addr0001".to_string(), &Uint256::from(1000000u64)),
+    ]);
+
+    let mut env = mock_env();
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(300000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
 
+    // the first loan doubles to 600000 before the second one
+    env.block.height += 100;
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
     };
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
 
-    // zero block passed
-    let msg = ExecuteMsg::ClaimRewards {
-        to: Some("addr0001".to_string()),
+    assert_eq!(
+        largest_borrowers(deps.as_ref(), env),
+        vec!["addr0000".to_string(), "addr0001".to_string()]
+    );
+}
+
+#[test]
+fn largest_borrowers_with_fixed_loans() {
+    let mut deps = setup_market();
+    let mut config = read_config(deps.as_ref().storage).unwrap();
+    config.fixed_rate = Some(FixedRateConfig {
+        premium: Decimal256::from_str("0.005").unwrap(),
+        rebalance_threshold: Decimal256::percent(1),
+    });
+    store_config(deps.as_mut().storage, &config).unwrap();
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.with_borrow_limit(&[
+        (&"addr0000".to_string(), &Uint256::from(1000000u64)),
+        (&"addr0001".to_string(), &Uint256::from(1000000u64)),
+    ]);
+
+    let mut env = mock_env();
+    let msg = ExecuteMsg::BorrowStableFixed {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
     };
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-    assert_eq!(res.messages.len(), 0);
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // the fixed rate loan accrues to 250000 while
+    // the global interest index grows to 6
+    env.block.height += 100;
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(5))]);
+    let msg = ExecuteMsg::RebalanceFixedRate {
+        borrower: "addr0000".to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0002", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(260000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    assert_eq!(
+        largest_borrowers(deps.as_ref(), env),
+        vec!["addr0001".to_string(), "addr0000".to_string()]
+    );
+}
+
+#[test]
+fn largest_borrowers_above_u128() {
+    let mut deps = setup_market();
+
+    for (borrower, loan_amount) in [
+        ("addr0000", Uint256::from(u128::MAX)),
+        ("addr0001", Uint256::from(u128::MAX) * Uint256::from(10u64)),
+        ("addr0002", Uint256::from(1000u64)),
+    ] {
+        let borrower_raw = deps.api.addr_canonicalize(borrower).unwrap();
+        store_borrower_info(
+            deps.as_mut().storage,
+            &borrower_raw,
+            &BorrowerInfo {
+                interest_index: Decimal256::one(),
+                reward_index: Decimal256::zero(),
+                loan_amount,
+                pending_rewards: Decimal256::zero(),
+            },
+        )
+        .unwrap();
+        store_loan_size(deps.as_mut().storage, &borrower_raw, Decimal256::one()).unwrap();
+    }
+
+    let borrowers = read_largest_borrowers(deps.as_ref().storage, None, None).unwrap();
+    let borrowers: Vec<String> = borrowers
+        .iter()
+        .map(|borrower| deps.api.addr_humanize(borrower).unwrap().to_string())
+        .collect();
+    assert_eq!(
+        borrowers,
+        vec![
+            "addr0001".to_string(),
+            "addr0000".to_string(),
+            "addr0002".to_string()
+        ]
+    );
+}
+
+#[test]
+fn reindex_loan_sizes() {
+    let mut deps = setup_market();
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+
+    // loans stored before the loan size index
+    for (borrower, loan_amount) in [
+        ("addr0000", 100000u64),
+        ("addr0001", 300000u64),
+        ("addr0002", 200000u64),
+    ] {
+        let borrower_raw = deps.api.addr_canonicalize(borrower).unwrap();
+        Bucket::new(deps.as_mut().storage, PREFIX_LIABILITY)
+            .save(
+                borrower_raw.as_slice(),
+                &BorrowerInfo {
+                    interest_index: Decimal256::one(),
+                    reward_index: Decimal256::zero(),
+                    loan_amount: Uint256::from(loan_amount),
+                    pending_rewards: Decimal256::zero(),
+                },
+            )
+            .unwrap();
+    }
+    assert!(largest_borrowers(deps.as_ref(), mock_env()).is_empty());
 
-    // 100 blocks passed
-    env.block.height += 100;
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let msg = ExecuteMsg::ReindexLoanSizes {
+        start_after: None,
+        limit: Some(2),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0003", &[]), msg).unwrap();
     assert_eq!(
-        res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "distributor".to_string(),
-            funds: vec![],
-            msg: to_binary(&FaucetExecuteMsg::Spend {
-                recipient: "addr0001".to_string(),
-                amount: Uint128::from(33u128),
-            })
-            .unwrap(),
-        }))]
+        res.attributes,
+        vec![
+            attr("action", "reindex_loan_sizes"),
+            attr("count", "2"),
+            attr("last_borrower", "addr0001"),
+        ]
     );
-
-    let res: BorrowerInfoResponse = from_binary(
-        &query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::BorrowerInfo {
-                borrower: "addr0000".to_string(),
-                block_height: None,
-            },
-        )
-        .unwrap(),
-    )
-    .unwrap();
     assert_eq!(
-        res.pending_rewards,
-        Decimal256::from_str("0.333333333333").unwrap()
+        largest_borrowers(deps.as_ref(), mock_env()),
+        vec!["addr0001".to_string(), "addr0000".to_string()]
     );
+
+    let msg = ExecuteMsg::ReindexLoanSizes {
+        start_after: Some("addr0001".to_string()),
+        limit: Some(2),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0003", &[]), msg).unwrap();
+    assert_eq!(res.attributes[1], attr("count", "1"));
     assert_eq!(
-        res.reward_index,
-        Decimal256::from_str("0.000066666666666666").unwrap()
+        largest_borrowers(deps.as_ref(), mock_env()),
+        vec![
+            "addr0001".to_string(),
+            "addr0002".to_string(),
+            "addr0000".to_string()
+        ]
     );
+
+    // reindexing again keeps a single entry per borrower
+    let msg = ExecuteMsg::ReindexLoanSizes {
+        start_after: None,
+        limit: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("addr0003", &[]), msg).unwrap();
+    assert_eq!(largest_borrowers(deps.as_ref(), mock_env()).len(), 3);
 }
 
 #[test]
-fn execute_epoch_operations() {
+fn emission_budget() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
     }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
-    );
 
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
@@ -1686,8 +5986,10 @@ fn execute_epoch_operations() {
         aterra_code_id: 123u64,
         anc_emission_rate: Decimal256::one(),
         max_borrow_factor: Decimal256::one(),
+        rate_unit: None,
+        flash_loan_fee: None,
+        fixed_rate: None,
     };
-
     let info = mock_info(
         "addr0000",
         &[Coin {
@@ -1695,8 +5997,6 @@ fn execute_epoch_operations() {
             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
         }],
     );
-
-    // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     // Register anchor token contract
@@ -1720,7 +6020,7 @@ fn execute_epoch_operations() {
         distributor_contract: "distributor".to_string(),
     };
     let mut env = mock_env();
-    let mut info = mock_info("addr0000", &[]);
+    let info = mock_info("addr0000", &[]);
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     deps.querier
@@ -1728,213 +6028,108 @@ fn execute_epoch_operations() {
     deps.querier
         .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
 
-    store_state(
-        deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
-    )
-    .unwrap();
-
-    env.block.height += 100;
-
-    // reserve == 3000
-    let msg = ExecuteMsg::ExecuteEpochOperations {
-        deposit_rate: Decimal256::one(),
-        target_deposit_rate: Decimal256::one(),
-        threshold_deposit_rate: Decimal256::one(),
-        distributed_interest: Uint256::zero(),
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
     };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-    // only overseer can execute this
+    let msg = ExecuteMsg::UpdateEmissionBudget {
+        emission_budget: Some(Uint256::from(150u64)),
+        epoch_emission_cap: Some(Uint256::from(120u64)),
+    };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
     match res {
         Err(ContractError::Unauthorized {}) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    info.sender = Addr::unchecked("overseer");
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
     assert_eq!(
-        res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "collector".to_string(),
-            amount: vec![Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128::from(2970u128), // 1% tax
-            }],
-        }))]
+        res.attributes,
+        vec![
+            attr("action", "update_emission_budget"),
+            attr("emission_budget", "150"),
+            attr("epoch_emission_cap", "120"),
+        ]
     );
 
-    let state = read_state(deps.as_ref().storage).unwrap();
+    let budget = |deps: Deps, env: Env| -> EmissionBudgetResponse {
+        from_binary(&query(deps, env, QueryMsg::EmissionBudget { block_height: None }).unwrap())
+            .unwrap()
+    };
+
+    env.block.height += 100;
     assert_eq!(
-        state,
-        State {
-            total_liabilities: Decimal256::from_uint256(2000000u128),
-            total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::from_uint256(2u64),
-            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
-            anc_emission_rate: Decimal256::from_uint256(5u64),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
+        budget(deps.as_ref(), env.clone()),
+        EmissionBudgetResponse {
+            emission_budget: Some(Uint256::from(150u64)),
+            epoch_emission_cap: Some(Uint256::from(120u64)),
+            total_emitted: Decimal256::from_uint256(100u64),
+            epoch_emitted: Decimal256::from_uint256(100u64),
+            total_claimed: Uint256::zero(),
+            remaining_budget: Some(Decimal256::from_uint256(50u64)),
+            remaining_epoch_emission: Some(Decimal256::from_uint256(20u64)),
         }
     );
 
-    // When there is not enough balance to cover reserve
-    // no message will be sent and reserve will be left as same
-    deps.querier.update_balance(
-        MOCK_CONTRACT_ADDR.to_string(),
-        vec![Coin {
-            denom: "uusd".to_string(),
-            amount: Uint128::from(2999u128),
-        }],
-    );
-
-    let mut env = mock_env();
-    let info = mock_info("overseer", &[]);
-    store_state(
-        deps.as_mut().storage,
-        &State {
-            total_liabilities: Decimal256::from_uint256(1000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::one(),
-            global_reward_index: Decimal256::zero(),
-            anc_emission_rate: Decimal256::one(),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        },
-    )
-    .unwrap();
-
+    // the epoch cap stops the emissions until the next epoch
     env.block.height += 100;
+    let res = budget(deps.as_ref(), env.clone());
+    assert_eq!(res.total_emitted, Decimal256::from_uint256(120u64));
+    assert_eq!(res.remaining_epoch_emission, Some(Decimal256::zero()));
 
-    // reserve == 3000
     let msg = ExecuteMsg::ExecuteEpochOperations {
         deposit_rate: Decimal256::one(),
         target_deposit_rate: Decimal256::one(),
         threshold_deposit_rate: Decimal256::one(),
         distributed_interest: Uint256::zero(),
     };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("overseer", &[]), msg).unwrap();
 
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-    assert_eq!(res.messages.len(), 0);
-
-    let state = read_state(deps.as_ref().storage).unwrap();
+    let res = budget(deps.as_ref(), env.clone());
+    assert_eq!(res.epoch_emitted, Decimal256::zero());
     assert_eq!(
-        state,
-        State {
-            total_liabilities: Decimal256::from_uint256(2000000u128),
-            total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
-            global_interest_index: Decimal256::from_uint256(2u64),
-            global_reward_index: Decimal256::from_str("0.0001").unwrap(),
-            anc_emission_rate: Decimal256::from_uint256(5u64),
-            prev_aterra_supply: Uint256::zero(),
-            prev_exchange_rate: Decimal256::one(),
-        }
+        res.remaining_epoch_emission,
+        Some(Decimal256::from_uint256(120u64))
     );
-}
-
-// #[test]
-// fn borrow_repay_execute_operations() {
-//     let mut deps = mock_dependencies(
-//         20,
-//         &[Coin {
-//             denom: "uusd".to_string(),
-//             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-//         }],
-//     );
-
-//     let msg = InstantiateMsg {
-//         owner_addr: "owner".to_string(),
-//         stable_denom: "uusd".to_string(),
-//         aterra_code_id: 123u64,
-//         anc_emission_rate: Decimal256::one(),
-//         max_borrow_factor: Decimal256::one(),
-//     };
-
-//     let env = mock_env(
-//         "addr0000",
-//         &[Coin {
-//             denom: "uusd".to_string(),
-//             amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-//         }],
-//     );
-
-//     // we can just call .unwrap() to assert this was a success
-//     let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-//     // Register anchor token contract
-//     let msg = ExecuteMsg::RegisterATerra {};
-//     let env = mock_env("AT-uusd", &[]);
-//     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-
-//     // Register overseer contract
-//     let msg = ExecuteMsg::RegisterContracts {
-//         overseer_contract: "overseer".to_string(),
-//         interest_model: "interest".to_string(),
-//         distribution_model: "distribution".to_string(),
-//         collector_contract: "collector".to_string(),
-//         distributor_contract: "distributor".to_string(),
-//     };
-//     let env = mock_env("addr0000", &[]);
-//     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-
-//     deps.querier
-//         .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
-//     deps.querier.with_token_balances(&[(
-//         &HumanAddr::from("AT-uusd"),
-//         &[(
-//             &MOCK_CONTRACT_ADDR.to_string(),
-//             &Uint128::from(1000000u128),
-//         )],
-//     )]);
-//     deps.querier.update_balance(
-//         MOCK_CONTRACT_ADDR.to_string(),
-//         vec![Coin {
-//             denom: "uusd".to_string(),
-//             amount: Uint128::from(373025692u128),
-//         }],
-//     );
 
-//     let mut env = mock_env("overseer", &[]);
+    // then the total budget does
+    env.block.height += 100;
+    let res = budget(deps.as_ref(), env.clone());
+    assert_eq!(res.total_emitted, Decimal256::from_uint256(150u64));
+    assert_eq!(res.epoch_emitted, Decimal256::from_uint256(30u64));
+    assert_eq!(res.remaining_budget, Some(Decimal256::zero()));
 
-//     store_state(
-//         deps.as_mut().storage,
-//         &State {
-//             total_liabilities: Decimal256::from_str("8.198749212085782102").unwrap(),
-//             total_reserves: Decimal256::from_str("372025697.802295205294219818").unwrap(),
-//             last_interest_updated: env.block.height,
-//             last_reward_updated: env.block.height,
-//             global_interest_index: Decimal256::from_str("1.000005078160215988").unwrap(),
-//             global_reward_index: Decimal256::from_str("119531.277425251814227128").unwrap(),
-//             anc_emission_rate: Decimal256::from_str("980001.99").unwrap(),
-//             prev_aterra_supply: Uint256::from(1000000001000000u128),
-//             prev_exchange_rate: Decimal256::from_str("1.0000022").unwrap(),
-//         },
-//     )
-//     .unwrap();
+    let msg = ExecuteMsg::ClaimRewards { to: None };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "claim_rewards"), attr("claim_amount", "150"),]
+    );
 
-//     env.block.height += 100;
+    env.block.height += 100;
+    let res = budget(deps.as_ref(), env);
+    assert_eq!(res.total_emitted, Decimal256::from_uint256(150u64));
+    assert_eq!(res.total_claimed, Uint256::from(150u64));
+}
 
-//     let msg = ExecuteMsg::ExecuteEpochOperations {
-//         deposit_rate: Decimal256::one(),
-//         target_deposit_rate: Decimal256::from_str("0.000000040762727704").unwrap(),
-//         threshold_deposit_rate: Decimal256::from_str("0.000000030572045778").unwrap(),
-//     };
+#[test]
+fn skip_model_epoch_operations() {
+    let mut deps = setup_market();
 
-//     // only overseer can execute this
-//     let _ = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-// }
+    // a model without epoch operations rejects them
+    let reply_msg = Reply {
+        id: EPOCH_OPERATIONS_REPLY_ID,
+        result: ContractResult::Err("unknown variant `execute_epoch_operations`".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    assert_eq!(res.messages, vec![]);
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "skip_model_epoch_operations"),
+            attr("error", "unknown variant `execute_epoch_operations`"),
+        ]
+    );
+}
@@ -1,15 +1,18 @@
 use crate::error::ContractError;
 use crate::state::{
-    read_config, read_feeder, read_price, read_prices, store_config, store_feeder, store_price,
-    Config, PriceInfo,
+    read_config, read_feeder_set, read_prices, read_submission, store_config, store_feeder_set,
+    store_price, store_submission, Config, FeederSet, PriceInfo,
 };
 use cosmwasm_bignumber::Decimal256;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{attr, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    attr, to_binary, Binary, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage,
+};
 use moneymarket::oracle::{
-    ConfigResponse, ExecuteMsg, FeederResponse, InstantiateMsg, PriceResponse, PricesResponse,
-    PricesResponseElem, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeederResponse, FeedersResponse, InstantiateMsg, PriceResponse,
+    PricesResponse, PricesResponseElem, QueryMsg, SubmissionResponse, SubmissionsResponse,
 };
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -40,6 +43,12 @@ pub fn execute(
     match msg {
         ExecuteMsg::UpdateConfig { owner } => update_config(deps, info, owner),
         ExecuteMsg::RegisterFeeder { asset, feeder } => register_feeder(deps, info, asset, feeder),
+        ExecuteMsg::RegisterFeeders {
+            asset,
+            feeders,
+            quorum,
+            price_timeout,
+        } => register_feeders(deps, info, asset, feeders, quorum, price_timeout),
         ExecuteMsg::FeedPrice { prices } => feed_prices(deps, env, info, prices),
     }
 }
@@ -73,7 +82,16 @@ pub fn register_feeder(
         return Err(ContractError::Unauthorized {});
     }
 
-    store_feeder(deps.storage, &asset, &deps.api.addr_canonicalize(&feeder)?)?;
+    // A single feeder sets the price on its own
+    store_feeder_set(
+        deps.storage,
+        &asset,
+        &FeederSet {
+            feeders: vec![deps.api.addr_canonicalize(&feeder)?],
+            quorum: 1,
+            price_timeout: None,
+        },
+    )?;
 
     Ok(Response::new().add_attributes(vec![
         attr("action", "register_feeder"),
@@ -82,6 +100,55 @@ pub fn register_feeder(
     ]))
 }
 
+pub fn register_feeders(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset: String,
+    feeders: Vec<String>,
+    quorum: u64,
+    price_timeout: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if quorum == 0 || quorum > feeders.len() as u64 {
+        return Err(ContractError::InvalidQuorum {});
+    }
+
+    let mut feeders_raw: Vec<CanonicalAddr> = vec![];
+    for feeder in feeders.iter() {
+        let feeder_raw = deps.api.addr_canonicalize(feeder)?;
+        if feeders_raw.contains(&feeder_raw) {
+            return Err(ContractError::DuplicateFeeder(feeder.to_string()));
+        }
+
+        feeders_raw.push(feeder_raw);
+    }
+
+    store_feeder_set(
+        deps.storage,
+        &asset,
+        &FeederSet {
+            feeders: feeders_raw,
+            quorum,
+            price_timeout,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_feeders"),
+        attr("asset", asset),
+        attr("feeders", feeders.join(",")),
+        attr("quorum", quorum.to_string()),
+        attr(
+            "price_timeout",
+            price_timeout.map_or_else(|| "none".to_string(), |v| v.to_string()),
+        ),
+    ]))
+}
+
 pub fn feed_prices(
     deps: DepsMut,
     env: Env,
@@ -95,33 +162,43 @@ pub fn feed_prices(
         let price: Decimal256 = price.1;
 
         // Check feeder permission
-        let feeder = read_feeder(deps.storage, &asset)?;
-        if feeder != sender_raw {
+        let feeder_set = read_feeder_set(deps.storage, &asset)?;
+        if !feeder_set.feeders.contains(&sender_raw) {
             return Err(ContractError::Unauthorized {});
         }
 
         attributes.push(attr("asset", asset.to_string()));
         attributes.push(attr("price", price.to_string()));
 
-        store_price(
+        store_submission(
             deps.storage,
             &asset,
+            &sender_raw,
             &PriceInfo {
                 last_updated_time: env.block.time.seconds(),
                 price,
             },
         )?;
+
+        // Keep the last aggregated price for the price list
+        if let Some(price_info) =
+            aggregate_price(deps.storage, &asset, &feeder_set, env.block.time.seconds())?
+        {
+            store_price(deps.storage, &asset, &price_info)?;
+        }
     }
 
     Ok(Response::new().add_attributes(attributes))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::Feeder { asset } => to_binary(&query_feeder(deps, asset)?),
-        QueryMsg::Price { base, quote } => to_binary(&query_price(deps, base, quote)?),
+        QueryMsg::Feeders { asset } => to_binary(&query_feeders(deps, asset)?),
+        QueryMsg::Submissions { asset } => to_binary(&query_submissions(deps, asset)?),
+        QueryMsg::Price { base, quote } => to_binary(&query_price(deps, env, base, quote)?),
         QueryMsg::Prices { start_after, limit } => {
             to_binary(&query_prices(deps, start_after, limit)?)
         }
@@ -138,17 +215,116 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     Ok(resp)
 }
 
+/// Median of the fresh submissions of the asset feeders,
+/// or None when fewer than the quorum are fresh
+fn aggregate_price(
+    storage: &dyn Storage,
+    asset: &str,
+    feeder_set: &FeederSet,
+    current_time: u64,
+) -> StdResult<Option<PriceInfo>> {
+    let mut submissions: Vec<PriceInfo> = vec![];
+    for feeder in feeder_set.feeders.iter() {
+        if let Some(submission) = read_submission(storage, asset, feeder)? {
+            let fresh = match feeder_set.price_timeout {
+                Some(price_timeout) => {
+                    current_time.saturating_sub(submission.last_updated_time) <= price_timeout
+                }
+                None => true,
+            };
+
+            if fresh {
+                submissions.push(submission);
+            }
+        }
+    }
+
+    if (submissions.len() as u64) < feeder_set.quorum {
+        return Ok(None);
+    }
+
+    let mut prices: Vec<Decimal256> = submissions.iter().map(|s| s.price).collect();
+    prices.sort();
+
+    let mid = prices.len() / 2;
+    let price = if prices.len() % 2 == 1 {
+        prices[mid]
+    } else {
+        (prices[mid - 1] + prices[mid]) / Decimal256::from_uint256(2u64)
+    };
+
+    // The price is as recent as the newest quorum of submissions,
+    // so feeders which stopped submitting do not hold it back
+    let mut updated_times: Vec<u64> = submissions.iter().map(|s| s.last_updated_time).collect();
+    updated_times.sort_unstable_by(|a, b| b.cmp(a));
+    let last_updated_time = updated_times[feeder_set.quorum as usize - 1];
+
+    Ok(Some(PriceInfo {
+        price,
+        last_updated_time,
+    }))
+}
+
+fn read_fresh_price(deps: Deps, asset: &str, current_time: u64) -> StdResult<PriceInfo> {
+    let feeder_set = read_feeder_set(deps.storage, asset)?;
+    match aggregate_price(deps.storage, asset, &feeder_set, current_time)? {
+        Some(price_info) => Ok(price_info),
+        None => Err(StdError::generic_err(
+            "Not enough fresh price data for the specified asset",
+        )),
+    }
+}
+
 fn query_feeder(deps: Deps, asset: String) -> StdResult<FeederResponse> {
-    let feeder = read_feeder(deps.storage, &asset)?;
+    let feeder_set = read_feeder_set(deps.storage, &asset)?;
+    if feeder_set.feeders.len() > 1 {
+        return Err(StdError::generic_err(
+            "The asset has several feeders; query feeders instead",
+        ));
+    }
+
     let resp = FeederResponse {
         asset,
-        feeder: deps.api.addr_humanize(&feeder)?.to_string(),
+        feeder: deps.api.addr_humanize(&feeder_set.feeders[0])?.to_string(),
     };
 
     Ok(resp)
 }
 
-fn query_price(deps: Deps, base: String, quote: String) -> StdResult<PriceResponse> {
+fn query_feeders(deps: Deps, asset: String) -> StdResult<FeedersResponse> {
+    let feeder_set = read_feeder_set(deps.storage, &asset)?;
+    let resp = FeedersResponse {
+        asset,
+        feeders: feeder_set
+            .feeders
+            .iter()
+            .map(|feeder| Ok(deps.api.addr_humanize(feeder)?.to_string()))
+            .collect::<StdResult<Vec<String>>>()?,
+        quorum: feeder_set.quorum,
+        price_timeout: feeder_set.price_timeout,
+    };
+
+    Ok(resp)
+}
+
+fn query_submissions(deps: Deps, asset: String) -> StdResult<SubmissionsResponse> {
+    let feeder_set = read_feeder_set(deps.storage, &asset)?;
+
+    let mut submissions: Vec<SubmissionResponse> = vec![];
+    for feeder in feeder_set.feeders.iter() {
+        if let Some(submission) = read_submission(deps.storage, &asset, feeder)? {
+            submissions.push(SubmissionResponse {
+                feeder: deps.api.addr_humanize(feeder)?.to_string(),
+                price: submission.price,
+                last_updated_time: submission.last_updated_time,
+            });
+        }
+    }
+
+    Ok(SubmissionsResponse { asset, submissions })
+}
+
+fn query_price(deps: Deps, env: Env, base: String, quote: String) -> StdResult<PriceResponse> {
     let config: Config = read_config(deps.storage)?;
     let quote_price = if config.base_asset == quote {
         PriceInfo {
@@ -156,7 +332,7 @@ fn query_price(deps: Deps, base: String, quote: String) -> StdResult<PriceRespon
             last_updated_time: 9999999999,
         }
     } else {
-        read_price(deps.storage, &quote)?
+        read_fresh_price(deps, &quote, env.block.time.seconds())?
     };
 
     let base_price = if config.base_asset == base {
@@ -165,7 +341,7 @@ fn query_price(deps: Deps, base: String, quote: String) -> StdResult<PriceRespon
             last_updated_time: 9999999999,
         }
     } else {
-        read_price(deps.storage, &base)?
+        read_fresh_price(deps, &base, env.block.time.seconds())?
     };
 
     Ok(PriceResponse {
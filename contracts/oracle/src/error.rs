@@ -11,4 +11,10 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Quorum must be between one and the number of feeders")]
+    InvalidQuorum {},
+
+    #[error("Feeder registered more than once: {0}")]
+    DuplicateFeeder(String),
 }
@@ -8,7 +8,10 @@ use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
 use moneymarket::oracle::PricesResponseElem;
 
 static PREFIX_PRICE: &[u8] = b"price";
-static PREFIX_FEEDER: &[u8] = b"feeder";
+// single feeder of the assets registered before feeder sets
+pub static PREFIX_FEEDER: &[u8] = b"feeder";
+static PREFIX_FEEDER_SET: &[u8] = b"feeder_set";
+static PREFIX_SUBMISSION: &[u8] = b"submission";
 
 static KEY_CONFIG: &[u8] = b"config";
 
@@ -77,26 +80,66 @@ pub fn read_prices(
         .collect()
 }
 
-pub fn store_feeder(
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeederSet {
+    pub feeders: Vec<CanonicalAddr>,
+    pub quorum: u64,
+    pub price_timeout: Option<u64>,
+}
+
+pub fn store_feeder_set(
     storage: &mut dyn Storage,
     asset: &str,
-    feeder: &CanonicalAddr,
+    feeder_set: &FeederSet,
 ) -> StdResult<()> {
-    let mut price_bucket: Bucket<CanonicalAddr> = Bucket::new(storage, PREFIX_FEEDER);
-    price_bucket.save(asset.as_bytes(), feeder)
+    Bucket::<CanonicalAddr>::new(storage, PREFIX_FEEDER).remove(asset.as_bytes());
+
+    let mut feeder_set_bucket: Bucket<FeederSet> = Bucket::new(storage, PREFIX_FEEDER_SET);
+    feeder_set_bucket.save(asset.as_bytes(), feeder_set)
 }
 
-pub fn read_feeder(storage: &dyn Storage, asset: &str) -> StdResult<CanonicalAddr> {
-    let price_bucket: ReadonlyBucket<CanonicalAddr> = ReadonlyBucket::new(storage, PREFIX_FEEDER);
-    let res = price_bucket.load(asset.as_bytes());
-    match res {
-        Ok(data) => Ok(data),
-        Err(_err) => Err(StdError::generic_err(
+pub fn read_feeder_set(storage: &dyn Storage, asset: &str) -> StdResult<FeederSet> {
+    let feeder_set_bucket: ReadonlyBucket<FeederSet> =
+        ReadonlyBucket::new(storage, PREFIX_FEEDER_SET);
+    if let Some(feeder_set) = feeder_set_bucket.may_load(asset.as_bytes())? {
+        return Ok(feeder_set);
+    }
+
+    // An asset registered before feeder sets is priced by its feeder alone
+    let feeder_bucket: ReadonlyBucket<CanonicalAddr> = ReadonlyBucket::new(storage, PREFIX_FEEDER);
+    match feeder_bucket.may_load(asset.as_bytes())? {
+        Some(feeder) => Ok(FeederSet {
+            feeders: vec![feeder],
+            quorum: 1,
+            price_timeout: None,
+        }),
+        None => Err(StdError::generic_err(
             "No feeder data for the specified asset exist",
         )),
     }
 }
 
+pub fn store_submission(
+    storage: &mut dyn Storage,
+    asset: &str,
+    feeder: &CanonicalAddr,
+    price: &PriceInfo,
+) -> StdResult<()> {
+    let mut submission_bucket: Bucket<PriceInfo> =
+        Bucket::multilevel(storage, &[PREFIX_SUBMISSION, asset.as_bytes()]);
+    submission_bucket.save(feeder.as_slice(), price)
+}
+
+pub fn read_submission(
+    storage: &dyn Storage,
+    asset: &str,
+    feeder: &CanonicalAddr,
+) -> StdResult<Option<PriceInfo>> {
+    let submission_bucket: ReadonlyBucket<PriceInfo> =
+        ReadonlyBucket::multilevel(storage, &[PREFIX_SUBMISSION, asset.as_bytes()]);
+    submission_bucket.may_load(feeder.as_slice())
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<String>) -> Option<Vec<u8>> {
     start_after.map(|idx| {
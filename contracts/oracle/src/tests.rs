@@ -1,11 +1,15 @@
 use crate::contract::{execute, instantiate, query};
 use crate::error::ContractError;
+use crate::state::PREFIX_FEEDER;
 use cosmwasm_bignumber::Decimal256;
-use cosmwasm_std::from_binary;
-use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{from_binary, Api, CanonicalAddr, Deps, DepsMut, Env, OwnedDeps, StdResult};
+use cosmwasm_storage::Bucket;
 use moneymarket::oracle::{
-    ConfigResponse, ExecuteMsg, FeederResponse, InstantiateMsg, PriceResponse, PricesResponse,
-    PricesResponseElem, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeederResponse, FeedersResponse, InstantiateMsg, PriceResponse,
+    PricesResponse, PricesResponseElem, QueryMsg, SubmissionResponse, SubmissionsResponse,
 };
 use std::str::FromStr;
 
@@ -234,3 +238,333 @@ fn feed_price() {
         _ => panic!("Must return unauthorized error"),
     }
 }
+
+/// Oracle with four mAAPL feeders and a quorum of three
+fn setup_feeders(price_timeout: Option<u64>) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_asset: "base0000".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterFeeders {
+        asset: "mAAPL".to_string(),
+        feeders: vec![
+            "feeder0000".to_string(),
+            "feeder0001".to_string(),
+            "feeder0002".to_string(),
+            "feeder0003".to_string(),
+        ],
+        quorum: 3,
+        price_timeout,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner0000", &[]), msg).unwrap();
+
+    deps
+}
+
+#[test]
+fn register_feeders() {
+    let deps = setup_feeders(Some(60));
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Feeders {
+            asset: "mAAPL".to_string(),
+        },
+    )
+    .unwrap();
+    let value: FeedersResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value,
+        FeedersResponse {
+            asset: "mAAPL".to_string(),
+            feeders: vec![
+                "feeder0000".to_string(),
+                "feeder0001".to_string(),
+                "feeder0002".to_string(),
+                "feeder0003".to_string(),
+            ],
+            quorum: 3,
+            price_timeout: Some(60),
+        }
+    );
+
+    // A single feeder does not describe the set
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Feeder {
+            asset: "mAAPL".to_string(),
+        },
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn legacy_feeder() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_asset: "base0000".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // Feeder stored before feeder sets
+    let feeder_raw = deps.api.addr_canonicalize("feeder0000").unwrap();
+    Bucket::<CanonicalAddr>::new(deps.as_mut().storage, PREFIX_FEEDER)
+        .save(b"mAAPL", &feeder_raw)
+        .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Feeder {
+            asset: "mAAPL".to_string(),
+        },
+    )
+    .unwrap();
+    let value: FeederResponse = from_binary(&res).unwrap();
+    assert_eq!(value.feeder, "feeder0000");
+
+    submit_price(deps.as_mut(), mock_env(), "feeder0000", "1.2");
+    assert_eq!(
+        query_rate(deps.as_ref(), mock_env()).unwrap().rate,
+        Decimal256::from_str("1.2").unwrap()
+    );
+}
+
+#[test]
+fn register_invalid_feeders() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_asset: "base0000".to_string(),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // Invalid quorum
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::RegisterFeeders {
+        asset: "mAAPL".to_string(),
+        feeders: vec!["feeder0000".to_string(), "feeder0001".to_string()],
+        quorum: 3,
+        price_timeout: Some(60),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidQuorum {}) => (),
+        _ => panic!("Must return invalid quorum error"),
+    }
+
+    // Duplicate feeder
+    let msg = ExecuteMsg::RegisterFeeders {
+        asset: "mAAPL".to_string(),
+        feeders: vec!["feeder0000".to_string(), "feeder0000".to_string()],
+        quorum: 1,
+        price_timeout: Some(60),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::DuplicateFeeder(feeder)) => assert_eq!(feeder, "feeder0000"),
+        _ => panic!("Must return duplicate feeder error"),
+    }
+
+    // Unauthorized err
+    let msg = ExecuteMsg::RegisterFeeders {
+        asset: "mAAPL".to_string(),
+        feeders: vec!["feeder0000".to_string()],
+        quorum: 1,
+        price_timeout: Some(60),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+}
+
+#[test]
+fn feed_price_unregistered_feeder() {
+    let mut deps = setup_feeders(Some(60));
+
+    let msg = ExecuteMsg::FeedPrice {
+        prices: vec![("mAAPL".to_string(), Decimal256::from_str("1.0").unwrap())],
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("feeder0004", &[]), msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+}
+
+#[test]
+fn median_price_below_quorum() {
+    let mut deps = setup_feeders(Some(60));
+
+    submit_price(deps.as_mut(), mock_env(), "feeder0000", "1.0");
+    submit_price(deps.as_mut(), mock_env(), "feeder0001", "100.0");
+    assert!(query_rate(deps.as_ref(), mock_env()).is_err());
+}
+
+#[test]
+fn median_price_ignores_outlier() {
+    let mut deps = setup_feeders(Some(60));
+    let mut env = mock_env();
+    let start_time = env.block.time.seconds();
+
+    submit_price(deps.as_mut(), env.clone(), "feeder0000", "1.0");
+    submit_price(deps.as_mut(), env.clone(), "feeder0001", "100.0");
+    env.block.time = env.block.time.plus_seconds(30);
+    submit_price(deps.as_mut(), env.clone(), "feeder0002", "1.2");
+    assert_eq!(
+        query_rate(deps.as_ref(), env.clone()).unwrap(),
+        PriceResponse {
+            rate: Decimal256::from_str("1.2").unwrap(),
+            last_updated_base: start_time,
+            last_updated_quote: 9999999999,
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Submissions {
+            asset: "mAAPL".to_string(),
+        },
+    )
+    .unwrap();
+    let value: SubmissionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value.submissions[2],
+        SubmissionResponse {
+            feeder: "feeder0002".to_string(),
+            price: Decimal256::from_str("1.2").unwrap(),
+            last_updated_time: start_time + 30,
+        }
+    );
+}
+
+#[test]
+fn median_price_even_submissions() {
+    let mut deps = setup_feeders(Some(60));
+
+    // The mean of the middle two
+    submit_price(deps.as_mut(), mock_env(), "feeder0000", "1.0");
+    submit_price(deps.as_mut(), mock_env(), "feeder0001", "100.0");
+    submit_price(deps.as_mut(), mock_env(), "feeder0002", "1.2");
+    submit_price(deps.as_mut(), mock_env(), "feeder0003", "1.3");
+    assert_eq!(
+        query_rate(deps.as_ref(), mock_env()).unwrap().rate,
+        Decimal256::from_str("1.25").unwrap()
+    );
+}
+
+#[test]
+fn median_price_stale_submissions() {
+    let mut deps = setup_feeders(Some(60));
+    let mut env = mock_env();
+    let start_time = env.block.time.seconds();
+
+    submit_price(deps.as_mut(), env.clone(), "feeder0000", "1.0");
+    submit_price(deps.as_mut(), env.clone(), "feeder0001", "100.0");
+    env.block.time = env.block.time.plus_seconds(30);
+    submit_price(deps.as_mut(), env.clone(), "feeder0002", "1.2");
+    submit_price(deps.as_mut(), env.clone(), "feeder0003", "1.3");
+
+    // The first two submissions go stale
+    env.block.time = env.block.time.plus_seconds(31);
+    assert!(query_rate(deps.as_ref(), env.clone()).is_err());
+
+    submit_price(deps.as_mut(), env.clone(), "feeder0000", "1.1");
+    assert_eq!(
+        query_rate(deps.as_ref(), env.clone()).unwrap(),
+        PriceResponse {
+            rate: Decimal256::from_str("1.2").unwrap(),
+            last_updated_base: start_time + 30,
+            last_updated_quote: 9999999999,
+        }
+    );
+
+    // The price list keeps the last aggregated price
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Prices {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let value: PricesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value,
+        PricesResponse {
+            prices: vec![PricesResponseElem {
+                asset: "mAAPL".to_string(),
+                price: Decimal256::from_str("1.2").unwrap(),
+                last_updated_time: start_time + 30,
+            }],
+        }
+    );
+}
+
+#[test]
+fn median_price_stopped_feeder() {
+    let mut deps = setup_feeders(None);
+    let mut env = mock_env();
+    let start_time = env.block.time.seconds();
+
+    submit_price(deps.as_mut(), env.clone(), "feeder0000", "1.0");
+    env.block.time = env.block.time.plus_seconds(100);
+    submit_price(deps.as_mut(), env.clone(), "feeder0001", "1.1");
+    submit_price(deps.as_mut(), env.clone(), "feeder0002", "1.2");
+    submit_price(deps.as_mut(), env.clone(), "feeder0003", "1.3");
+
+    // The feeder which stopped does not hold back the update time
+    assert_eq!(
+        query_rate(deps.as_ref(), env).unwrap(),
+        PriceResponse {
+            rate: Decimal256::from_str("1.15").unwrap(),
+            last_updated_base: start_time + 100,
+            last_updated_quote: 9999999999,
+        }
+    );
+}
+
+#[test]
+fn median_price_long_timeout() {
+    let mut deps = setup_feeders(Some(u64::MAX));
+
+    submit_price(deps.as_mut(), mock_env(), "feeder0000", "1.0");
+    submit_price(deps.as_mut(), mock_env(), "feeder0001", "1.1");
+    submit_price(deps.as_mut(), mock_env(), "feeder0002", "1.2");
+    assert_eq!(
+        query_rate(deps.as_ref(), mock_env()).unwrap().rate,
+        Decimal256::from_str("1.1").unwrap()
+    );
+}
+
+fn submit_price(deps: DepsMut, env: Env, feeder: &str, price: &str) {
+    let msg = ExecuteMsg::FeedPrice {
+        prices: vec![("mAAPL".to_string(), Decimal256::from_str(price).unwrap())],
+    };
+    execute(deps, env, mock_info(feeder, &[]), msg).unwrap();
+}
+
+fn query_rate(deps: Deps, env: Env) -> StdResult<PriceResponse> {
+    let res = query(
+        deps,
+        env,
+        QueryMsg::Price {
+            base: "mAAPL".to_string(),
+            quote: "base0000".to_string(),
+        },
+    )?;
+    from_binary(&res)
+}
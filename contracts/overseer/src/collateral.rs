@@ -1,14 +1,14 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult, SubMsg,
-    WasmMsg,
+    attr, to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, SubMsg, WasmMsg,
 };
 
 use crate::error::ContractError;
 use crate::querier::{query_borrower_info, query_liquidation_amount};
 use crate::state::{
-    read_all_collaterals, read_collaterals, read_config, read_whitelist_elem, store_collaterals,
-    Config, WhitelistElem,
+    read_all_collaterals, read_collaterals, read_config, read_epoch_state, read_whitelist_elem,
+    store_collaterals, store_epoch_state, Config, EpochState, WhitelistElem,
 };
 
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
@@ -16,7 +16,7 @@ use moneymarket::liquidation::LiquidationAmountResponse;
 use moneymarket::market::{BorrowerInfoResponse, ExecuteMsg as MarketExecuteMsg};
 use moneymarket::oracle::PriceResponse;
 use moneymarket::overseer::{AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse};
-use moneymarket::querier::{query_balance, query_price, TimeConstraints};
+use moneymarket::querier::{deduct_tax, query_balance, query_price, TimeConstraints};
 use moneymarket::tokens::{Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
 
 pub fn lock_collateral(
@@ -86,9 +86,13 @@ pub fn unlock_collateral(
         &cur_collaterals,
         Some(env.block.time.seconds()),
     )?;
-    let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps.as_ref(), market, borrower.clone(), env.block.height)?;
-    if borrow_limit < borrow_amount_res.loan_amount {
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        market,
+        borrower.clone(),
+        config.rate_unit.now(&env.block),
+    )?;
+    if borrow_limit < borrow_amount_res.loan_amount + borrow_amount_res.fixed_loan_amount {
         return Err(ContractError::UnlockTooLarge(borrow_limit.into()));
     }
 
@@ -143,9 +147,13 @@ pub fn liquidate_collateral(
         &cur_collaterals,
         Some(env.block.time.seconds()),
     )?;
-    let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps.as_ref(), market, borrower.clone(), env.block.height)?;
-    let borrow_amount = borrow_amount_res.loan_amount;
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        market,
+        borrower.clone(),
+        config.rate_unit.now(&env.block),
+    )?;
+    let borrow_amount = borrow_amount_res.loan_amount + borrow_amount_res.fixed_loan_amount;
 
     // borrow limit is equal or bigger than loan amount
     // cannot liquidation collaterals
@@ -205,6 +213,81 @@ pub fn liquidate_collateral(
         })))
 }
 
+pub fn write_off_bad_debt(
+    deps: DepsMut,
+    env: Env,
+    borrower: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let market_contract = deps.api.addr_humanize(&config.market_contract)?;
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+    if cur_collaterals.iter().any(|c| !c.1.is_zero()) {
+        return Err(ContractError::CannotWriteOffCollateralizedLoan {});
+    }
+
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        market_contract.clone(),
+        borrower.clone(),
+        config.rate_unit.now(&env.block),
+    )?;
+    let bad_debt = borrow_amount_res.loan_amount + borrow_amount_res.fixed_loan_amount;
+    if bad_debt.is_zero() {
+        return Err(ContractError::NoBadDebt {});
+    }
+
+    // Cover the bad debt with the interest buffer first
+    let interest_buffer = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+    let buffer_amount = std::cmp::min(bad_debt, interest_buffer);
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !buffer_amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: market_contract.to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom.to_string(),
+                    amount: buffer_amount.into(),
+                },
+            )?],
+        }));
+
+        // The spent buffer is not part of the next accrued buffer
+        let mut epoch_state: EpochState = read_epoch_state(deps.storage)?;
+        epoch_state.prev_interest_buffer = if epoch_state.prev_interest_buffer > buffer_amount {
+            epoch_state.prev_interest_buffer - buffer_amount
+        } else {
+            Uint256::zero()
+        };
+        store_epoch_state(deps.storage, &epoch_state)?;
+    }
+
+    let prev_balance: Uint256 =
+        query_balance(deps.as_ref(), market_contract.clone(), config.stable_denom)?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: market_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&MarketExecuteMsg::WriteOffBadDebt {
+            borrower: borrower.to_string(),
+            prev_balance,
+        })?,
+    }));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "write_off_bad_debt"),
+        attr("borrower", borrower),
+        attr("bad_debt", bad_debt),
+        attr("buffer_amount", buffer_amount),
+    ]))
+}
+
 pub fn query_collaterals(deps: Deps, borrower: Addr) -> StdResult<CollateralsResponse> {
     let collaterals: Tokens = read_collaterals(
         deps.storage,
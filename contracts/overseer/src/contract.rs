@@ -7,22 +7,24 @@ use cosmwasm_std::{
 
 use crate::collateral::{
     liquidate_collateral, lock_collateral, query_all_collaterals, query_borrow_limit,
-    query_collaterals, unlock_collateral,
+    query_collaterals, unlock_collateral, write_off_bad_debt,
 };
 use crate::error::ContractError;
 use crate::querier::query_epoch_state;
 use crate::state::{
-    read_config, read_epoch_state, read_whitelist, read_whitelist_elem, store_config,
-    store_epoch_state, store_whitelist_elem, Config, EpochState, WhitelistElem,
+    read_config, read_contract_version, read_epoch_state, read_legacy_config, read_whitelist,
+    read_whitelist_elem, store_config, store_contract_version, store_epoch_state,
+    store_whitelist_elem, Config, EpochState, WhitelistElem, CONTRACT_VERSION,
 };
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use moneymarket::common::optional_addr_validate;
+use moneymarket::common::{estimate_block_time, optional_addr_validate, RateUnit};
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::market::EpochStateResponse;
 use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
 use moneymarket::overseer::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, WhitelistResponse, WhitelistResponseElem,
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, WhitelistResponse,
+    WhitelistResponseElem,
 };
 use moneymarket::querier::{deduct_tax, query_balance};
 
@@ -33,6 +35,7 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    let rate_unit = msg.rate_unit.unwrap_or(RateUnit::Block);
     store_config(
         deps.storage,
         &Config {
@@ -48,6 +51,7 @@ pub fn instantiate(
             buffer_distribution_factor: msg.buffer_distribution_factor,
             anc_purchase_factor: msg.anc_purchase_factor,
             price_timeframe: msg.price_timeframe,
+            rate_unit,
         },
     )?;
 
@@ -58,9 +62,10 @@ pub fn instantiate(
             prev_aterra_supply: Uint256::zero(),
             prev_interest_buffer: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
-            last_executed_height: env.block.height,
+            last_executed_height: rate_unit.now(&env.block),
         },
     )?;
+    store_contract_version(deps.storage, CONTRACT_VERSION)?;
 
     Ok(Response::default())
 }
@@ -144,6 +149,10 @@ pub fn execute(
             let api = deps.api;
             liquidate_collateral(deps, env, info, api.addr_validate(&borrower)?)
         }
+        ExecuteMsg::WriteOffBadDebt { borrower } => {
+            let api = deps.api;
+            write_off_bad_debt(deps, env, api.addr_validate(&borrower)?)
+        }
     }
 }
 
@@ -290,27 +299,21 @@ pub fn update_whitelist(
 pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     let state: EpochState = read_epoch_state(deps.storage)?;
-    if env.block.height < state.last_executed_height + config.epoch_period {
+    let current_height = config.rate_unit.now(&env.block);
+    if current_height < state.last_executed_height + config.epoch_period {
         return Err(ContractError::EpochNotPassed(state.last_executed_height));
     }
 
-    // # of blocks from the last executed height
-    let blocks = Uint256::from(env.block.height - state.last_executed_height);
+    // # of blocks (or seconds) from the last executed height
+    let blocks = Uint256::from(current_height - state.last_executed_height);
 
     // Compute next epoch state
     let market_contract = deps.api.addr_humanize(&config.market_contract)?;
-    let epoch_state: EpochStateResponse = query_epoch_state(
-        deps.as_ref(),
-        market_contract.clone(),
-        env.block.height,
-        None,
-    )?;
+    let epoch_state: EpochStateResponse =
+        query_epoch_state(deps.as_ref(), market_contract.clone(), current_height, None)?;
 
-    // effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
-    // deposit_rate = (effective_deposit_rate - 1) / blocks
-    let effective_deposit_rate = epoch_state.exchange_rate / state.prev_exchange_rate;
     let deposit_rate =
-        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
+        compute_deposit_rate(epoch_state.exchange_rate, state.prev_exchange_rate, blocks);
 
     let mut messages: Vec<CosmosMsg> = vec![];
     let mut interest_buffer = query_balance(
@@ -428,30 +431,30 @@ pub fn update_epoch_state(
         return Err(ContractError::Unauthorized {});
     }
 
-    // # of blocks from the last executed height
-    let blocks = Uint256::from(env.block.height - overseer_epoch_state.last_executed_height);
+    // # of blocks (or seconds) from the last executed height
+    let current_height = config.rate_unit.now(&env.block);
+    let blocks = Uint256::from(current_height - overseer_epoch_state.last_executed_height);
 
     // Compute next epoch state
     let market_contract = deps.api.addr_humanize(&config.market_contract)?;
     let market_epoch_state: EpochStateResponse = query_epoch_state(
         deps.as_ref(),
         market_contract.clone(),
-        env.block.height,
+        current_height,
         Some(distributed_interest),
     )?;
 
-    // effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
-    // deposit_rate = (effective_deposit_rate - 1) / blocks
-    let effective_deposit_rate =
-        market_epoch_state.exchange_rate / overseer_epoch_state.prev_exchange_rate;
-    let deposit_rate =
-        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
+    let deposit_rate = compute_deposit_rate(
+        market_epoch_state.exchange_rate,
+        overseer_epoch_state.prev_exchange_rate,
+        blocks,
+    );
 
     // store updated epoch state
     store_epoch_state(
         deps.storage,
         &EpochState {
-            last_executed_height: env.block.height,
+            last_executed_height: current_height,
             prev_aterra_supply: market_epoch_state.aterra_supply,
             prev_exchange_rate: market_epoch_state.exchange_rate,
             prev_interest_buffer: interest_buffer,
@@ -482,6 +485,79 @@ pub fn update_epoch_state(
         ]))
 }
 
+// effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
+// deposit_rate = (effective_deposit_rate - 1) / blocks
+// The exchange rate drops when depositors take a bad debt loss,
+// which is reported as a zero deposit rate
+fn compute_deposit_rate(
+    exchange_rate: Decimal256,
+    prev_exchange_rate: Decimal256,
+    blocks: Uint256,
+) -> Decimal256 {
+    if prev_exchange_rate.is_zero() || exchange_rate <= prev_exchange_rate {
+        return Decimal256::zero();
+    }
+
+    let effective_deposit_rate = exchange_rate / prev_exchange_rate;
+    (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // The config is only rebuilt from the legacy layout
+    // once; later migrations keep every stored value
+    if read_contract_version(deps.storage)?.is_some() {
+        if msg.seconds_per_block.is_some() {
+            return Err(ContractError::RateConversionNotAllowed {});
+        }
+
+        store_contract_version(deps.storage, CONTRACT_VERSION)?;
+        return Ok(Response::new().add_attributes(vec![attr("action", "migrate")]));
+    }
+
+    let legacy_config = read_legacy_config(deps.storage)?;
+    let mut config = Config {
+        owner_addr: legacy_config.owner_addr,
+        oracle_contract: legacy_config.oracle_contract,
+        market_contract: legacy_config.market_contract,
+        liquidation_contract: legacy_config.liquidation_contract,
+        collector_contract: legacy_config.collector_contract,
+        stable_denom: legacy_config.stable_denom,
+        epoch_period: legacy_config.epoch_period,
+        threshold_deposit_rate: legacy_config.threshold_deposit_rate,
+        target_deposit_rate: legacy_config.target_deposit_rate,
+        buffer_distribution_factor: legacy_config.buffer_distribution_factor,
+        anc_purchase_factor: legacy_config.anc_purchase_factor,
+        price_timeframe: legacy_config.price_timeframe,
+        rate_unit: RateUnit::Block,
+    };
+
+    // Convert the per block deposit rates and the epoch
+    // period into per second rates and seconds
+    if let Some(seconds_per_block) = msg.seconds_per_block {
+        if seconds_per_block.is_zero() {
+            return Err(ContractError::InvalidSecondsPerBlock {});
+        }
+
+        config.rate_unit = RateUnit::Second;
+        config.threshold_deposit_rate = config.threshold_deposit_rate / seconds_per_block;
+        config.target_deposit_rate = config.target_deposit_rate / seconds_per_block;
+        let epoch_period: u128 = (Uint256::from(config.epoch_period) * seconds_per_block).into();
+        config.epoch_period = epoch_period as u64;
+
+        let mut state: EpochState = read_epoch_state(deps.storage)?;
+        state.deposit_rate = state.deposit_rate / seconds_per_block;
+        state.last_executed_height =
+            estimate_block_time(&env.block, state.last_executed_height, seconds_per_block);
+        store_epoch_state(deps.storage, &state)?;
+    }
+
+    store_config(deps.storage, &config)?;
+    store_contract_version(deps.storage, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "migrate")]))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -538,6 +614,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         buffer_distribution_factor: config.buffer_distribution_factor,
         anc_purchase_factor: config.anc_purchase_factor,
         price_timeframe: config.price_timeframe,
+        rate_unit: config.rate_unit,
     })
 }
 
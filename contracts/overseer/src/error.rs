@@ -15,9 +15,21 @@ pub enum ContractError {
     #[error("Cannot liquidate safely collateralized loan")]
     CannotLiquidateSafeLoan {},
 
+    #[error("Cannot write off a loan with collaterals left")]
+    CannotWriteOffCollateralizedLoan {},
+
     #[error("An epoch has not passed yet; last executed height: {0}")]
     EpochNotPassed(u64),
 
+    #[error("Seconds per block must be greater than 0")]
+    InvalidSecondsPerBlock {},
+
+    #[error("Rates are only converted when migrating from the legacy version")]
+    RateConversionNotAllowed {},
+
+    #[error("Borrower has no loan to write off")]
+    NoBadDebt {},
+
     #[error("Token is already registered as collateral")]
     TokenAlreadyRegistered {},
 
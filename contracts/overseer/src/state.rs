@@ -5,15 +5,21 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{CanonicalAddr, Deps, Order, StdError, StdResult, Storage};
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 
+use moneymarket::common::RateUnit;
 use moneymarket::overseer::{CollateralsResponse, WhitelistResponseElem};
 use moneymarket::tokens::Tokens;
 
-const KEY_CONFIG: &[u8] = b"config";
+pub const KEY_CONFIG: &[u8] = b"config";
 const KEY_EPOCH_STATE: &[u8] = b"epoch_state";
+pub const KEY_CONTRACT_VERSION: &[u8] = b"contract_version";
 
 const PREFIX_WHITELIST: &[u8] = b"whitelist";
 const PREFIX_COLLATERALS: &[u8] = b"collateral";
 
+/// Version of the storage layout; contracts instantiated
+/// before it was stored have the legacy config
+pub const CONTRACT_VERSION: u64 = 1;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner_addr: CanonicalAddr,
@@ -28,8 +34,28 @@ pub struct Config {
     pub buffer_distribution_factor: Decimal256,
     pub anc_purchase_factor: Decimal256,
     pub price_timeframe: u64,
+    pub rate_unit: RateUnit,
 }
 
+/// Config layout before rate units were introduced,
+/// only used to migrate the stored config
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner_addr: CanonicalAddr,
+    pub oracle_contract: CanonicalAddr,
+    pub market_contract: CanonicalAddr,
+    pub liquidation_contract: CanonicalAddr,
+    pub collector_contract: CanonicalAddr,
+    pub stable_denom: String,
+    pub epoch_period: u64,
+    pub threshold_deposit_rate: Decimal256,
+    pub target_deposit_rate: Decimal256,
+    pub buffer_distribution_factor: Decimal256,
+    pub anc_purchase_factor: Decimal256,
+    pub price_timeframe: u64,
+}
+
+/// last_executed_height is measured in the config rate_unit
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct EpochState {
     pub deposit_rate: Decimal256,
@@ -55,6 +81,18 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
+pub fn read_legacy_config(storage: &dyn Storage) -> StdResult<LegacyConfig> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
+pub fn store_contract_version(storage: &mut dyn Storage, version: u64) -> StdResult<()> {
+    Singleton::new(storage, KEY_CONTRACT_VERSION).save(&version)
+}
+
+pub fn read_contract_version(storage: &dyn Storage) -> StdResult<Option<u64>> {
+    ReadonlySingleton::new(storage, KEY_CONTRACT_VERSION).may_load()
+}
+
 pub fn store_epoch_state(storage: &mut dyn Storage, data: &EpochState) -> StdResult<()> {
     Singleton::new(storage, KEY_EPOCH_STATE).save(data)
 }
@@ -28,6 +28,7 @@ fn proper_compute_borrow_limit() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     // we can just call .unwrap() to assert this was a success
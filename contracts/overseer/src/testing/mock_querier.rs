@@ -259,6 +259,8 @@ impl WasmMockQuerier {
                                 reward_index: Decimal256::zero(),
                                 loan_amount: *v,
                                 pending_rewards: Decimal256::zero(),
+                                fixed_loan_amount: Uint256::zero(),
+                                fixed_rate: Decimal256::zero(),
                             },
                         ))),
                         None => SystemResult::Err(SystemError::InvalidRequest {
@@ -1,7 +1,10 @@
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::error::ContractError;
 use crate::querier::query_epoch_state;
-use crate::state::{read_epoch_state, store_epoch_state, EpochState};
+use crate::state::{
+    read_config, read_epoch_state, store_epoch_state, EpochState, LegacyConfig, KEY_CONFIG,
+    KEY_CONTRACT_VERSION,
+};
 use crate::testing::mock_querier::mock_dependencies;
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
@@ -10,12 +13,14 @@ use cosmwasm_std::{
     attr, from_binary, to_binary, Addr, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, Decimal,
     SubMsg, Uint128, WasmMsg,
 };
+use cosmwasm_storage::Singleton;
 
+use moneymarket::common::RateUnit;
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
 use moneymarket::overseer::{
     AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse, ExecuteMsg,
-    InstantiateMsg, QueryMsg, WhitelistResponse, WhitelistResponseElem,
+    InstantiateMsg, MigrateMsg, QueryMsg, WhitelistResponse, WhitelistResponseElem,
 };
 use moneymarket::querier::deduct_tax;
 
@@ -38,6 +43,7 @@ fn proper_initialization() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -62,6 +68,7 @@ fn proper_initialization() {
             buffer_distribution_factor: Decimal256::percent(20),
             anc_purchase_factor: Decimal256::percent(20),
             price_timeframe: 60u64,
+            rate_unit: RateUnit::Block,
         }
     );
 
@@ -97,6 +104,7 @@ fn update_config() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -196,6 +204,7 @@ fn whitelist() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -342,6 +351,7 @@ fn execute_epoch_operations() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -566,6 +576,7 @@ fn update_epoch_state() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -694,6 +705,62 @@ fn update_epoch_state() {
     )
 }
 
+#[test]
+fn epoch_operations_after_exchange_rate_drop() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(10000000000u128),
+    }]);
+
+    let mut env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        rate_unit: None,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    store_epoch_state(
+        deps.as_mut().storage,
+        &EpochState {
+            last_executed_height: env.block.height,
+            prev_exchange_rate: Decimal256::from_str("1.2").unwrap(),
+            prev_aterra_supply: Uint256::from(1000000u64),
+            prev_interest_buffer: Uint256::from(10000000000u128),
+            deposit_rate: Decimal256::zero(),
+        },
+    )
+    .unwrap();
+
+    // the depositors took a bad debt loss during the epoch
+    deps.querier.with_epoch_state(&[(
+        &"market".to_string(),
+        &(Uint256::from(1000000u64), Decimal256::percent(60)),
+    )]);
+    env.block.height += 86400u64;
+
+    let msg = ExecuteMsg::ExecuteEpochOperations {};
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(res.attributes[1], attr("deposit_rate", "0"));
+
+    let msg = ExecuteMsg::UpdateEpochState {
+        interest_buffer: Uint256::from(10000000000u128),
+        distributed_interest: Uint256::zero(),
+    };
+    let res = execute(deps.as_mut(), env, mock_info(MOCK_CONTRACT_ADDR, &[]), msg).unwrap();
+    assert_eq!(res.attributes[1], attr("deposit_rate", "0"));
+}
+
 #[test]
 fn lock_collateral() {
     let mut deps = mock_dependencies(&[]);
@@ -712,6 +779,7 @@ fn lock_collateral() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -866,6 +934,7 @@ fn unlock_collateral() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -1076,6 +1145,7 @@ fn liquidate_collateral() {
         buffer_distribution_factor: Decimal256::percent(20),
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
+        rate_unit: None,
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -1221,3 +1291,225 @@ fn liquidate_collateral() {
         }
     );
 }
+
+#[test]
+fn migrate_to_seconds() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 100u64,
+        threshold_deposit_rate: Decimal256::permille(6),
+        target_deposit_rate: Decimal256::permille(12),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        rate_unit: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // store the config in the layout before rate units
+    let config = read_config(deps.as_ref().storage).unwrap();
+    Singleton::new(deps.as_mut().storage, KEY_CONFIG)
+        .save(&LegacyConfig {
+            owner_addr: config.owner_addr,
+            oracle_contract: config.oracle_contract,
+            market_contract: config.market_contract,
+            liquidation_contract: config.liquidation_contract,
+            collector_contract: config.collector_contract,
+            stable_denom: config.stable_denom,
+            epoch_period: config.epoch_period,
+            threshold_deposit_rate: config.threshold_deposit_rate,
+            target_deposit_rate: config.target_deposit_rate,
+            buffer_distribution_factor: config.buffer_distribution_factor,
+            anc_purchase_factor: config.anc_purchase_factor,
+            price_timeframe: config.price_timeframe,
+        })
+        .unwrap();
+    Singleton::<u64>::new(deps.as_mut().storage, KEY_CONTRACT_VERSION).remove();
+
+    let mut state = read_epoch_state(deps.as_ref().storage).unwrap();
+    state.deposit_rate = Decimal256::permille(12);
+    state.last_executed_height = mock_env().block.height - 10;
+    store_epoch_state(deps.as_mut().storage, &state).unwrap();
+
+    let res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: Some(Decimal256::zero()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InvalidSecondsPerBlock {});
+
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: Some(Decimal256::from_uint256(6u64)),
+        },
+    )
+    .unwrap();
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&query_res).unwrap();
+    assert_eq!(config_res.rate_unit, RateUnit::Second);
+    assert_eq!(config_res.epoch_period, 600u64);
+    assert_eq!(config_res.threshold_deposit_rate, Decimal256::permille(1));
+    assert_eq!(config_res.target_deposit_rate, Decimal256::permille(2));
+
+    let state = read_epoch_state(deps.as_ref().storage).unwrap();
+    assert_eq!(state.deposit_rate, Decimal256::permille(2));
+    assert_eq!(
+        state.last_executed_height,
+        mock_env().block.time.seconds() - 60
+    );
+
+    // rates are not converted twice
+    let res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: Some(Decimal256::from_uint256(6u64)),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::RateConversionNotAllowed {});
+
+    // a later code upgrade keeps the config and the epoch state
+    let config = read_config(deps.as_ref().storage).unwrap();
+    let _res = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg {
+            seconds_per_block: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(read_config(deps.as_ref().storage).unwrap(), config);
+    assert_eq!(read_epoch_state(deps.as_ref().storage).unwrap(), state);
+    assert_eq!(config.rate_unit, RateUnit::Second);
+}
+
+#[test]
+fn write_off_bad_debt() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(100000u128),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        rate_unit: None,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000u64))],
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let mut epoch_state = read_epoch_state(deps.as_ref().storage).unwrap();
+    epoch_state.prev_interest_buffer = Uint256::from(150000u64);
+    store_epoch_state(deps.as_mut().storage, &epoch_state).unwrap();
+
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(500000u64)),
+        (&"addr0001".to_string(), &Uint256::zero()),
+    ]);
+
+    // collaterals left
+    let info = mock_info("addr0002", &[]);
+    let msg = ExecuteMsg::WriteOffBadDebt {
+        borrower: "addr0000".to_string(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::CannotWriteOffCollateralizedLoan {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::WriteOffBadDebt {
+        borrower: "addr0001".to_string(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::NoBadDebt {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the whole interest buffer covers the bad debt
+    deps.querier
+        .with_loan_amount(&[(&"addr0001".to_string(), &Uint256::from(500000u64))]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "market".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(100000u128),
+                    }
+                )
+                .unwrap()],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::WriteOffBadDebt {
+                    borrower: "addr0001".to_string(),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "write_off_bad_debt"),
+            attr("borrower", "addr0001"),
+            attr("bad_debt", "500000"),
+            attr("buffer_amount", "100000"),
+        ]
+    );
+
+    let epoch_state = read_epoch_state(deps.as_ref().storage).unwrap();
+    assert_eq!(epoch_state.prev_interest_buffer, Uint256::from(50000u64));
+}
@@ -1,4 +1,8 @@
-use cosmwasm_std::{Addr, Api, StdResult};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Addr, Api, BlockInfo, StdResult};
 
 pub fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Option<Addr>> {
     let addr = if let Some(addr) = addr {
@@ -9,3 +13,46 @@ pub fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<
 
     Ok(addr)
 }
+
+pub const SECONDS_PER_YEAR: u64 = 31_536_000u64;
+
+/// Blocks per year at an average block time of
+/// about 6.77 seconds, used to annualise block rates
+pub const BLOCKS_PER_YEAR: u64 = 4_656_810u64;
+
+/// Unit of time that interest, deposit and
+/// emission rates are denominated in
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RateUnit {
+    /// Rates are per block and accrue with block height
+    Block,
+    /// Rates are per second and accrue with block time
+    Second,
+}
+
+impl RateUnit {
+    /// Current point in time measured in this unit;
+    /// block height or block time in seconds
+    pub fn now(&self, block: &BlockInfo) -> u64 {
+        match self {
+            RateUnit::Block => block.height,
+            RateUnit::Second => block.time.seconds(),
+        }
+    }
+
+    /// Number of units in a year
+    pub fn per_year(&self) -> u64 {
+        match self {
+            RateUnit::Block => BLOCKS_PER_YEAR,
+            RateUnit::Second => SECONDS_PER_YEAR,
+        }
+    }
+}
+
+/// Estimate the block time of a past block height
+/// with the given average block time
+pub fn estimate_block_time(block: &BlockInfo, height: u64, seconds_per_block: Decimal256) -> u64 {
+    let elapsed: u128 = (Uint256::from(block.height - height) * seconds_per_block).into();
+    block.time.seconds().saturating_sub(elapsed as u64)
+}
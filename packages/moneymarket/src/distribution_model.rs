@@ -22,6 +22,90 @@ pub enum ExecuteMsg {
         increment_multiplier: Option<Decimal256>,
         decrement_multiplier: Option<Decimal256>,
     },
+    /// Set the ANC emission rate of the market depositors
+    /// bonding aTerra, per market rate unit
+    UpdateDepositorEmissionRate { emission_rate: Decimal256 },
+    /// Follow a predetermined emission schedule instead of
+    /// the deposit rate; unset it to go back to the multipliers
+    UpdateEmissionSchedule { schedule: Option<EmissionSchedule> },
+    /// Drive the emission rate with a proportional-integral
+    /// controller instead of the multipliers; updating it
+    /// resets the integral
+    UpdateEmissionController {
+        controller: Option<EmissionControllerConfig>,
+    },
+
+    ////////////////////
+    /// Market operations
+    ////////////////////
+    /// Accumulate the deposit rate error of the epoch into
+    /// the controller integral, no-op when the controller
+    /// is not configured or a schedule is set.
+    /// `block_height` is measured in the market's rate_unit
+    ExecuteEpochOperations {
+        deposit_rate: Decimal256,
+        target_deposit_rate: Decimal256,
+        threshold_deposit_rate: Decimal256,
+        current_emission_rate: Decimal256,
+        block_height: u64,
+    },
+}
+
+/// Emission rate set by the schedule epoch, counted in
+/// epoch_period seconds from start_time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionSchedule {
+    pub start_time: u64,
+    pub epoch_period: u64,
+    pub kind: ScheduleKind,
+    /// Keep the scheduled rate within
+    /// emission_floor and emission_cap
+    pub clamp: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleKind {
+    /// initial_rate, halved every halving_epochs epochs
+    Halving {
+        initial_rate: Decimal256,
+        halving_epochs: u64,
+    },
+    /// Rate of the last step started; steps are sorted
+    /// by start_epoch and the first one starts at 0
+    Table { steps: Vec<ScheduleStep> },
+}
+
+/// Proportional-integral controller of the emission rate. The error
+/// is the distance of the deposit rate from the midpoint of the
+/// threshold and target deposit rates, relative to the midpoint;
+/// the emission rate moves by proportional_gain * error +
+/// integral_gain * integral, relative to the current rate
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionControllerConfig {
+    /// Market contract allowed to execute epoch operations
+    pub market_contract: String,
+    pub proportional_gain: Decimal256,
+    pub integral_gain: Decimal256,
+    /// Bound of the integral in both directions
+    pub integral_limit: Decimal256,
+    /// Maximum relative emission rate change per epoch
+    pub max_step: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScheduleStep {
+    pub start_epoch: u64,
+    pub emission_rate: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// Average block time used to convert per block emission_cap,
+    /// emission_floor and the scheduled rates to per second ones.
+    /// Nothing is converted when it is not given.
+    /// Only allowed when migrating from the legacy version
+    pub seconds_per_block: Option<Decimal256>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -34,6 +118,15 @@ pub enum QueryMsg {
         threshold_deposit_rate: Decimal256,
         current_emission_rate: Decimal256,
     },
+    DepositorEmissionRate {},
+    /// Scheduled emission rates by epoch, starting
+    /// from the current epoch when start_after is not given
+    EmissionSchedule {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Inputs and terms of the last controller decision
+    EmissionControllerState {},
 }
 
 // We define a custom struct for each query response
@@ -51,3 +144,44 @@ pub struct ConfigResponse {
 pub struct AncEmissionRateResponse {
     pub emission_rate: Decimal256,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionScheduleResponse {
+    pub schedule: EmissionSchedule,
+    pub current_epoch: u64,
+    pub epochs: Vec<ScheduledEmission>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionControllerStateResponse {
+    pub controller: EmissionControllerConfig,
+    /// Midpoint of the threshold and target deposit rates
+    pub mid_rate: Decimal256,
+    pub deposit_rate: Decimal256,
+    /// Relative distance of the deposit rate from mid_rate; the
+    /// `_up` flags tell whether a term raises the emission rate
+    pub error: Decimal256,
+    pub error_up: bool,
+    pub integral: Decimal256,
+    pub integral_up: bool,
+    pub proportional_term: Decimal256,
+    pub integral_term: Decimal256,
+    /// Relative emission rate change, capped by max_step
+    pub step: Decimal256,
+    pub step_up: bool,
+    /// Whether emission_cap or emission_floor limited the rate,
+    /// which holds the integral back
+    pub saturated: bool,
+    pub prev_emission_rate: Decimal256,
+    pub emission_rate: Decimal256,
+    pub last_executed_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScheduledEmission {
+    pub epoch: u64,
+    pub start_time: u64,
+    pub emission_rate: Decimal256,
+}
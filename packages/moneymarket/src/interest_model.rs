@@ -8,8 +8,64 @@ pub struct InstantiateMsg {
     pub owner: String,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    pub curve: Curve,
+    /// Optional controller moving base_rate toward
+    /// a target utilization every epoch
+    pub controller: Option<ControllerConfig>,
 }
 
+/// Shape of the borrow rate over the utilization ratio;
+/// base_rate is added on top of every curve, so the
+/// controller shifts whichever curve is active
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    /// utilization * interest_multiplier
+    Linear {},
+    /// Jump-rate model; above optimal_utilization the
+    /// rate grows with jump_multiplier instead of
+    /// interest_multiplier
+    Kinked {
+        optimal_utilization: Decimal256,
+        jump_multiplier: Decimal256,
+    },
+    /// Rate interpolated between the breakpoints, flat
+    /// below the first one and above the last one
+    PiecewiseLinear { breakpoints: Vec<Breakpoint> },
+    /// interest_multiplier * (e^(steepness * utilization) - 1)
+    /// / (e^steepness - 1), meeting the linear curve at
+    /// zero and full utilization
+    Exponential { steepness: Decimal256 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Breakpoint {
+    pub utilization: Decimal256,
+    pub rate: Decimal256,
+}
+
+/// Adaptive controller of base_rate. Every epoch base_rate is
+/// scaled up when utilization is above the target and down when
+/// it is below, by at most max_step and within the given bounds.
+/// min_base_rate must be greater than 0 for base_rate to recover
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ControllerConfig {
+    /// Market contract allowed to execute epoch operations
+    pub market_contract: String,
+    pub target_utilization: Decimal256,
+    /// Relative base_rate change per unit of normalized
+    /// utilization error
+    pub adjustment_factor: Decimal256,
+    /// Maximum relative base_rate change per epoch
+    pub max_step: Decimal256,
+    pub min_base_rate: Decimal256,
+    pub max_base_rate: Decimal256,
+    /// Minimum number of the market's rate units between
+    /// two base_rate changes
+    pub epoch_period: u64,
+}
+
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
@@ -17,9 +73,34 @@ pub enum ExecuteMsg {
         owner: Option<String>,
         base_rate: Option<Decimal256>,
         interest_multiplier: Option<Decimal256>,
+        /// Switch the active curve
+        curve: Option<Curve>,
+        controller: Option<ControllerConfig>,
+    },
+
+    ////////////////////
+    /// Market operations
+    ////////////////////
+    /// Adjust base_rate toward the target utilization,
+    /// no-op when the controller is not configured.
+    /// `block_height` is measured in the market's rate_unit
+    ExecuteEpochOperations {
+        market_balance: Uint256,
+        total_liabilities: Decimal256,
+        total_reserves: Decimal256,
+        block_height: u64,
     },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// Average block time used to convert per block base_rate
+    /// and interest_multiplier to per second ones.
+    /// Nothing is converted when it is not given.
+    /// Only allowed when migrating from the legacy version
+    pub seconds_per_block: Option<Decimal256>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
@@ -29,6 +110,12 @@ pub enum QueryMsg {
         total_liabilities: Decimal256,
         total_reserves: Decimal256,
     },
+    ControllerState {},
+    /// Borrow rates of the active curve at the given
+    /// utilization ratios, for charts
+    RateCurve {
+        points: Vec<Decimal256>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -37,6 +124,8 @@ pub struct ConfigResponse {
     pub owner: String,
     pub base_rate: Decimal256,
     pub interest_multiplier: Decimal256,
+    pub curve: Curve,
+    pub controller: Option<ControllerConfig>,
 }
 
 // We define a custom struct for each query response
@@ -44,3 +133,30 @@ pub struct ConfigResponse {
 pub struct BorrowRateResponse {
     pub rate: Decimal256,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ControllerStateResponse {
+    pub target_utilization: Decimal256,
+    /// Utilization observed at the last epoch
+    pub utilization: Decimal256,
+    pub base_rate: Decimal256,
+    /// Relative base_rate change applied at the last epoch
+    pub last_step: Decimal256,
+    /// Whether the last step raised or lowered base_rate
+    pub last_step_up: bool,
+    pub last_executed_height: u64,
+    pub epoch_period: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateCurveResponse {
+    pub points: Vec<RateCurvePoint>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateCurvePoint {
+    pub utilization: Decimal256,
+    pub rate: Decimal256,
+}
@@ -1,7 +1,9 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::common::RateUnit;
 use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::Binary;
 use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -17,6 +19,24 @@ pub struct InstantiateMsg {
     pub anc_emission_rate: Decimal256,
     /// Maximum allowed borrow rate over deposited stable balance
     pub max_borrow_factor: Decimal256,
+    /// Unit of time the rates are denominated in, block by default
+    pub rate_unit: Option<RateUnit>,
+    /// Fee rate charged on flash loans,
+    /// flash loans are disabled when it is not given
+    pub flash_loan_fee: Option<Decimal256>,
+    /// Fixed rate borrowing parameters,
+    /// fixed rate loans are disabled when it is not given
+    pub fixed_rate: Option<FixedRateConfig>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct FixedRateConfig {
+    /// Added to the variable borrow rate to get the fixed rate
+    pub premium: Decimal256,
+    /// A fixed rate can be reset once the variable borrow rate
+    /// exceeds it by more than this amount
+    pub rebalance_threshold: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -48,6 +68,50 @@ pub enum ExecuteMsg {
         max_borrow_factor: Option<Decimal256>,
         interest_model: Option<String>,
         distribution_model: Option<String>,
+        flash_loan_fee: Option<Decimal256>,
+        fixed_rate: Option<FixedRateConfig>,
+    },
+
+    /// Set the limit of the total liabilities of the market,
+    /// removed when not given
+    UpdateDebtCeiling {
+        debt_ceiling: Option<Uint256>,
+    },
+
+    /// Set the limit of the loans of a borrower,
+    /// removed when not given
+    UpdateBorrowCap {
+        borrower: String,
+        borrow_cap: Option<Uint256>,
+    },
+
+    /// Update the share of the interest set aside as reserves
+    /// and the split of the reserves swept every epoch
+    UpdateReserveConfig {
+        reserve_factor: Option<Decimal256>,
+        insurance_contract: Option<String>,
+        insurance_share: Option<Decimal256>,
+    },
+
+    /// Set the share of the borrowed amount charged upfront
+    /// and added to the reserves
+    UpdateOriginationFee {
+        origination_fee: Decimal256,
+    },
+
+    /// Set the limits of the ANC emitted over the market
+    /// life and between two epoch operations, removed
+    /// when not given; the rewards stop once one is spent
+    UpdateEmissionBudget {
+        emission_budget: Option<Uint256>,
+        epoch_emission_cap: Option<Uint256>,
+    },
+
+    /// Withdraw reserves, all of them when `amount` is not given,
+    /// to `to` or to the owner
+    WithdrawReserves {
+        amount: Option<Uint256>,
+        to: Option<String>,
     },
 
     ////////////////////
@@ -59,6 +123,14 @@ pub enum ExecuteMsg {
         prev_balance: Uint256,
     },
 
+    /// Write off the loan of a borrower left without collateral;
+    /// the stable received since `prev_balance` covers it first,
+    /// then the reserves and the exchange rate take the rest
+    WriteOffBadDebt {
+        borrower: String,
+        prev_balance: Uint256,
+    },
+
     /// Execute epoch operations
     /// 1. send reserve to collector contract
     /// 2. update anc_emission_rate state
@@ -73,7 +145,10 @@ pub enum ExecuteMsg {
     /// User operations
     ////////////////////
     /// Deposit stable asset to get interest
-    DepositStable {},
+    DepositStable {
+        /// Recipient of the minted aTerra, the sender by default
+        to: Option<String>,
+    },
 
     /// Borrow stable asset with collaterals in overseer contract
     BorrowStable {
@@ -81,23 +156,114 @@ pub enum ExecuteMsg {
         to: Option<String>,
     },
 
-    /// Repay stable asset to decrease liability
-    RepayStable {},
+    /// Borrow stable asset at the current borrow rate plus
+    /// the fixed rate premium for the life of the loan
+    BorrowStableFixed {
+        borrow_amount: Uint256,
+        to: Option<String>,
+    },
+
+    /// Reset the fixed rate of the borrower to the current
+    /// one when the variable rate moved far above it
+    RebalanceFixedRate {
+        borrower: String,
+    },
+
+    /// Repay stable asset to decrease liability of the borrower,
+    /// the sender by default; the variable rate loan is repaid
+    /// first and overpayment is refunded to the sender
+    RepayStable {
+        borrower: Option<String>,
+    },
+
+    /// Withdraw bonded aTerra
+    UnbondATerra {
+        amount: Uint256,
+    },
 
-    /// Claim distributed ANC rewards
+    /// Claim distributed ANC rewards, of both
+    /// the borrowed stable and the bonded aTerra
     ClaimRewards {
         to: Option<String>,
     },
+
+    /// Borrow stable asset within a transaction; the sender
+    /// contract receives FlashLoanReceiverMsg::FlashLoanCallback
+    /// and must send back the amount plus the fee to the market
+    FlashLoan {
+        amount: Uint256,
+        msg: Binary,
+    },
+
+    /// Claim the filled part of a queued redemption
+    ClaimRedemption {
+        id: u64,
+    },
+
+    /// Leave the redemption queue; the unfilled part is minted
+    /// back as aTerra and the filled part is paid out
+    CancelRedemption {
+        id: u64,
+    },
+
+    /// Accrue the interest and rewards up to the current block;
+    /// only the owner can also have the interest model controller
+    /// adjust its base rate, at most once per its epoch period
+    AccrueInterest {
+        update_interest_model: Option<bool>,
+    },
+
+    /// Rebuild the loan size index of the borrowers after
+    /// `start_after` in address order, to index the loans
+    /// stored before the index or refresh the fixed rate
+    /// loans against the current interest index; anyone
+    /// can execute it
+    ReindexLoanSizes {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+/// Message a flash loan receiver contract must handle
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashLoanReceiverMsg {
+    FlashLoanCallback {
+        amount: Uint256,
+        fee: Uint256,
+        msg: Binary,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     /// Return stable coins to a user
-    /// according to exchange rate
-    RedeemStable {},
+    /// according to exchange rate,
+    /// to `to` when given
+    RedeemStable { to: Option<String> },
+    /// Return the given stable amount after tax to a user,
+    /// burning only the aTerra needed and refunding the rest
+    RedeemExactStable { stable_amount: Uint256 },
+    /// Lock aTerra into the redemption queue at the current
+    /// exchange rate, to be filled by later deposits and repays
+    QueueRedemption {},
+    /// Bond aTerra to earn the depositor ANC emissions
+    BondATerra {},
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// Average block time used to convert block based
+    /// rates and indexes to per second ones.
+    /// Rates stay denominated per block when it is not given.
+    /// Only allowed when migrating from the legacy version
+    pub seconds_per_block: Option<Decimal256>,
+}
+
+/// `block_height` fields of the queries are measured in
+/// the market's `rate_unit`; block time in seconds
+/// when the rates are denominated per second
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
@@ -117,6 +283,79 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Borrower infos in address order with the
+    /// interest and rewards accrued up to `block_height`
+    AccruedBorrowerInfos {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        block_height: Option<u64>,
+    },
+    /// Accrued borrower infos ordered by the principal
+    /// of the loans of each borrower, largest first
+    LargestBorrowers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        block_height: Option<u64>,
+    },
+    DepositorInfo {
+        depositor: String,
+        block_height: Option<u64>,
+    },
+    /// Simulations of the user operations at `block_height`, the
+    /// current block by default; failing operations are reported
+    /// in the `error` field
+    SimulateDeposit {
+        deposit_amount: Uint256,
+        block_height: Option<u64>,
+    },
+    SimulateRedeem {
+        burn_amount: Uint256,
+        block_height: Option<u64>,
+    },
+    SimulateRedeemExactStable {
+        stable_amount: Uint256,
+        block_height: Option<u64>,
+    },
+    SimulateBorrow {
+        borrower: String,
+        borrow_amount: Uint256,
+        block_height: Option<u64>,
+    },
+    SimulateRepay {
+        borrower: String,
+        repay_amount: Uint256,
+        block_height: Option<u64>,
+    },
+    RedemptionQueue {},
+    Redemption {
+        id: u64,
+    },
+    BorrowHeadroom {
+        borrower: Option<String>,
+        block_height: Option<u64>,
+    },
+    /// Exchange rate and borrow rate snapshots
+    /// recorded at each epoch operation, oldest first
+    Snapshots {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Annualised deposit and borrow rates realised over
+    /// the last `window` seconds of snapshots
+    Apy {
+        window: u64,
+    },
+    /// Headline numbers of the market at the current block
+    MarketOverview {},
+    /// Written off loans, oldest first
+    BadDebts {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// ANC emitted and claimed against the emission limits
+    EmissionBudget {
+        block_height: Option<u64>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -131,6 +370,14 @@ pub struct ConfigResponse {
     pub distributor_contract: String,
     pub stable_denom: String,
     pub max_borrow_factor: Decimal256,
+    pub rate_unit: RateUnit,
+    pub flash_loan_fee: Option<Decimal256>,
+    pub fixed_rate: Option<FixedRateConfig>,
+    pub debt_ceiling: Option<Uint256>,
+    pub reserve_factor: Decimal256,
+    pub insurance_contract: Option<String>,
+    pub insurance_share: Decimal256,
+    pub origination_fee: Decimal256,
 }
 
 // We define a custom struct for each query response
@@ -145,6 +392,16 @@ pub struct StateResponse {
     pub anc_emission_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
+    pub pending_redemptions: Uint256,
+    pub claimable_redemptions: Uint256,
+    pub total_fixed_liabilities: Decimal256,
+    pub fixed_interest_flow: Decimal256,
+    pub total_bonded_aterra: Uint256,
+    pub depositor_reward_index: Decimal256,
+    pub depositor_emission_rate: Decimal256,
+    pub total_emitted_anc: Decimal256,
+    pub epoch_emitted_anc: Decimal256,
+    pub total_claimed_anc: Uint256,
 }
 
 // We define a custom struct for each query response
@@ -162,6 +419,8 @@ pub struct BorrowerInfoResponse {
     pub reward_index: Decimal256,
     pub loan_amount: Uint256,
     pub pending_rewards: Decimal256,
+    pub fixed_loan_amount: Uint256,
+    pub fixed_rate: Decimal256,
 }
 
 // We define a custom struct for each query response
@@ -169,3 +428,192 @@ pub struct BorrowerInfoResponse {
 pub struct BorrowerInfosResponse {
     pub borrower_infos: Vec<BorrowerInfoResponse>,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateRedeemExactStableResponse {
+    /// aTerra burned for the redemption
+    pub burn_amount: Uint256,
+    /// Stable amount sent by the market before tax
+    pub redeem_amount: Uint256,
+    /// Stable amount received after tax
+    pub stable_amount: Uint256,
+    pub exchange_rate: Decimal256,
+    pub error: Option<String>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateDepositResponse {
+    pub mint_amount: Uint256,
+    pub exchange_rate: Decimal256,
+    pub error: Option<String>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateRedeemResponse {
+    /// Stable sent before tax
+    pub redeem_amount: Uint256,
+    /// Stable received after tax
+    pub received_amount: Uint256,
+    pub exchange_rate: Decimal256,
+    pub error: Option<String>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateBorrowResponse {
+    /// Stable received after the origination fee and tax
+    pub received_amount: Uint256,
+    pub origination_fee: Uint256,
+    /// Variable rate loan amount after the borrow
+    pub loan_amount: Uint256,
+    pub error: Option<String>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SimulateRepayResponse {
+    pub repay_amount: Uint256,
+    /// Excess stable refunded to the payer after tax
+    pub refund_amount: Uint256,
+    /// Loan amounts after the repay
+    pub loan_amount: Uint256,
+    pub fixed_loan_amount: Uint256,
+    pub error: Option<String>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionQueueResponse {
+    /// Oldest redemption not completely filled yet
+    pub head_id: u64,
+    /// Number of redemptions not completely filled yet
+    pub pending_count: u64,
+    pub pending_amount: Uint256,
+    pub claimable_amount: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionResponse {
+    pub id: u64,
+    pub owner: String,
+    pub amount: Uint256,
+    pub filled_amount: Uint256,
+    /// Number of unfilled redemptions ahead in the queue,
+    /// counting the cancelled ones the fills have not passed yet
+    pub position: u64,
+    /// Unfilled stable amount ahead in the queue, counting
+    /// the cancelled redemptions the fills have not passed yet
+    pub amount_ahead: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowHeadroomResponse {
+    pub debt_ceiling: Option<Uint256>,
+    /// Amount which can still be borrowed under the debt ceiling
+    pub debt_ceiling_headroom: Option<Uint256>,
+    pub borrow_cap: Option<Uint256>,
+    /// Amount the borrower can still borrow under its borrow cap
+    pub borrow_cap_headroom: Option<Uint256>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SnapshotResponse {
+    pub id: u64,
+    /// Block height or block time, following the market rate unit
+    pub height: u64,
+    pub time: u64,
+    pub exchange_rate: Decimal256,
+    pub borrow_rate: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SnapshotsResponse {
+    pub snapshots: Vec<SnapshotResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ApyResponse {
+    /// Block time of the first snapshot of the window
+    pub from_time: u64,
+    /// Block time of the latest snapshot
+    pub to_time: u64,
+    pub deposit_apy: Decimal256,
+    pub borrow_apy: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositorInfoResponse {
+    pub depositor: String,
+    pub bonded_amount: Uint256,
+    pub reward_index: Decimal256,
+    pub pending_rewards: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarketOverviewResponse {
+    /// Stable balance of the market
+    pub stable_liquidity: Uint256,
+    /// Total liabilities over total deposits
+    pub utilization: Decimal256,
+    /// Variable borrow rate per `rate_unit`
+    pub borrow_rate: Decimal256,
+    pub borrow_apr: Decimal256,
+    /// Deposit rate per `rate_unit` at the current interest
+    /// flow, after reserves and the target deposit rate
+    pub deposit_rate: Decimal256,
+    pub deposit_apr: Decimal256,
+    pub exchange_rate: Decimal256,
+    pub aterra_supply: Uint256,
+    pub total_reserves: Decimal256,
+    /// Amount that can still be borrowed under the
+    /// max borrow factor and the available liquidity
+    pub borrow_capacity: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtResponse {
+    pub id: u64,
+    pub borrower: String,
+    /// Block height or block time, following the market rate unit
+    pub height: u64,
+    /// Variable and fixed rate loan written off
+    pub amount: Uint256,
+    /// Part covered by the overseer interest buffer
+    pub buffer_amount: Uint256,
+    /// Part covered by the reserves
+    pub reserve_amount: Uint256,
+    /// Part taken from the depositors through the exchange rate
+    pub loss_amount: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtsResponse {
+    pub bad_debts: Vec<BadDebtResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionBudgetResponse {
+    pub emission_budget: Option<Uint256>,
+    pub epoch_emission_cap: Option<Uint256>,
+    /// ANC promised to the borrowers and the bonded aTerra
+    pub total_emitted: Decimal256,
+    /// ANC promised since the last epoch operations
+    pub epoch_emitted: Decimal256,
+    /// ANC spent from the distributor by the claims
+    pub total_claimed: Uint256,
+    pub remaining_budget: Option<Decimal256>,
+    pub remaining_epoch_emission: Option<Decimal256>,
+}
@@ -19,6 +19,16 @@ pub enum ExecuteMsg {
         asset: String,
         feeder: String,
     },
+    /// Owner operation to replace the feeders of the asset;
+    /// the price is the median of the fresh submissions and
+    /// needs at least `quorum` of them. Submissions older than
+    /// `price_timeout` seconds are stale, never without a timeout
+    RegisterFeeders {
+        asset: String,
+        feeders: Vec<String>,
+        quorum: u64,
+        price_timeout: Option<u64>,
+    },
     FeedPrice {
         prices: Vec<(String, Decimal256)>, // (asset, price)
     },
@@ -28,9 +38,17 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    /// Feeder of an asset with a single feeder;
+    /// query `Feeders` for the other assets
     Feeder {
         asset: String,
     },
+    Feeders {
+        asset: String,
+    },
+    Submissions {
+        asset: String,
+    },
     Price {
         base: String,
         quote: String,
@@ -55,6 +73,30 @@ pub struct FeederResponse {
     pub feeder: String,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeedersResponse {
+    pub asset: String,
+    pub feeders: Vec<String>,
+    pub quorum: u64,
+    pub price_timeout: Option<u64>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SubmissionResponse {
+    pub feeder: String,
+    pub price: Decimal256,
+    pub last_updated_time: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SubmissionsResponse {
+    pub asset: String,
+    pub submissions: Vec<SubmissionResponse>,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct PriceResponse {
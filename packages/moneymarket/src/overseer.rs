@@ -1,6 +1,7 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::common::RateUnit;
 use crate::tokens::TokensHuman;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 
@@ -20,7 +21,7 @@ pub struct InstantiateMsg {
     /// The base denomination used when fetching oracle price,
     /// reward distribution, and borrow
     pub stable_denom: String,
-    /// # of blocks per epoch period
+    /// # of blocks (or seconds, see rate_unit) per epoch period
     pub epoch_period: u64,
     /// Distribute interest buffer to market contract,
     /// when deposit_rate < threshold_deposit_rate
@@ -35,6 +36,10 @@ pub struct InstantiateMsg {
     pub anc_purchase_factor: Decimal256,
     /// Valid oracle price timeframe
     pub price_timeframe: u64,
+    /// Unit of time the deposit rates and epoch period
+    /// are denominated in, block by default.
+    /// Must match the rate unit of the market contract
+    pub rate_unit: Option<RateUnit>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -97,6 +102,19 @@ pub enum ExecuteMsg {
     /// Permissionless operations
     /////////////////////////////
     LiquidateCollateral { borrower: String },
+    /// Write off the loan of a borrower left without collateral;
+    /// the interest buffer covers as much of it as it can
+    WriteOffBadDebt { borrower: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {
+    /// Average block time used to convert block based
+    /// deposit rates and epoch period to per second ones.
+    /// Rates stay denominated per block when it is not given.
+    /// Only allowed when migrating from the legacy version
+    pub seconds_per_block: Option<Decimal256>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -137,6 +155,7 @@ pub struct ConfigResponse {
     pub stable_denom: String,
     pub epoch_period: u64,
     pub price_timeframe: u64,
+    pub rate_unit: RateUnit,
 }
 
 // We define a custom struct for each query response
@@ -73,13 +73,20 @@ pub fn compute_tax(deps: Deps, coin: &Coin) -> StdResult<Uint256> {
     let terra_querier = TerraQuerier::new(&deps.querier);
     let tax_rate = Decimal256::from((terra_querier.query_tax_rate()?).rate);
     let tax_cap = Uint256::from((terra_querier.query_tax_cap(coin.denom.to_string())?).cap);
-    let amount = Uint256::from(coin.amount);
-    Ok(std::cmp::min(
-        amount * Decimal256::one() - amount / (Decimal256::one() + tax_rate),
+    Ok(compute_tax_amount(
+        Uint256::from(coin.amount),
+        tax_rate,
         tax_cap,
     ))
 }
 
+fn compute_tax_amount(amount: Uint256, tax_rate: Decimal256, tax_cap: Uint256) -> Uint256 {
+    std::cmp::min(
+        amount * Decimal256::one() - amount / (Decimal256::one() + tax_rate),
+        tax_cap,
+    )
+}
+
 pub fn deduct_tax(deps: Deps, coin: Coin) -> StdResult<Coin> {
     let tax_amount = compute_tax(deps, &coin)?;
     Ok(Coin {
@@ -88,6 +95,30 @@ pub fn deduct_tax(deps: Deps, coin: Coin) -> StdResult<Coin> {
     })
 }
 
+/// Smallest amount to send so that the
+/// receiver gets the given coin after tax
+pub fn compute_amount_with_tax(deps: Deps, coin: &Coin) -> StdResult<Uint256> {
+    let (tax_rate, tax_cap) = query_tax_rate_and_cap(deps, coin.denom.to_string())?;
+    let amount = Uint256::from(coin.amount);
+
+    let mut amount_with_tax = std::cmp::min(
+        amount * (Decimal256::one() + tax_rate) + Uint256::one(),
+        amount + tax_cap,
+    );
+
+    // compute_tax rounds in favor of the tax, so step
+    // back while the receiver still gets the amount
+    while amount_with_tax > amount {
+        let lower = amount_with_tax - Uint256::one();
+        if lower - compute_tax_amount(lower, tax_rate, tax_cap) < amount {
+            break;
+        }
+        amount_with_tax = lower;
+    }
+
+    Ok(amount_with_tax)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TimeConstraints {
     pub block_time: u64,
@@ -1,6 +1,8 @@
 use crate::mock_querier::mock_dependencies;
 use crate::oracle::PriceResponse;
-use crate::querier::{compute_tax, deduct_tax, query_price, query_tax_rate, TimeConstraints};
+use crate::querier::{
+    compute_amount_with_tax, compute_tax, deduct_tax, query_price, query_tax_rate, TimeConstraints,
+};
 use crate::tokens::{Tokens, TokensHuman, TokensMath, TokensToRaw};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
@@ -67,6 +69,38 @@ fn test_deduct_tax() {
     );
 }
 
+#[test]
+fn test_compute_amount_with_tax() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    // the smallest amount which leaves the receiver the coin
+    for amount in [49504950u128, 49504951u128, 99999u128, 10000000000u128] {
+        let amount_with_tax =
+            compute_amount_with_tax(deps.as_ref(), &Coin::new(amount, "uusd")).unwrap();
+        let received = deduct_tax(deps.as_ref(), Coin::new(amount_with_tax.into(), "uusd"))
+            .unwrap()
+            .amount;
+        assert!(received >= Uint128::from(amount));
+
+        let lower = amount_with_tax - Uint256::one();
+        let received = deduct_tax(deps.as_ref(), Coin::new(lower.into(), "uusd"))
+            .unwrap()
+            .amount;
+        assert!(received < Uint128::from(amount));
+    }
+
+    // cap to 1000000
+    assert_eq!(
+        compute_amount_with_tax(deps.as_ref(), &Coin::new(10000000000u128, "uusd")).unwrap(),
+        Uint256::from(10001000000u64)
+    );
+}
+
 #[test]
 fn oracle_price_querier() {
     let mut deps = mock_dependencies(&[]);